@@ -22,4 +22,52 @@ pub enum AmmError {
     InvalidPoolState,
     #[msg("Token mints must be different")]
     IdenticalMints,
+    #[msg("Invalid amplification coefficient")]
+    InvalidAmplification,
+    #[msg("Invalid amplification ramp")]
+    InvalidAmpRamp,
+    #[msg("Curve calculation did not converge")]
+    CurveDidNotConverge,
+    #[msg("Tick index is out of range or not a multiple of the tick spacing")]
+    InvalidTickIndex,
+    #[msg("Square-root price is out of range")]
+    InvalidSqrtPrice,
+    #[msg("Invalid tick spacing")]
+    InvalidTickSpacing,
+    #[msg("Missing or invalid tick array")]
+    InvalidTickArray,
+    #[msg("Position still holds liquidity or uncollected fees")]
+    PositionNotEmpty,
+    #[msg("Route accounts are missing or do not form a connected path")]
+    InvalidRoute,
+    #[msg("Requested time is older than the oldest oracle observation")]
+    ObservationTooOld,
+    #[msg("A flash loan is in progress on this pool")]
+    FlashLoanActive,
+    #[msg("Flash loan is not repaid later in this transaction")]
+    FlashLoanNotRepaid,
+    #[msg("Pool is paused")]
+    PoolPaused,
+    #[msg("Mint's transfer hook program is not on the pool's allowlist")]
+    TransferHookNotAllowed,
+    #[msg("Too many transfer hook programs")]
+    TooManyTransferHookPrograms,
+    #[msg("Transaction deadline has passed")]
+    DeadlineExceeded,
+    #[msg("Not enough LP tokens staked")]
+    InsufficientStake,
+    #[msg("Invalid dynamic fee configuration")]
+    InvalidDynamicFee,
+    #[msg("Token weight is out of range")]
+    InvalidWeight,
+    #[msg("Wrong number of pool tokens or of their accounts")]
+    InvalidTokenCount,
+    #[msg("Fee rate is not one of the allowed fee tiers")]
+    FeeTierNotAllowed,
+    #[msg("Fee tiers must be strictly ascending and at most eight")]
+    InvalidFeeTiers,
+    #[msg("Token A mint must sort before token B mint")]
+    UnsortedMints,
+    #[msg("Fee rate must stay between the fee tiers around the pool's own")]
+    FeeRateOutsideTier,
 }
@@ -0,0 +1,416 @@
+//! Events emitted by pool instructions so indexers can follow activity
+//! without diffing vault balances. Token amounts are what the vaults
+//! actually received or paid out, and reserves exclude uncollected
+//! protocol fees.
+
+use anchor_lang::prelude::*;
+
+use crate::{
+    math::{self, SwapResult},
+    states::{AmmConfig, CurveType, DynamicFeeConfig},
+};
+
+/// Emits `event` as a self-CPI when the `event-cpi` feature is enabled, which
+/// keeps it out of the truncatable program log, and with `emit!` otherwise.
+/// The accounts struct behind `$ctx` must carry `#[event_cpi]` under that
+/// feature.
+macro_rules! emit_event {
+    ($ctx:expr, $event:expr) => {{
+        #[cfg(feature = "event-cpi")]
+        {
+            let ctx = &$ctx;
+            anchor_lang::prelude::emit_cpi!($event);
+        }
+        #[cfg(not(feature = "event-cpi"))]
+        {
+            let _ = &$ctx;
+            anchor_lang::prelude::emit!($event);
+        }
+    }};
+}
+
+pub(crate) use emit_event;
+
+#[event]
+pub struct AmmConfigUpdated {
+    pub admin: Pubkey,
+    pub pending_admin: Option<Pubkey>,
+    pub fee_tiers: Vec<u16>,
+    pub pool_creation_fee: u64,
+    pub fee_receiver: Pubkey,
+}
+
+impl AmmConfigUpdated {
+    pub fn new(config: &AmmConfig) -> Self {
+        Self {
+            admin: config.admin,
+            pending_admin: config.pending_admin,
+            fee_tiers: config.fee_tiers.clone(),
+            pool_creation_fee: config.pool_creation_fee,
+            fee_receiver: config.fee_receiver,
+        }
+    }
+}
+
+#[event]
+pub struct PoolInitialized {
+    pub pool: Pubkey,
+    /// Position of the pool in the registry.
+    pub index: u64,
+    pub authority: Pubkey,
+    pub token_a_mint: Pubkey,
+    pub token_b_mint: Pubkey,
+    pub lp_mint: Pubkey,
+    pub fee_rate: u16,
+    pub protocol_fee_rate: u16,
+    pub curve_type: CurveType,
+    pub amp: u64,
+    pub weight_a: u16,
+    pub weight_b: u16,
+}
+
+#[event]
+pub struct LiquidityAdded {
+    pub pool: Pubkey,
+    pub user: Pubkey,
+    pub amount_a: u64,
+    pub amount_b: u64,
+    pub lp_tokens: u64,
+    pub reserve_a: u64,
+    pub reserve_b: u64,
+    pub lp_supply: u64,
+}
+
+#[event]
+pub struct LiquidityRemoved {
+    pub pool: Pubkey,
+    pub user: Pubkey,
+    pub amount_a: u64,
+    pub amount_b: u64,
+    pub lp_tokens: u64,
+    pub reserve_a: u64,
+    pub reserve_b: u64,
+    pub lp_supply: u64,
+}
+
+#[event]
+pub struct Swapped {
+    pub pool: Pubkey,
+    pub user: Pubkey,
+    pub a_to_b: bool,
+    pub amount_in: u64,
+    pub amount_out: u64,
+    /// Total fee charged on the input, LP and protocol share combined.
+    pub fee: u64,
+    pub protocol_fee: u64,
+    pub reserve_a: u64,
+    pub reserve_b: u64,
+}
+
+impl Swapped {
+    /// Event for `swap` priced against `reserve_in` and `reserve_out`.
+    pub fn new(
+        pool: Pubkey,
+        user: Pubkey,
+        a_to_b: bool,
+        swap: &SwapResult,
+        reserve_in: u64,
+        reserve_out: u64,
+    ) -> Result<Self> {
+        let (reserve_in, reserve_out) = math::reserves_after_swap(swap, reserve_in, reserve_out)?;
+        let (reserve_a, reserve_b) = if a_to_b {
+            (reserve_in, reserve_out)
+        } else {
+            (reserve_out, reserve_in)
+        };
+
+        Ok(Self {
+            pool,
+            user,
+            a_to_b,
+            amount_in: swap.amount_in,
+            amount_out: swap.amount_out,
+            fee: swap.fee,
+            protocol_fee: swap.protocol_fee,
+            reserve_a,
+            reserve_b,
+        })
+    }
+}
+
+#[event]
+pub struct ProtocolFeesCollected {
+    pub pool: Pubkey,
+    pub amount_a: u64,
+    pub amount_b: u64,
+}
+
+/// Reserves are the vault balances once the loan left them, less
+/// uncollected protocol fees.
+#[event]
+pub struct FlashBorrowed {
+    pub pool: Pubkey,
+    pub borrower: Pubkey,
+    pub amount_a: u64,
+    pub amount_b: u64,
+    /// Fees `flash_repay` will charge on each amount.
+    pub fee_a: u64,
+    pub fee_b: u64,
+    pub reserve_a: u64,
+    pub reserve_b: u64,
+}
+
+#[event]
+pub struct FlashRepaid {
+    pub pool: Pubkey,
+    pub borrower: Pubkey,
+    pub amount_a: u64,
+    pub amount_b: u64,
+    pub fee_a: u64,
+    pub fee_b: u64,
+    pub reserve_a: u64,
+    pub reserve_b: u64,
+}
+
+#[event]
+pub struct FeeRateUpdated {
+    pub pool: Pubkey,
+    pub old_fee_rate: u16,
+    pub new_fee_rate: u16,
+}
+
+#[event]
+pub struct DynamicFeeUpdated {
+    pub pool: Pubkey,
+    pub dynamic_fee: Option<DynamicFeeConfig>,
+}
+
+#[event]
+pub struct PausedSet {
+    pub pool: Pubkey,
+    pub paused: bool,
+}
+
+#[event]
+pub struct AmpRampStarted {
+    pub pool: Pubkey,
+    pub amp_initial: u64,
+    pub amp_target: u64,
+    pub ramp_start_ts: i64,
+    pub ramp_stop_ts: i64,
+}
+
+#[event]
+pub struct TransferHookProgramsSet {
+    pub pool: Pubkey,
+    /// Hook programs the pool now accepts; empty accepts any.
+    pub transfer_hook_programs: Vec<Pubkey>,
+}
+
+#[event]
+pub struct AuthorityProposed {
+    pub pool: Pubkey,
+    pub authority: Pubkey,
+    pub pending_authority: Option<Pubkey>,
+}
+
+#[event]
+pub struct AuthorityAccepted {
+    pub pool: Pubkey,
+    pub old_authority: Pubkey,
+    pub new_authority: Pubkey,
+}
+
+#[event]
+pub struct ClPoolInitialized {
+    pub pool: Pubkey,
+    /// Position of the pool in the registry.
+    pub index: u64,
+    pub authority: Pubkey,
+    pub token_a_mint: Pubkey,
+    pub token_b_mint: Pubkey,
+    pub tick_spacing: u16,
+    pub fee_rate: u16,
+    pub sqrt_price_x64: u128,
+    pub tick_current: i32,
+}
+
+#[event]
+pub struct PositionOpened {
+    pub pool: Pubkey,
+    pub position: Pubkey,
+    pub owner: Pubkey,
+    pub tick_lower: i32,
+    pub tick_upper: i32,
+}
+
+#[event]
+pub struct PositionClosed {
+    pub pool: Pubkey,
+    pub position: Pubkey,
+    pub owner: Pubkey,
+}
+
+/// Concentrated liquidity reserves are the vault balances, which include
+/// fees owed to positions.
+#[event]
+pub struct ClLiquidityAdded {
+    pub pool: Pubkey,
+    pub position: Pubkey,
+    pub owner: Pubkey,
+    pub liquidity: u128,
+    pub amount_a: u64,
+    pub amount_b: u64,
+    /// Liquidity active at the current price afterwards.
+    pub pool_liquidity: u128,
+    pub reserve_a: u64,
+    pub reserve_b: u64,
+}
+
+#[event]
+pub struct ClLiquidityRemoved {
+    pub pool: Pubkey,
+    pub position: Pubkey,
+    pub owner: Pubkey,
+    pub liquidity: u128,
+    pub amount_a: u64,
+    pub amount_b: u64,
+    /// Liquidity active at the current price afterwards.
+    pub pool_liquidity: u128,
+    pub reserve_a: u64,
+    pub reserve_b: u64,
+}
+
+#[event]
+pub struct PositionFeesCollected {
+    pub pool: Pubkey,
+    pub position: Pubkey,
+    pub owner: Pubkey,
+    pub amount_a: u64,
+    pub amount_b: u64,
+}
+
+#[event]
+pub struct ClSwapped {
+    pub pool: Pubkey,
+    pub user: Pubkey,
+    pub a_to_b: bool,
+    pub amount_in: u64,
+    pub amount_out: u64,
+    /// Fee charged on the input, all of it accrued to positions in range.
+    pub fee: u64,
+    pub sqrt_price_x64: u128,
+    pub tick_current: i32,
+    pub liquidity: u128,
+    pub reserve_a: u64,
+    pub reserve_b: u64,
+}
+
+#[event]
+pub struct MultiPoolInitialized {
+    pub pool: Pubkey,
+    /// Position of the pool in the registry.
+    pub index: u64,
+    pub authority: Pubkey,
+    pub mints: Vec<Pubkey>,
+    pub lp_mint: Pubkey,
+    pub fee_rate: u16,
+}
+
+#[event]
+pub struct MultiLiquidityAdded {
+    pub pool: Pubkey,
+    pub user: Pubkey,
+    /// Amount each vault received, in the pool's token order.
+    pub amounts: Vec<u64>,
+    pub lp_tokens: u64,
+    /// Swap fee charged on a single-asset deposit, in the deposited token.
+    pub fee: u64,
+    pub lp_supply: u64,
+}
+
+#[event]
+pub struct MultiLiquidityRemoved {
+    pub pool: Pubkey,
+    pub user: Pubkey,
+    /// Amount paid out of each vault, in the pool's token order.
+    pub amounts: Vec<u64>,
+    pub lp_tokens: u64,
+    /// Swap fee kept from a single-asset withdrawal, in the withdrawn token.
+    pub fee: u64,
+    pub lp_supply: u64,
+}
+
+#[event]
+pub struct MultiSwapped {
+    pub pool: Pubkey,
+    pub user: Pubkey,
+    pub mint_in: Pubkey,
+    pub mint_out: Pubkey,
+    pub amount_in: u64,
+    pub amount_out: u64,
+    pub fee: u64,
+    pub reserve_in: u64,
+    pub reserve_out: u64,
+}
+
+#[event]
+pub struct FarmInitialized {
+    pub farm: Pubkey,
+    pub pool: Pubkey,
+    pub lp_mint: Pubkey,
+    pub reward_mint: Pubkey,
+    pub reward_rate: u64,
+}
+
+#[event]
+pub struct FarmFunded {
+    pub farm: Pubkey,
+    pub funder: Pubkey,
+    pub amount: u64,
+    pub rewards_unallocated: u64,
+}
+
+#[event]
+pub struct RewardRateUpdated {
+    pub farm: Pubkey,
+    pub old_reward_rate: u64,
+    pub new_reward_rate: u64,
+}
+
+#[event]
+pub struct FarmPositionOpened {
+    pub farm: Pubkey,
+    pub position: Pubkey,
+    pub owner: Pubkey,
+}
+
+#[event]
+pub struct FarmPositionClosed {
+    pub farm: Pubkey,
+    pub position: Pubkey,
+    pub owner: Pubkey,
+}
+
+#[event]
+pub struct Staked {
+    pub farm: Pubkey,
+    pub user: Pubkey,
+    pub amount: u64,
+    pub total_staked: u64,
+}
+
+#[event]
+pub struct Unstaked {
+    pub farm: Pubkey,
+    pub user: Pubkey,
+    pub amount: u64,
+    pub total_staked: u64,
+}
+
+#[event]
+pub struct RewardsClaimed {
+    pub farm: Pubkey,
+    pub user: Pubkey,
+    pub amount: u64,
+}
@@ -0,0 +1,31 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    errors::AmmError,
+    events::{emit_event, AmmConfigUpdated},
+    states::AmmConfig,
+};
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+pub struct AcceptAmmAdmin<'info> {
+    pub new_admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"amm_config"],
+        bump = amm_config.bump,
+        constraint = amm_config.pending_admin == Some(new_admin.key()) @ AmmError::Unauthorized,
+    )]
+    pub amm_config: Account<'info, AmmConfig>,
+}
+
+pub fn accept_amm_admin(ctx: Context<AcceptAmmAdmin>) -> Result<()> {
+    let config = &mut ctx.accounts.amm_config;
+    config.admin = ctx.accounts.new_admin.key();
+    config.pending_admin = None;
+
+    emit_event!(ctx, AmmConfigUpdated::new(&ctx.accounts.amm_config));
+
+    Ok(())
+}
@@ -0,0 +1,45 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    errors::AmmError,
+    events::{emit_event, AuthorityAccepted},
+    states::Pool,
+};
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+pub struct AcceptAuthority<'info> {
+    pub new_authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"pool",
+            pool.token_a_mint.as_ref(),
+            pool.token_b_mint.as_ref(),
+            &pool.fee_tier.to_le_bytes(),
+        ],
+        bump = pool.bump,
+        constraint = pool.pending_authority == Some(new_authority.key()) @ AmmError::Unauthorized,
+    )]
+    pub pool: Account<'info, Pool>,
+}
+
+pub fn accept_authority(ctx: Context<AcceptAuthority>) -> Result<()> {
+    let pool = &mut ctx.accounts.pool;
+    let old_authority = pool.authority;
+
+    pool.authority = ctx.accounts.new_authority.key();
+    pool.pending_authority = None;
+
+    emit_event!(
+        ctx,
+        AuthorityAccepted {
+            pool: ctx.accounts.pool.key(),
+            old_authority,
+            new_authority: ctx.accounts.new_authority.key(),
+        }
+    );
+
+    Ok(())
+}
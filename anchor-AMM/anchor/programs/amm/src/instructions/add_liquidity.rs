@@ -1,13 +1,21 @@
 use anchor_lang::prelude::*;
 use anchor_spl::{
     associated_token::AssociatedToken,
-    token_interface::{
-        mint_to, Mint, MintTo, TokenAccount, TokenInterface,     },
+    token_interface::{Mint, TokenAccount, TokenInterface},
 };
 
-use crate::{errors::AmmError, instructions::transfer_tokens};
-use crate::states::Pool;
+use crate::states::{Oracle, Pool};
+use crate::{
+    errors::AmmError,
+    events::{emit_event, LiquidityAdded},
+    instructions::{
+        amount_received, amount_to_send, check_deadline, mint_lp_tokens, transfer_hook_program,
+        transfer_tokens,
+    },
+    math::{self, MINIMUM_LIQUIDITY},
+};
 
+#[cfg_attr(feature = "event-cpi", event_cpi)]
 #[derive(Accounts)]
 pub struct AddLiquidity<'info> {
     #[account(mut)]
@@ -15,11 +23,25 @@ pub struct AddLiquidity<'info> {
 
     #[account(
         mut,
-        seeds = [b"pool", pool.token_a_mint.as_ref(), pool.token_b_mint.as_ref()],
+        seeds = [
+            b"pool",
+            pool.token_a_mint.as_ref(),
+            pool.token_b_mint.as_ref(),
+            &pool.fee_tier.to_le_bytes(),
+        ],
         bump = pool.bump,
+        constraint = !pool.flash_loan_active @ AmmError::FlashLoanActive,
+        constraint = !pool.paused @ AmmError::PoolPaused,
     )]
     pub pool: Account<'info, Pool>,
 
+    #[account(
+        mut,
+        seeds = [b"oracle", pool.key().as_ref()],
+        bump = oracle.bump,
+    )]
+    pub oracle: Box<Account<'info, Oracle>>,
+
     #[account(
         mut,
         seeds = [b"lp_mint", pool.key().as_ref()],
@@ -29,56 +51,142 @@ pub struct AddLiquidity<'info> {
 
     #[account(
         mut,
+        address = pool.token_a_vault @ AmmError::InvalidPoolState,
     )]
     pub token_a_vault: InterfaceAccount<'info, TokenAccount>,
 
     #[account(
-        mut
+        mut,
+        address = pool.token_b_vault @ AmmError::InvalidPoolState,
     )]
     pub token_b_vault: InterfaceAccount<'info, TokenAccount>,
 
-    #[account(mut,)]
+    #[account(
+        mut,
+        constraint = user_token_a.mint == token_a_mint.key() @ AmmError::InvalidTokenMint,
+        constraint = user_token_a.owner == user.key() @ AmmError::Unauthorized,
+    )]
     pub user_token_a: InterfaceAccount<'info, TokenAccount>,
 
-    #[account(mut,)]
+    #[account(
+        mut,
+        constraint = user_token_b.mint == token_b_mint.key() @ AmmError::InvalidTokenMint,
+        constraint = user_token_b.owner == user.key() @ AmmError::Unauthorized,
+    )]
     pub user_token_b: InterfaceAccount<'info, TokenAccount>,
 
     #[account(
-        mut
+        mut,
+        constraint = user_lp_token.mint == lp_mint.key() @ AmmError::InvalidTokenMint,
+        constraint = user_lp_token.owner == user.key() @ AmmError::Unauthorized,
     )]
     pub user_lp_token: InterfaceAccount<'info, TokenAccount>,
 
+    /// Pool-owned LP account holding the liquidity locked on the first deposit
+    #[account(
+        mut,
+        associated_token::mint = lp_mint,
+        associated_token::authority = pool,
+        associated_token::token_program = token_program,
+    )]
+    pub locked_lp_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        address = pool.token_a_mint @ AmmError::InvalidTokenMint,
+        mint::token_program = token_a_program,
+        constraint = pool.accepts_transfer_hook(transfer_hook_program(&token_a_mint))
+            @ AmmError::TransferHookNotAllowed,
+    )]
     pub token_a_mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        address = pool.token_b_mint @ AmmError::InvalidTokenMint,
+        mint::token_program = token_b_program,
+        constraint = pool.accepts_transfer_hook(transfer_hook_program(&token_b_mint))
+            @ AmmError::TransferHookNotAllowed,
+    )]
     pub token_b_mint: InterfaceAccount<'info, Mint>,
 
+    pub token_a_program: Interface<'info, TokenInterface>,
+    pub token_b_program: Interface<'info, TokenInterface>,
+
+    /// Token program of the LP mint
     pub token_program: Interface<'info, TokenInterface>,
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub system_program: Program<'info, System>,
 }
 
-pub fn add_liquidity(
-    ctx: Context<AddLiquidity>,
-    amount_a: u64,
-    amount_b: u64,
+pub fn add_liquidity<'info>(
+    ctx: Context<'_, '_, 'info, 'info, AddLiquidity<'info>>,
+    amount_a_desired: u64,
+    amount_b_desired: u64,
+    min_amount_a: u64,
+    min_amount_b: u64,
     min_lp_tokens: u64,
+    deadline: Option<i64>,
 ) -> Result<()> {
+    check_deadline(deadline)?;
+
     let pool = &ctx.accounts.pool;
 
-    require!(amount_a > 0 && amount_b > 0, AmmError::InvalidAmount);
+    require!(
+        amount_a_desired > 0 && amount_b_desired > 0,
+        AmmError::InvalidAmount
+    );
 
-    let reserve_a = ctx.accounts.token_a_vault.amount;
-    let reserve_b = ctx.accounts.token_b_vault.amount;
+    let (reserve_a, reserve_b) = pool.reserves(
+        ctx.accounts.token_a_vault.amount,
+        ctx.accounts.token_b_vault.amount,
+    )?;
+
+    let now = Clock::get()?.unix_timestamp;
+    let (price_a, price_b) = pool.spot_prices_x64(reserve_a, reserve_b);
+    ctx.accounts.oracle.update(now, price_a, price_b);
+
+    // Shares are priced on what the vaults actually receive, net of any transfer fee
+    let desired_a = amount_received(&ctx.accounts.token_a_mint, amount_a_desired)?;
+    let desired_b = amount_received(&ctx.accounts.token_b_mint, amount_b_desired)?;
+
+    let lp_supply = ctx.accounts.lp_mint.supply;
+    let is_first_deposit = lp_supply == 0;
+
+    // The first deposit sets the ratio; later ones only take what matches it
+    let (received_a, received_b) = if is_first_deposit {
+        (desired_a, desired_b)
+    } else {
+        math::optimal_deposit(desired_a, desired_b, reserve_a, reserve_b)?
+    };
+    require!(
+        received_a >= min_amount_a && received_b >= min_amount_b,
+        AmmError::SlippageExceeded
+    );
+
+    // Send the full desired amount for an untouched side, so no fee rounding is lost
+    let amount_a = if received_a == desired_a {
+        amount_a_desired
+    } else {
+        amount_to_send(&ctx.accounts.token_a_mint, received_a)?
+    };
+    let amount_b = if received_b == desired_b {
+        amount_b_desired
+    } else {
+        amount_to_send(&ctx.accounts.token_b_mint, received_b)?
+    };
 
-    let lp_tokens_to_mint = if reserve_a == 0 && reserve_b == 0 {
-        (amount_a.checked_mul(amount_b).unwrap() as f64).sqrt() as u64
+    let lp_tokens_to_mint = if is_first_deposit {
+        // Part of the first deposit is locked forever so the LP supply can never
+        // shrink back to a value small enough to inflate the share price
+        pool.curve(now)
+            .initial_lp_tokens(received_a, received_b)?
+            .checked_sub(MINIMUM_LIQUIDITY)
+            .ok_or(AmmError::InsufficientLiquidity)?
     } else {
-        let lp_supply = ctx.accounts.lp_mint.supply;
-        let lp_from_a = amount_a.checked_mul(lp_supply).unwrap().checked_div(reserve_a).unwrap();
-        let lp_from_b = amount_b.checked_mul(lp_supply).unwrap().checked_div(reserve_b).unwrap();
-        std::cmp::min(lp_from_a, lp_from_b)
+        math::lp_tokens_for_deposit(received_a, received_b, reserve_a, reserve_b, lp_supply)?
     };
 
-    require!(lp_tokens_to_mint >= min_lp_tokens, AmmError::SlippageExceeded);
+    require!(
+        lp_tokens_to_mint >= min_lp_tokens,
+        AmmError::SlippageExceeded
+    );
     require!(lp_tokens_to_mint > 0, AmmError::InvalidAmount);
 
     // Transfer tokens from user to pool vaults
@@ -86,40 +194,71 @@ pub fn add_liquidity(
         &ctx.accounts.user_token_a,
         &ctx.accounts.token_a_vault,
         &ctx.accounts.user,
-        &ctx.accounts.token_program,
+        &ctx.accounts.token_a_program,
         &ctx.accounts.token_a_mint,
+        ctx.remaining_accounts,
         amount_a,
-
     )?;
     transfer_tokens(
         &ctx.accounts.user_token_b,
         &ctx.accounts.token_b_vault,
         &ctx.accounts.user,
-        &ctx.accounts.token_program,
+        &ctx.accounts.token_b_program,
         &ctx.accounts.token_b_mint,
+        ctx.remaining_accounts,
         amount_b,
     )?;
-    
+
     // Mint LP tokens to user
+    let fee_tier_bytes = pool.fee_tier.to_le_bytes();
     let seeds = &[
         b"pool",
         pool.token_a_mint.as_ref(),
         pool.token_b_mint.as_ref(),
+        fee_tier_bytes.as_ref(),
         &[pool.bump],
     ];
     let signer = &[&seeds[..]];
 
-    let cpi_accounts = MintTo {
-        mint: ctx.accounts.lp_mint.to_account_info(),
-        to: ctx.accounts.user_lp_token.to_account_info(),
-        authority: ctx.accounts.pool.to_account_info(),
-    };
-    
-    let cpi_program = ctx.accounts.token_program.to_account_info();
-    let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+    if is_first_deposit {
+        mint_lp_tokens(
+            &ctx.accounts.lp_mint,
+            &ctx.accounts.locked_lp_vault,
+            &pool.to_account_info(),
+            &ctx.accounts.token_program,
+            signer,
+            MINIMUM_LIQUIDITY,
+        )?;
+    }
 
-    mint_to(cpi_ctx, lp_tokens_to_mint)?;
+    mint_lp_tokens(
+        &ctx.accounts.lp_mint,
+        &ctx.accounts.user_lp_token,
+        &pool.to_account_info(),
+        &ctx.accounts.token_program,
+        signer,
+        lp_tokens_to_mint,
+    )?;
+
+    // The token program already rejected any supply overflow
+    let locked = if is_first_deposit {
+        MINIMUM_LIQUIDITY
+    } else {
+        0
+    };
+    emit_event!(
+        ctx,
+        LiquidityAdded {
+            pool: ctx.accounts.pool.key(),
+            user: ctx.accounts.user.key(),
+            amount_a: received_a,
+            amount_b: received_b,
+            lp_tokens: lp_tokens_to_mint,
+            reserve_a: reserve_a + received_a,
+            reserve_b: reserve_b + received_b,
+            lp_supply: lp_supply + locked + lp_tokens_to_mint,
+        }
+    );
 
     Ok(())
 }
-
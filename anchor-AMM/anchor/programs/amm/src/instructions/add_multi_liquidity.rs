@@ -0,0 +1,262 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+
+use crate::{
+    errors::AmmError,
+    events::{emit_event, MultiLiquidityAdded},
+    instructions::{
+        amount_received, amount_to_send, check_deadline, mint_lp_tokens, token_program_for,
+        transfer_tokens,
+    },
+    math::{multi_asset, MINIMUM_LIQUIDITY},
+    states::MultiPool,
+};
+
+/// Accounts passed in `remaining_accounts` for every constituent a multi-pool
+/// deposit or withdrawal touches, in the pool's token order: vault, mint,
+/// user token account.
+pub const ACCOUNTS_PER_CONSTITUENT: usize = 3;
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+pub struct AddMultiLiquidity<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        seeds = [b"multi_pool", pool.mints_hash.as_ref()],
+        bump = pool.bump,
+        constraint = !pool.paused @ AmmError::PoolPaused,
+    )]
+    pub pool: Account<'info, MultiPool>,
+
+    #[account(
+        mut,
+        seeds = [b"lp_mint", pool.key().as_ref()],
+        bump = pool.lp_mint_bump,
+    )]
+    pub lp_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        constraint = user_lp_token.mint == lp_mint.key() @ AmmError::InvalidTokenMint,
+        constraint = user_lp_token.owner == user.key() @ AmmError::Unauthorized,
+    )]
+    pub user_lp_token: InterfaceAccount<'info, TokenAccount>,
+
+    /// Pool-owned LP account holding the liquidity locked on the first deposit
+    #[account(
+        mut,
+        associated_token::mint = lp_mint,
+        associated_token::authority = pool,
+        associated_token::token_program = token_program,
+    )]
+    pub locked_lp_vault: InterfaceAccount<'info, TokenAccount>,
+
+    /// SPL Token program, which also owns the LP mint
+    #[account(address = anchor_spl::token::ID)]
+    pub token_program: Interface<'info, TokenInterface>,
+
+    /// Token-2022 program, used for every constituent mint it owns
+    #[account(address = anchor_spl::token_2022::ID)]
+    pub token_2022_program: Interface<'info, TokenInterface>,
+}
+
+/// One of a multi-pool's tokens, with the user's account for it.
+pub struct Constituent<'info> {
+    pub index: usize,
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    pub user_token: InterfaceAccount<'info, TokenAccount>,
+}
+
+impl<'info> Constituent<'info> {
+    /// Loads the constituent whose accounts are `accounts` and checks them
+    /// against `pool` and `user`.
+    pub fn load(
+        pool: &MultiPool,
+        user: &Pubkey,
+        accounts: &'info [AccountInfo<'info>],
+    ) -> Result<Self> {
+        let vault = InterfaceAccount::<TokenAccount>::try_from(&accounts[0])?;
+        let mint = InterfaceAccount::<Mint>::try_from(&accounts[1])?;
+        let user_token = InterfaceAccount::<TokenAccount>::try_from(&accounts[2])?;
+
+        let index = pool.token_index(&mint.key())?;
+        require_keys_eq!(
+            vault.key(),
+            pool.tokens[index].vault,
+            AmmError::InvalidPoolState
+        );
+        require_keys_eq!(user_token.mint, mint.key(), AmmError::InvalidTokenMint);
+        require_keys_eq!(user_token.owner, *user, AmmError::Unauthorized);
+
+        Ok(Self {
+            index,
+            vault,
+            mint,
+            user_token,
+        })
+    }
+
+    /// Loads every constituent of `pool`, which `accounts` must list in the
+    /// pool's token order.
+    pub fn load_all(
+        pool: &MultiPool,
+        user: &Pubkey,
+        accounts: &'info [AccountInfo<'info>],
+    ) -> Result<Vec<Self>> {
+        require!(
+            accounts.len() == pool.tokens.len() * ACCOUNTS_PER_CONSTITUENT,
+            AmmError::InvalidTokenCount
+        );
+
+        let constituents = accounts
+            .chunks_exact(ACCOUNTS_PER_CONSTITUENT)
+            .map(|accounts| Self::load(pool, user, accounts))
+            .collect::<Result<Vec<_>>>()?;
+        for (i, constituent) in constituents.iter().enumerate() {
+            require!(constituent.index == i, AmmError::InvalidTokenMint);
+        }
+        Ok(constituents)
+    }
+
+    /// Loads the single constituent a single-asset deposit or withdrawal uses.
+    pub fn load_one(
+        pool: &MultiPool,
+        user: &Pubkey,
+        accounts: &'info [AccountInfo<'info>],
+    ) -> Result<Self> {
+        require!(
+            accounts.len() == ACCOUNTS_PER_CONSTITUENT,
+            AmmError::InvalidTokenCount
+        );
+        Self::load(pool, user, accounts)
+    }
+}
+
+/// Deposits every token of a multi-asset pool in proportion to its reserves.
+/// `max_amounts` are in the pool's token order; the first deposit takes them
+/// all and sets the pool's ratio.
+pub fn add_multi_liquidity<'info>(
+    ctx: Context<'_, '_, 'info, 'info, AddMultiLiquidity<'info>>,
+    max_amounts: Vec<u64>,
+    min_lp_tokens: u64,
+    deadline: Option<i64>,
+) -> Result<()> {
+    check_deadline(deadline)?;
+
+    let pool = &ctx.accounts.pool;
+    let user = ctx.accounts.user.key();
+    let constituents = Constituent::load_all(pool, &user, ctx.remaining_accounts)?;
+
+    require!(
+        max_amounts.len() == constituents.len(),
+        AmmError::InvalidTokenCount
+    );
+    require!(
+        max_amounts.iter().all(|amount| *amount > 0),
+        AmmError::InvalidAmount
+    );
+
+    // Shares are priced on what the vaults actually receive, net of any transfer fee
+    let reserves: Vec<u64> = constituents
+        .iter()
+        .map(|token| token.vault.amount)
+        .collect();
+    let desired = constituents
+        .iter()
+        .zip(&max_amounts)
+        .map(|(token, amount)| amount_received(&token.mint, *amount))
+        .collect::<Result<Vec<_>>>()?;
+
+    let lp_supply = ctx.accounts.lp_mint.supply;
+    let is_first_deposit = lp_supply == 0;
+
+    let (received, lp_tokens_to_mint) = if is_first_deposit {
+        // Part of the first deposit is locked forever so the LP supply can never
+        // shrink back to a value small enough to inflate the share price
+        let lp_tokens = multi_asset::initial_lp_tokens(&desired)?
+            .checked_sub(MINIMUM_LIQUIDITY)
+            .ok_or(AmmError::InsufficientLiquidity)?;
+        (desired.clone(), lp_tokens)
+    } else {
+        let lp_tokens = multi_asset::lp_tokens_for_deposit(&desired, &reserves, lp_supply)?;
+        (
+            multi_asset::deposit_amounts(lp_tokens, &reserves, lp_supply)?,
+            lp_tokens,
+        )
+    };
+
+    require!(
+        lp_tokens_to_mint >= min_lp_tokens,
+        AmmError::SlippageExceeded
+    );
+    require!(lp_tokens_to_mint > 0, AmmError::InvalidAmount);
+
+    for (i, token) in constituents.iter().enumerate() {
+        // Send the full amount for an untouched token, so no fee rounding is lost
+        let amount = if received[i] == desired[i] {
+            max_amounts[i]
+        } else {
+            amount_to_send(&token.mint, received[i])?
+        };
+
+        transfer_tokens(
+            &token.user_token,
+            &token.vault,
+            &ctx.accounts.user,
+            token_program_for(
+                &token.mint,
+                &ctx.accounts.token_program,
+                &ctx.accounts.token_2022_program,
+            ),
+            &token.mint,
+            &[],
+            amount,
+        )?;
+    }
+
+    let seeds = &[b"multi_pool", pool.mints_hash.as_ref(), &[pool.bump]];
+    let signer = &[&seeds[..]];
+
+    if is_first_deposit {
+        mint_lp_tokens(
+            &ctx.accounts.lp_mint,
+            &ctx.accounts.locked_lp_vault,
+            &pool.to_account_info(),
+            &ctx.accounts.token_program,
+            signer,
+            MINIMUM_LIQUIDITY,
+        )?;
+    }
+
+    mint_lp_tokens(
+        &ctx.accounts.lp_mint,
+        &ctx.accounts.user_lp_token,
+        &pool.to_account_info(),
+        &ctx.accounts.token_program,
+        signer,
+        lp_tokens_to_mint,
+    )?;
+
+    // The token program already rejected any supply overflow
+    let locked = if is_first_deposit {
+        MINIMUM_LIQUIDITY
+    } else {
+        0
+    };
+    emit_event!(
+        ctx,
+        MultiLiquidityAdded {
+            pool: ctx.accounts.pool.key(),
+            user,
+            amounts: received,
+            lp_tokens: lp_tokens_to_mint,
+            fee: 0,
+            lp_supply: lp_supply + locked + lp_tokens_to_mint,
+        }
+    );
+
+    Ok(())
+}
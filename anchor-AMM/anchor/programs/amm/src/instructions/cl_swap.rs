@@ -0,0 +1,369 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+
+use crate::{
+    errors::AmmError,
+    events::{emit_event, ClSwapped},
+    instructions::{
+        amount_received, amount_to_send, check_deadline, transfer_hook_program, transfer_tokens,
+        transfer_tokens_from_vault,
+    },
+    math::{
+        concentrated_liquidity,
+        tick_math::{self, MAX_SQRT_PRICE_X64, MAX_TICK, MIN_SQRT_PRICE_X64, MIN_TICK},
+    },
+    states::{ClPool, TickArray, TICK_ARRAY_SIZE},
+};
+
+/// Swap against a concentrated liquidity pool. The tick arrays the swap may
+/// cross are passed as writable remaining accounts, starting with the one
+/// that holds the current tick and continuing in the swap direction.
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+pub struct ClSwap<'info> {
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"cl_pool",
+            cl_pool.token_a_mint.as_ref(),
+            cl_pool.token_b_mint.as_ref(),
+            &cl_pool.fee_rate.to_le_bytes(),
+        ],
+        bump = cl_pool.bump,
+    )]
+    pub cl_pool: Account<'info, ClPool>,
+
+    #[account(
+        mut,
+        address = cl_pool.token_a_vault @ AmmError::InvalidPoolState,
+    )]
+    pub token_a_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        address = cl_pool.token_b_vault @ AmmError::InvalidPoolState,
+    )]
+    pub token_b_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = user_token_a.mint == token_a_mint.key() @ AmmError::InvalidTokenMint,
+        constraint = user_token_a.owner == user.key() @ AmmError::Unauthorized,
+    )]
+    pub user_token_a: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = user_token_b.mint == token_b_mint.key() @ AmmError::InvalidTokenMint,
+        constraint = user_token_b.owner == user.key() @ AmmError::Unauthorized,
+    )]
+    pub user_token_b: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        address = cl_pool.token_a_mint @ AmmError::InvalidTokenMint,
+        mint::token_program = token_a_program,
+        constraint = cl_pool.accepts_transfer_hook(transfer_hook_program(&token_a_mint))
+            @ AmmError::TransferHookNotAllowed,
+    )]
+    pub token_a_mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        address = cl_pool.token_b_mint @ AmmError::InvalidTokenMint,
+        mint::token_program = token_b_program,
+        constraint = cl_pool.accepts_transfer_hook(transfer_hook_program(&token_b_mint))
+            @ AmmError::TransferHookNotAllowed,
+    )]
+    pub token_b_mint: InterfaceAccount<'info, Mint>,
+
+    pub token_a_program: Interface<'info, TokenInterface>,
+    pub token_b_program: Interface<'info, TokenInterface>,
+}
+
+/// Loads the first `tick_array_count` accounts of `remaining_accounts` as
+/// tick arrays and checks that they form a contiguous run from the current
+/// tick in the swap direction.
+fn load_tick_arrays<'info>(
+    remaining_accounts: &'info [AccountInfo<'info>],
+    tick_array_count: usize,
+    cl_pool_key: Pubkey,
+    tick_current: i32,
+    tick_spacing: u16,
+    a_to_b: bool,
+) -> Result<Vec<Account<'info, TickArray>>> {
+    // Tick arrays lead `remaining_accounts`; transfer hook accounts may follow
+    require!(
+        tick_array_count <= remaining_accounts.len(),
+        AmmError::InvalidTickArray
+    );
+    let tick_arrays = remaining_accounts[..tick_array_count]
+        .iter()
+        .map(|info| {
+            require!(info.is_writable, AmmError::InvalidTickArray);
+            let tick_array = Account::<TickArray>::try_from(info)?;
+            require_keys_eq!(tick_array.pool, cl_pool_key, AmmError::InvalidTickArray);
+            Ok(tick_array)
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    require!(
+        tick_arrays
+            .first()
+            .is_some_and(|first| first.contains(tick_current, tick_spacing)),
+        AmmError::InvalidTickArray
+    );
+
+    let span = tick_spacing as i32 * TICK_ARRAY_SIZE as i32;
+    let step = if a_to_b { -span } else { span };
+    for pair in tick_arrays.windows(2) {
+        require!(
+            pair[1].start_tick_index == pair[0].start_tick_index + step,
+            AmmError::InvalidTickArray
+        );
+    }
+
+    Ok(tick_arrays)
+}
+
+pub fn cl_swap<'info>(
+    ctx: Context<'_, '_, 'info, 'info, ClSwap<'info>>,
+    amount_in: u64,
+    min_amount_out: u64,
+    sqrt_price_limit_x64: u128,
+    a_to_b: bool,
+    tick_array_count: u8,
+    deadline: Option<i64>,
+) -> Result<()> {
+    check_deadline(deadline)?;
+    require!(amount_in > 0, AmmError::InvalidAmount);
+
+    let (mint_in, mint_out) = if a_to_b {
+        (&ctx.accounts.token_a_mint, &ctx.accounts.token_b_mint)
+    } else {
+        (&ctx.accounts.token_b_mint, &ctx.accounts.token_a_mint)
+    };
+    // The swap runs on what the vault actually receives, net of any transfer fee
+    let net_amount_in = amount_received(mint_in, amount_in)?;
+
+    let cl_pool_key = ctx.accounts.cl_pool.key();
+    let pool = &mut ctx.accounts.cl_pool;
+    let tick_spacing = pool.tick_spacing;
+
+    // Zero means no limit beyond the price bounds
+    let sqrt_price_limit = match (sqrt_price_limit_x64, a_to_b) {
+        (0, true) => MIN_SQRT_PRICE_X64 + 1,
+        (0, false) => MAX_SQRT_PRICE_X64 - 1,
+        (limit, _) => limit,
+    };
+    require!(
+        sqrt_price_limit > MIN_SQRT_PRICE_X64 && sqrt_price_limit < MAX_SQRT_PRICE_X64,
+        AmmError::InvalidSqrtPrice
+    );
+    if a_to_b {
+        require!(
+            sqrt_price_limit < pool.sqrt_price_x64,
+            AmmError::InvalidSqrtPrice
+        );
+    } else {
+        require!(
+            sqrt_price_limit > pool.sqrt_price_x64,
+            AmmError::InvalidSqrtPrice
+        );
+    }
+
+    let mut tick_arrays = load_tick_arrays(
+        ctx.remaining_accounts,
+        tick_array_count as usize,
+        cl_pool_key,
+        pool.tick_current,
+        tick_spacing,
+        a_to_b,
+    )?;
+
+    let mut amount_remaining = net_amount_in;
+    let mut amount_out: u64 = 0;
+    let mut fee: u64 = 0;
+    let mut sqrt_price = pool.sqrt_price_x64;
+    let mut tick_current = pool.tick_current;
+    let mut liquidity = pool.liquidity;
+    let mut fee_growth_global_a = pool.fee_growth_global_a_x64;
+    let mut fee_growth_global_b = pool.fee_growth_global_b_x64;
+    let mut array_index = 0;
+
+    while amount_remaining > 0 && sqrt_price != sqrt_price_limit {
+        // Next initialized tick in the swap direction, or the edge of the
+        // current array when it has none left
+        let (tick_next, initialized) = loop {
+            let tick_array = &tick_arrays[array_index];
+            let passed_array = if a_to_b {
+                tick_current < tick_array.start_tick_index
+            } else {
+                tick_current >= tick_array.last_tick_index(tick_spacing)
+            };
+            if passed_array {
+                array_index += 1;
+                require!(array_index < tick_arrays.len(), AmmError::InvalidTickArray);
+                continue;
+            }
+
+            break match tick_array.next_initialized_tick(tick_current, tick_spacing, a_to_b) {
+                Some(tick) => (tick, true),
+                None if a_to_b => (tick_array.start_tick_index, false),
+                None => (tick_array.last_tick_index(tick_spacing), false),
+            };
+        };
+        let tick_next = tick_next.clamp(MIN_TICK, MAX_TICK);
+        let sqrt_price_next = tick_math::sqrt_price_at_tick(tick_next)?;
+
+        let sqrt_price_target = if a_to_b {
+            sqrt_price_next.max(sqrt_price_limit)
+        } else {
+            sqrt_price_next.min(sqrt_price_limit)
+        };
+
+        let step = concentrated_liquidity::compute_swap_step(
+            sqrt_price,
+            sqrt_price_target,
+            liquidity,
+            amount_remaining,
+            pool.fee_rate,
+        )?;
+
+        amount_remaining -= step.amount_in + step.fee_amount;
+        fee += step.fee_amount;
+        amount_out = amount_out
+            .checked_add(step.amount_out)
+            .ok_or(AmmError::MathOverflow)?;
+
+        let fee_growth = concentrated_liquidity::fee_growth_delta(step.fee_amount, liquidity);
+        if a_to_b {
+            fee_growth_global_a = fee_growth_global_a.wrapping_add(fee_growth);
+        } else {
+            fee_growth_global_b = fee_growth_global_b.wrapping_add(fee_growth);
+        }
+
+        if step.sqrt_price_next == sqrt_price_next {
+            if initialized {
+                let liquidity_net = tick_arrays[array_index]
+                    .tick_mut(tick_next, tick_spacing)?
+                    .cross(fee_growth_global_a, fee_growth_global_b);
+                let liquidity_delta = if a_to_b {
+                    liquidity_net.checked_neg().ok_or(AmmError::MathOverflow)?
+                } else {
+                    liquidity_net
+                };
+                liquidity =
+                    concentrated_liquidity::add_liquidity_delta(liquidity, liquidity_delta)?;
+            }
+            tick_current = if a_to_b { tick_next - 1 } else { tick_next };
+        } else if step.sqrt_price_next != sqrt_price {
+            tick_current = tick_math::tick_at_sqrt_price(step.sqrt_price_next)?;
+        }
+        sqrt_price = step.sqrt_price_next;
+    }
+
+    // A swap stopped by the price limit only charges what the vault needs to net
+    let amount_in = if amount_remaining == 0 {
+        amount_in
+    } else {
+        amount_to_send(mint_in, net_amount_in - amount_remaining)?
+    };
+    require!(
+        amount_received(mint_out, amount_out)? >= min_amount_out,
+        AmmError::SlippageExceeded
+    );
+
+    pool.sqrt_price_x64 = sqrt_price;
+    pool.tick_current = tick_current;
+    pool.liquidity = liquidity;
+    pool.fee_growth_global_a_x64 = fee_growth_global_a;
+    pool.fee_growth_global_b_x64 = fee_growth_global_b;
+
+    for tick_array in tick_arrays.iter_mut() {
+        tick_array.exit(&crate::ID)?;
+    }
+
+    let cl_pool = &ctx.accounts.cl_pool;
+    let fee_rate_bytes = cl_pool.fee_rate.to_le_bytes();
+    let seeds = &[
+        b"cl_pool",
+        cl_pool.token_a_mint.as_ref(),
+        cl_pool.token_b_mint.as_ref(),
+        fee_rate_bytes.as_ref(),
+        &[cl_pool.bump],
+    ];
+    let signer = &[&seeds[..]];
+
+    let side_a = (
+        &ctx.accounts.user_token_a,
+        &ctx.accounts.token_a_vault,
+        &ctx.accounts.token_a_mint,
+        &ctx.accounts.token_a_program,
+    );
+    let side_b = (
+        &ctx.accounts.user_token_b,
+        &ctx.accounts.token_b_vault,
+        &ctx.accounts.token_b_mint,
+        &ctx.accounts.token_b_program,
+    );
+    let (side_in, side_out) = if a_to_b {
+        (side_a, side_b)
+    } else {
+        (side_b, side_a)
+    };
+    let (user_source, vault_in, mint_in, program_in) = side_in;
+    let (user_destination, vault_out, mint_out, program_out) = side_out;
+
+    transfer_tokens(
+        user_source,
+        vault_in,
+        &ctx.accounts.user,
+        program_in,
+        mint_in,
+        ctx.remaining_accounts,
+        amount_in,
+    )?;
+
+    if amount_out > 0 {
+        transfer_tokens_from_vault(
+            vault_out,
+            user_destination,
+            &cl_pool.to_account_info(),
+            program_out,
+            mint_out,
+            ctx.remaining_accounts,
+            signer,
+            amount_out,
+        )?;
+    }
+
+    let received = amount_received(mint_in, amount_in)?;
+    let reserve_in = vault_in
+        .amount
+        .checked_add(received)
+        .ok_or(AmmError::MathOverflow)?;
+    let reserve_out = vault_out.amount - amount_out;
+    let (reserve_a, reserve_b) = if a_to_b {
+        (reserve_in, reserve_out)
+    } else {
+        (reserve_out, reserve_in)
+    };
+    emit_event!(
+        ctx,
+        ClSwapped {
+            pool: cl_pool.key(),
+            user: ctx.accounts.user.key(),
+            a_to_b,
+            amount_in: received,
+            amount_out,
+            fee,
+            sqrt_price_x64: cl_pool.sqrt_price_x64,
+            tick_current: cl_pool.tick_current,
+            liquidity: cl_pool.liquidity,
+            reserve_a,
+            reserve_b,
+        }
+    );
+
+    Ok(())
+}
@@ -0,0 +1,94 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+
+use crate::{
+    errors::AmmError,
+    events::{emit_event, RewardsClaimed},
+    instructions::transfer_tokens_from_vault,
+    states::{Farm, FarmPosition},
+};
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+pub struct ClaimFarmRewards<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"farm", farm.pool.as_ref(), farm.reward_mint.as_ref()],
+        bump = farm.bump,
+    )]
+    pub farm: Account<'info, Farm>,
+
+    #[account(
+        mut,
+        has_one = owner @ AmmError::Unauthorized,
+        has_one = farm @ AmmError::InvalidPoolState,
+    )]
+    pub position: Account<'info, FarmPosition>,
+
+    #[account(
+        address = farm.reward_mint @ AmmError::InvalidTokenMint,
+        mint::token_program = reward_token_program,
+    )]
+    pub reward_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        address = farm.reward_vault @ AmmError::InvalidPoolState,
+    )]
+    pub reward_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = user_reward_token.mint == reward_mint.key() @ AmmError::InvalidTokenMint,
+        constraint = user_reward_token.owner == owner.key() @ AmmError::Unauthorized,
+    )]
+    pub user_reward_token: InterfaceAccount<'info, TokenAccount>,
+
+    pub reward_token_program: Interface<'info, TokenInterface>,
+}
+
+pub fn claim_farm_rewards<'info>(
+    ctx: Context<'_, '_, 'info, 'info, ClaimFarmRewards<'info>>,
+) -> Result<()> {
+    ctx.accounts.farm.update(Clock::get()?.unix_timestamp);
+    let reward_per_share_x64 = ctx.accounts.farm.reward_per_share_x64;
+    ctx.accounts.position.settle_rewards(reward_per_share_x64)?;
+
+    let amount = ctx.accounts.position.rewards_owed;
+    require!(amount > 0, AmmError::InvalidAmount);
+
+    let farm = &ctx.accounts.farm;
+    let seeds = &[
+        b"farm",
+        farm.pool.as_ref(),
+        farm.reward_mint.as_ref(),
+        &[farm.bump],
+    ];
+    let signer = &[&seeds[..]];
+
+    transfer_tokens_from_vault(
+        &ctx.accounts.reward_vault,
+        &ctx.accounts.user_reward_token,
+        &farm.to_account_info(),
+        &ctx.accounts.reward_token_program,
+        &ctx.accounts.reward_mint,
+        ctx.remaining_accounts,
+        signer,
+        amount,
+    )?;
+
+    ctx.accounts.position.rewards_owed = 0;
+
+    emit_event!(
+        ctx,
+        RewardsClaimed {
+            farm: ctx.accounts.farm.key(),
+            user: ctx.accounts.owner.key(),
+            amount,
+        }
+    );
+
+    Ok(())
+}
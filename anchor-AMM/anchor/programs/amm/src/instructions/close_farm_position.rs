@@ -0,0 +1,41 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    errors::AmmError,
+    events::{emit_event, FarmPositionClosed},
+    states::FarmPosition,
+};
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+pub struct CloseFarmPosition<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        close = owner,
+        has_one = owner @ AmmError::Unauthorized,
+    )]
+    pub position: Account<'info, FarmPosition>,
+}
+
+pub fn close_farm_position(ctx: Context<CloseFarmPosition>) -> Result<()> {
+    let position = &ctx.accounts.position;
+
+    require!(
+        position.amount == 0 && position.rewards_owed == 0,
+        AmmError::PositionNotEmpty
+    );
+
+    emit_event!(
+        ctx,
+        FarmPositionClosed {
+            farm: position.farm,
+            position: position.key(),
+            owner: position.owner,
+        }
+    );
+
+    Ok(())
+}
@@ -0,0 +1,41 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    errors::AmmError,
+    events::{emit_event, PositionClosed},
+    states::Position,
+};
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+pub struct ClosePosition<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        close = owner,
+        has_one = owner @ AmmError::Unauthorized,
+    )]
+    pub position: Account<'info, Position>,
+}
+
+pub fn close_position(ctx: Context<ClosePosition>) -> Result<()> {
+    let position = &ctx.accounts.position;
+
+    require!(
+        position.liquidity == 0 && position.tokens_owed_a == 0 && position.tokens_owed_b == 0,
+        AmmError::PositionNotEmpty
+    );
+
+    emit_event!(
+        ctx,
+        PositionClosed {
+            pool: position.pool,
+            position: position.key(),
+            owner: position.owner,
+        }
+    );
+
+    Ok(())
+}
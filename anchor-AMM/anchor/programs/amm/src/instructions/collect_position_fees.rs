@@ -0,0 +1,71 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    errors::AmmError,
+    events::{emit_event, PositionFeesCollected},
+    instructions::{transfer_tokens_from_vault, ModifyLiquidity},
+};
+
+pub fn collect_position_fees<'info>(
+    ctx: Context<'_, '_, 'info, 'info, ModifyLiquidity<'info>>,
+) -> Result<()> {
+    // A zero-liquidity update settles the fees earned since the last one
+    ctx.accounts.modify_position(0)?;
+
+    let amount_a = ctx.accounts.position.tokens_owed_a;
+    let amount_b = ctx.accounts.position.tokens_owed_b;
+    require!(amount_a > 0 || amount_b > 0, AmmError::InvalidAmount);
+
+    let cl_pool = &ctx.accounts.cl_pool;
+    let fee_rate_bytes = cl_pool.fee_rate.to_le_bytes();
+    let seeds = &[
+        b"cl_pool",
+        cl_pool.token_a_mint.as_ref(),
+        cl_pool.token_b_mint.as_ref(),
+        fee_rate_bytes.as_ref(),
+        &[cl_pool.bump],
+    ];
+    let signer = &[&seeds[..]];
+
+    if amount_a > 0 {
+        transfer_tokens_from_vault(
+            &ctx.accounts.token_a_vault,
+            &ctx.accounts.user_token_a,
+            &cl_pool.to_account_info(),
+            &ctx.accounts.token_a_program,
+            &ctx.accounts.token_a_mint,
+            ctx.remaining_accounts,
+            signer,
+            amount_a,
+        )?;
+    }
+    if amount_b > 0 {
+        transfer_tokens_from_vault(
+            &ctx.accounts.token_b_vault,
+            &ctx.accounts.user_token_b,
+            &cl_pool.to_account_info(),
+            &ctx.accounts.token_b_program,
+            &ctx.accounts.token_b_mint,
+            ctx.remaining_accounts,
+            signer,
+            amount_b,
+        )?;
+    }
+
+    let position = &mut ctx.accounts.position;
+    position.tokens_owed_a = 0;
+    position.tokens_owed_b = 0;
+
+    emit_event!(
+        ctx,
+        PositionFeesCollected {
+            pool: ctx.accounts.cl_pool.key(),
+            position: ctx.accounts.position.key(),
+            owner: ctx.accounts.owner.key(),
+            amount_a,
+            amount_b,
+        }
+    );
+
+    Ok(())
+}
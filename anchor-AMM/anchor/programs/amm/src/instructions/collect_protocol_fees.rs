@@ -0,0 +1,133 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+
+use crate::states::Pool;
+use crate::{
+    errors::AmmError,
+    events::{emit_event, ProtocolFeesCollected},
+    instructions::{transfer_hook_program, transfer_tokens_from_vault},
+};
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+pub struct CollectProtocolFees<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"pool",
+            pool.token_a_mint.as_ref(),
+            pool.token_b_mint.as_ref(),
+            &pool.fee_tier.to_le_bytes(),
+        ],
+        bump = pool.bump,
+        has_one = authority @ AmmError::Unauthorized,
+        constraint = !pool.flash_loan_active @ AmmError::FlashLoanActive,
+    )]
+    pub pool: Account<'info, Pool>,
+
+    #[account(
+        mut,
+        address = pool.token_a_vault @ AmmError::InvalidPoolState,
+    )]
+    pub token_a_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        address = pool.token_b_vault @ AmmError::InvalidPoolState,
+    )]
+    pub token_b_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = treasury_token_a.mint == token_a_mint.key() @ AmmError::InvalidTokenMint,
+    )]
+    pub treasury_token_a: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = treasury_token_b.mint == token_b_mint.key() @ AmmError::InvalidTokenMint,
+    )]
+    pub treasury_token_b: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        address = pool.token_a_mint @ AmmError::InvalidTokenMint,
+        mint::token_program = token_a_program,
+        constraint = pool.accepts_transfer_hook(transfer_hook_program(&token_a_mint))
+            @ AmmError::TransferHookNotAllowed,
+    )]
+    pub token_a_mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        address = pool.token_b_mint @ AmmError::InvalidTokenMint,
+        mint::token_program = token_b_program,
+        constraint = pool.accepts_transfer_hook(transfer_hook_program(&token_b_mint))
+            @ AmmError::TransferHookNotAllowed,
+    )]
+    pub token_b_mint: InterfaceAccount<'info, Mint>,
+
+    pub token_a_program: Interface<'info, TokenInterface>,
+    pub token_b_program: Interface<'info, TokenInterface>,
+}
+
+pub fn collect_protocol_fees<'info>(
+    ctx: Context<'_, '_, 'info, 'info, CollectProtocolFees<'info>>,
+) -> Result<()> {
+    let pool = &ctx.accounts.pool;
+
+    let amount_a = pool.protocol_fees_a;
+    let amount_b = pool.protocol_fees_b;
+
+    require!(amount_a > 0 || amount_b > 0, AmmError::InvalidAmount);
+
+    let fee_tier_bytes = pool.fee_tier.to_le_bytes();
+    let seeds = &[
+        b"pool",
+        pool.token_a_mint.as_ref(),
+        pool.token_b_mint.as_ref(),
+        fee_tier_bytes.as_ref(),
+        &[pool.bump],
+    ];
+    let signer = &[&seeds[..]];
+
+    if amount_a > 0 {
+        transfer_tokens_from_vault(
+            &ctx.accounts.token_a_vault,
+            &ctx.accounts.treasury_token_a,
+            &pool.to_account_info(),
+            &ctx.accounts.token_a_program,
+            &ctx.accounts.token_a_mint,
+            ctx.remaining_accounts,
+            signer,
+            amount_a,
+        )?;
+    }
+
+    if amount_b > 0 {
+        transfer_tokens_from_vault(
+            &ctx.accounts.token_b_vault,
+            &ctx.accounts.treasury_token_b,
+            &pool.to_account_info(),
+            &ctx.accounts.token_b_program,
+            &ctx.accounts.token_b_mint,
+            ctx.remaining_accounts,
+            signer,
+            amount_b,
+        )?;
+    }
+
+    let pool = &mut ctx.accounts.pool;
+    pool.protocol_fees_a = 0;
+    pool.protocol_fees_b = 0;
+
+    emit_event!(
+        ctx,
+        ProtocolFeesCollected {
+            pool: ctx.accounts.pool.key(),
+            amount_a,
+            amount_b,
+        }
+    );
+
+    Ok(())
+}
@@ -0,0 +1,87 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    errors::AmmError,
+    events::{emit_event, ClLiquidityRemoved},
+    instructions::{amount_received, check_deadline, transfer_tokens_from_vault, ModifyLiquidity},
+    math::Rounding,
+};
+
+pub fn decrease_liquidity<'info>(
+    ctx: Context<'_, '_, 'info, 'info, ModifyLiquidity<'info>>,
+    liquidity: u128,
+    min_amount_a: u64,
+    min_amount_b: u64,
+    deadline: Option<i64>,
+) -> Result<()> {
+    check_deadline(deadline)?;
+    require!(
+        liquidity > 0 && liquidity <= ctx.accounts.position.liquidity,
+        AmmError::InvalidAmount
+    );
+    let liquidity_delta = i128::try_from(liquidity).map_err(|_| error!(AmmError::MathOverflow))?;
+
+    ctx.accounts.modify_position(-liquidity_delta)?;
+
+    let (amount_a, amount_b) = ctx
+        .accounts
+        .amounts_for_liquidity(liquidity, Rounding::Down)?;
+    require!(
+        amount_received(&ctx.accounts.token_a_mint, amount_a)? >= min_amount_a
+            && amount_received(&ctx.accounts.token_b_mint, amount_b)? >= min_amount_b,
+        AmmError::SlippageExceeded
+    );
+
+    let cl_pool = &ctx.accounts.cl_pool;
+    let fee_rate_bytes = cl_pool.fee_rate.to_le_bytes();
+    let seeds = &[
+        b"cl_pool",
+        cl_pool.token_a_mint.as_ref(),
+        cl_pool.token_b_mint.as_ref(),
+        fee_rate_bytes.as_ref(),
+        &[cl_pool.bump],
+    ];
+    let signer = &[&seeds[..]];
+
+    if amount_a > 0 {
+        transfer_tokens_from_vault(
+            &ctx.accounts.token_a_vault,
+            &ctx.accounts.user_token_a,
+            &cl_pool.to_account_info(),
+            &ctx.accounts.token_a_program,
+            &ctx.accounts.token_a_mint,
+            ctx.remaining_accounts,
+            signer,
+            amount_a,
+        )?;
+    }
+    if amount_b > 0 {
+        transfer_tokens_from_vault(
+            &ctx.accounts.token_b_vault,
+            &ctx.accounts.user_token_b,
+            &cl_pool.to_account_info(),
+            &ctx.accounts.token_b_program,
+            &ctx.accounts.token_b_mint,
+            ctx.remaining_accounts,
+            signer,
+            amount_b,
+        )?;
+    }
+
+    emit_event!(
+        ctx,
+        ClLiquidityRemoved {
+            pool: cl_pool.key(),
+            position: ctx.accounts.position.key(),
+            owner: ctx.accounts.owner.key(),
+            liquidity,
+            amount_a,
+            amount_b,
+            pool_liquidity: cl_pool.liquidity,
+            reserve_a: ctx.accounts.token_a_vault.amount - amount_a,
+            reserve_b: ctx.accounts.token_b_vault.amount - amount_b,
+        }
+    );
+
+    Ok(())
+}
@@ -0,0 +1,188 @@
+use anchor_lang::{
+    prelude::*,
+    solana_program::sysvar::instructions::{
+        load_current_index_checked, load_instruction_at_checked,
+    },
+    Discriminator,
+};
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+
+use crate::{
+    errors::AmmError,
+    events::{emit_event, FlashBorrowed},
+    instructions::{transfer_hook_program, transfer_tokens_from_vault},
+    math,
+    states::Pool,
+};
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+pub struct FlashBorrow<'info> {
+    pub borrower: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"pool",
+            pool.token_a_mint.as_ref(),
+            pool.token_b_mint.as_ref(),
+            &pool.fee_tier.to_le_bytes(),
+        ],
+        bump = pool.bump,
+        constraint = !pool.flash_loan_active @ AmmError::FlashLoanActive,
+        constraint = !pool.paused @ AmmError::PoolPaused,
+    )]
+    pub pool: Account<'info, Pool>,
+
+    #[account(
+        mut,
+        address = pool.token_a_vault @ AmmError::InvalidPoolState,
+    )]
+    pub token_a_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        address = pool.token_b_vault @ AmmError::InvalidPoolState,
+    )]
+    pub token_b_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = borrower_token_a.mint == token_a_mint.key() @ AmmError::InvalidTokenMint,
+        constraint = borrower_token_a.owner == borrower.key() @ AmmError::Unauthorized,
+    )]
+    pub borrower_token_a: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = borrower_token_b.mint == token_b_mint.key() @ AmmError::InvalidTokenMint,
+        constraint = borrower_token_b.owner == borrower.key() @ AmmError::Unauthorized,
+    )]
+    pub borrower_token_b: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        address = pool.token_a_mint @ AmmError::InvalidTokenMint,
+        mint::token_program = token_a_program,
+        constraint = pool.accepts_transfer_hook(transfer_hook_program(&token_a_mint))
+            @ AmmError::TransferHookNotAllowed,
+    )]
+    pub token_a_mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        address = pool.token_b_mint @ AmmError::InvalidTokenMint,
+        mint::token_program = token_b_program,
+        constraint = pool.accepts_transfer_hook(transfer_hook_program(&token_b_mint))
+            @ AmmError::TransferHookNotAllowed,
+    )]
+    pub token_b_mint: InterfaceAccount<'info, Mint>,
+
+    pub token_a_program: Interface<'info, TokenInterface>,
+    pub token_b_program: Interface<'info, TokenInterface>,
+
+    /// CHECK: address is checked against the instructions sysvar
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+    pub instructions: UncheckedAccount<'info>,
+}
+
+/// Index of `pool` in the accounts of a `flash_repay` instruction.
+const REPAY_POOL_ACCOUNT_INDEX: usize = 1;
+
+/// Whether an instruction after the current one repays a flash loan on `pool`.
+fn has_later_repay(instructions: &AccountInfo, pool: Pubkey) -> Result<bool> {
+    let current_index = load_current_index_checked(instructions)? as usize;
+
+    let mut index = current_index + 1;
+    while let Ok(instruction) = load_instruction_at_checked(index, instructions) {
+        let is_repay = instruction.program_id == crate::ID
+            && instruction
+                .data
+                .starts_with(crate::instruction::FlashRepay::DISCRIMINATOR)
+            && instruction
+                .accounts
+                .get(REPAY_POOL_ACCOUNT_INDEX)
+                .is_some_and(|account| account.pubkey == pool);
+        if is_repay {
+            return Ok(true);
+        }
+        index += 1;
+    }
+
+    Ok(false)
+}
+
+/// Lends vault tokens until a `flash_repay` later in the same transaction,
+/// which must return them together with `fee_rate` on the borrowed amounts.
+pub fn flash_borrow<'info>(
+    ctx: Context<'_, '_, 'info, 'info, FlashBorrow<'info>>,
+    amount_a: u64,
+    amount_b: u64,
+) -> Result<()> {
+    require!(amount_a > 0 || amount_b > 0, AmmError::InvalidAmount);
+
+    let pool = &ctx.accounts.pool;
+    let (reserve_a, reserve_b) = pool.reserves(
+        ctx.accounts.token_a_vault.amount,
+        ctx.accounts.token_b_vault.amount,
+    )?;
+    require!(
+        amount_a < reserve_a && amount_b < reserve_b,
+        AmmError::InsufficientLiquidity
+    );
+    require!(
+        has_later_repay(&ctx.accounts.instructions, pool.key())?,
+        AmmError::FlashLoanNotRepaid
+    );
+
+    let fee_tier_bytes = pool.fee_tier.to_le_bytes();
+    let seeds = &[
+        b"pool",
+        pool.token_a_mint.as_ref(),
+        pool.token_b_mint.as_ref(),
+        fee_tier_bytes.as_ref(),
+        &[pool.bump],
+    ];
+    let signer = &[&seeds[..]];
+
+    if amount_a > 0 {
+        transfer_tokens_from_vault(
+            &ctx.accounts.token_a_vault,
+            &ctx.accounts.borrower_token_a,
+            &pool.to_account_info(),
+            &ctx.accounts.token_a_program,
+            &ctx.accounts.token_a_mint,
+            ctx.remaining_accounts,
+            signer,
+            amount_a,
+        )?;
+    }
+    if amount_b > 0 {
+        transfer_tokens_from_vault(
+            &ctx.accounts.token_b_vault,
+            &ctx.accounts.borrower_token_b,
+            &pool.to_account_info(),
+            &ctx.accounts.token_b_program,
+            &ctx.accounts.token_b_mint,
+            ctx.remaining_accounts,
+            signer,
+            amount_b,
+        )?;
+    }
+
+    let pool = &mut ctx.accounts.pool;
+    pool.flash_loan_active = true;
+    pool.flash_loan_amount_a = amount_a;
+    pool.flash_loan_amount_b = amount_b;
+
+    let borrowed = FlashBorrowed {
+        pool: pool.key(),
+        borrower: ctx.accounts.borrower.key(),
+        amount_a,
+        amount_b,
+        fee_a: math::fee_amount(amount_a, pool.fee_rate)?,
+        fee_b: math::fee_amount(amount_b, pool.fee_rate)?,
+        reserve_a: reserve_a - amount_a,
+        reserve_b: reserve_b - amount_b,
+    };
+    emit_event!(ctx, borrowed);
+
+    Ok(())
+}
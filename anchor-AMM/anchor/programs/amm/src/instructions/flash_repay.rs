@@ -0,0 +1,159 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+
+use crate::{
+    errors::AmmError,
+    events::{emit_event, FlashRepaid},
+    instructions::{amount_to_send, transfer_hook_program, transfer_tokens},
+    math,
+    states::Pool,
+};
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+pub struct FlashRepay<'info> {
+    pub borrower: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"pool",
+            pool.token_a_mint.as_ref(),
+            pool.token_b_mint.as_ref(),
+            &pool.fee_tier.to_le_bytes(),
+        ],
+        bump = pool.bump,
+        constraint = pool.flash_loan_active @ AmmError::InvalidPoolState,
+    )]
+    pub pool: Account<'info, Pool>,
+
+    #[account(
+        mut,
+        address = pool.token_a_vault @ AmmError::InvalidPoolState,
+    )]
+    pub token_a_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        address = pool.token_b_vault @ AmmError::InvalidPoolState,
+    )]
+    pub token_b_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = borrower_token_a.mint == token_a_mint.key() @ AmmError::InvalidTokenMint,
+        constraint = borrower_token_a.owner == borrower.key() @ AmmError::Unauthorized,
+    )]
+    pub borrower_token_a: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = borrower_token_b.mint == token_b_mint.key() @ AmmError::InvalidTokenMint,
+        constraint = borrower_token_b.owner == borrower.key() @ AmmError::Unauthorized,
+    )]
+    pub borrower_token_b: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        address = pool.token_a_mint @ AmmError::InvalidTokenMint,
+        mint::token_program = token_a_program,
+        constraint = pool.accepts_transfer_hook(transfer_hook_program(&token_a_mint))
+            @ AmmError::TransferHookNotAllowed,
+    )]
+    pub token_a_mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        address = pool.token_b_mint @ AmmError::InvalidTokenMint,
+        mint::token_program = token_b_program,
+        constraint = pool.accepts_transfer_hook(transfer_hook_program(&token_b_mint))
+            @ AmmError::TransferHookNotAllowed,
+    )]
+    pub token_b_mint: InterfaceAccount<'info, Mint>,
+
+    pub token_a_program: Interface<'info, TokenInterface>,
+    pub token_b_program: Interface<'info, TokenInterface>,
+}
+
+pub fn flash_repay<'info>(ctx: Context<'_, '_, 'info, 'info, FlashRepay<'info>>) -> Result<()> {
+    let pool = &ctx.accounts.pool;
+    let amount_a = pool.flash_loan_amount_a;
+    let amount_b = pool.flash_loan_amount_b;
+
+    // Nothing else can move vault tokens during the loan, so the reserves
+    // before it are the current ones plus what was lent
+    let (reserve_a, reserve_b) = pool.reserves(
+        ctx.accounts.token_a_vault.amount,
+        ctx.accounts.token_b_vault.amount,
+    )?;
+    let k_before = (reserve_a as u128 + amount_a as u128) * (reserve_b as u128 + amount_b as u128);
+
+    let fee_a = math::fee_amount(amount_a, pool.fee_rate)?;
+    let fee_b = math::fee_amount(amount_b, pool.fee_rate)?;
+    let protocol_fee_a = math::protocol_fee_amount(fee_a, pool.protocol_fee_rate)?;
+    let protocol_fee_b = math::protocol_fee_amount(fee_b, pool.protocol_fee_rate)?;
+
+    // The vaults must net the loan plus fee, so any transfer fee is on the borrower
+    let repay_a = amount_to_send(
+        &ctx.accounts.token_a_mint,
+        amount_a.checked_add(fee_a).ok_or(AmmError::MathOverflow)?,
+    )?;
+    let repay_b = amount_to_send(
+        &ctx.accounts.token_b_mint,
+        amount_b.checked_add(fee_b).ok_or(AmmError::MathOverflow)?,
+    )?;
+
+    if repay_a > 0 {
+        transfer_tokens(
+            &ctx.accounts.borrower_token_a,
+            &ctx.accounts.token_a_vault,
+            &ctx.accounts.borrower,
+            &ctx.accounts.token_a_program,
+            &ctx.accounts.token_a_mint,
+            ctx.remaining_accounts,
+            repay_a,
+        )?;
+    }
+    if repay_b > 0 {
+        transfer_tokens(
+            &ctx.accounts.borrower_token_b,
+            &ctx.accounts.token_b_vault,
+            &ctx.accounts.borrower,
+            &ctx.accounts.token_b_program,
+            &ctx.accounts.token_b_mint,
+            ctx.remaining_accounts,
+            repay_b,
+        )?;
+    }
+
+    let pool = &mut ctx.accounts.pool;
+    pool.accrue_protocol_fee(protocol_fee_a, true)?;
+    pool.accrue_protocol_fee(protocol_fee_b, false)?;
+    pool.flash_loan_active = false;
+    pool.flash_loan_amount_a = 0;
+    pool.flash_loan_amount_b = 0;
+
+    ctx.accounts.token_a_vault.reload()?;
+    ctx.accounts.token_b_vault.reload()?;
+    let (reserve_a, reserve_b) = ctx.accounts.pool.reserves(
+        ctx.accounts.token_a_vault.amount,
+        ctx.accounts.token_b_vault.amount,
+    )?;
+    require!(
+        reserve_a as u128 * reserve_b as u128 >= k_before,
+        AmmError::FlashLoanNotRepaid
+    );
+
+    emit_event!(
+        ctx,
+        FlashRepaid {
+            pool: ctx.accounts.pool.key(),
+            borrower: ctx.accounts.borrower.key(),
+            amount_a,
+            amount_b,
+            fee_a,
+            fee_b,
+            reserve_a,
+            reserve_b,
+        }
+    );
+
+    Ok(())
+}
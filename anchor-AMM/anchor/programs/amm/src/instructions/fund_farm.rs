@@ -0,0 +1,83 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+
+use crate::{
+    errors::AmmError,
+    events::{emit_event, FarmFunded},
+    instructions::{amount_received, transfer_tokens},
+    states::Farm,
+};
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+pub struct FundFarm<'info> {
+    pub funder: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"farm", farm.pool.as_ref(), farm.reward_mint.as_ref()],
+        bump = farm.bump,
+    )]
+    pub farm: Account<'info, Farm>,
+
+    #[account(
+        address = farm.reward_mint @ AmmError::InvalidTokenMint,
+        mint::token_program = reward_token_program,
+    )]
+    pub reward_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        address = farm.reward_vault @ AmmError::InvalidPoolState,
+    )]
+    pub reward_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = funder_reward_token.mint == reward_mint.key() @ AmmError::InvalidTokenMint,
+        constraint = funder_reward_token.owner == funder.key() @ AmmError::Unauthorized,
+    )]
+    pub funder_reward_token: InterfaceAccount<'info, TokenAccount>,
+
+    pub reward_token_program: Interface<'info, TokenInterface>,
+}
+
+/// Adds `amount` of the reward mint to the farm. Anyone may fund a farm;
+/// emission resumes from now if it had run dry.
+pub fn fund_farm<'info>(
+    ctx: Context<'_, '_, 'info, 'info, FundFarm<'info>>,
+    amount: u64,
+) -> Result<()> {
+    require!(amount > 0, AmmError::InvalidAmount);
+
+    ctx.accounts.farm.update(Clock::get()?.unix_timestamp);
+
+    transfer_tokens(
+        &ctx.accounts.funder_reward_token,
+        &ctx.accounts.reward_vault,
+        &ctx.accounts.funder,
+        &ctx.accounts.reward_token_program,
+        &ctx.accounts.reward_mint,
+        ctx.remaining_accounts,
+        amount,
+    )?;
+
+    let received = amount_received(&ctx.accounts.reward_mint, amount)?;
+    let farm = &mut ctx.accounts.farm;
+    farm.rewards_unallocated = farm
+        .rewards_unallocated
+        .checked_add(received)
+        .ok_or(AmmError::MathOverflow)?;
+
+    emit_event!(
+        ctx,
+        FarmFunded {
+            farm: ctx.accounts.farm.key(),
+            funder: ctx.accounts.funder.key(),
+            amount: received,
+            rewards_unallocated: ctx.accounts.farm.rewards_unallocated,
+        }
+    );
+
+    Ok(())
+}
@@ -1,50 +1,176 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface, TransferChecked};
+use anchor_spl::{
+    token_2022::{
+        self,
+        spl_token_2022::{
+            extension::{
+                transfer_fee::TransferFeeConfig, transfer_hook, BaseStateWithExtensions,
+                StateWithExtensions,
+            },
+            onchain::invoke_transfer_checked,
+            state::Mint as MintState,
+        },
+    },
+    token_interface::{Mint, MintTo, TokenAccount, TokenInterface},
+};
+
+use crate::errors::AmmError;
 
+/// Transfers `amount` of `mint` on behalf of `authority`. Mints with a
+/// Token-2022 transfer hook resolve the hook's extra accounts from
+/// `remaining_accounts`.
 pub fn transfer_tokens<'info>(
     from: &InterfaceAccount<'info, TokenAccount>,
     to: &InterfaceAccount<'info, TokenAccount>,
     authority: &Signer<'info>,
     token_program: &Interface<'info, TokenInterface>,
     mint: &InterfaceAccount<'info, Mint>,
+    remaining_accounts: &[AccountInfo<'info>],
     amount: u64,
 ) -> Result<()> {
-    anchor_spl::token_interface::transfer_checked(
-        CpiContext::new(
-            token_program.to_account_info(),
-            TransferChecked {
-                from: from.to_account_info(),
-                mint: mint.to_account_info(),
-                to: to.to_account_info(),
-                authority: authority.to_account_info(),
-            },
-        ),
+    invoke_transfer_checked(
+        token_program.key,
+        from.to_account_info(),
+        mint.to_account_info(),
+        to.to_account_info(),
+        authority.to_account_info(),
+        remaining_accounts,
         amount,
         mint.decimals,
+        &[],
     )
+    .map_err(Into::into)
 }
 
+/// Transfers `amount` of `mint` out of a vault owned by the PDA `authority`.
+/// Mints with a Token-2022 transfer hook resolve the hook's extra accounts
+/// from `remaining_accounts`.
+#[allow(clippy::too_many_arguments)]
 pub fn transfer_tokens_from_vault<'info>(
     from: &InterfaceAccount<'info, TokenAccount>,
     to: &InterfaceAccount<'info, TokenAccount>,
     authority: &AccountInfo<'info>,
     token_program: &Interface<'info, TokenInterface>,
     mint: &InterfaceAccount<'info, Mint>,
+    remaining_accounts: &[AccountInfo<'info>],
+    signer_seeds: &[&[&[u8]]],
+    amount: u64,
+) -> Result<()> {
+    invoke_transfer_checked(
+        token_program.key,
+        from.to_account_info(),
+        mint.to_account_info(),
+        to.to_account_info(),
+        authority.clone(),
+        remaining_accounts,
+        amount,
+        mint.decimals,
+        signer_seeds,
+    )
+    .map_err(Into::into)
+}
+
+pub fn mint_lp_tokens<'info>(
+    lp_mint: &InterfaceAccount<'info, Mint>,
+    to: &InterfaceAccount<'info, TokenAccount>,
+    authority: &AccountInfo<'info>,
+    token_program: &Interface<'info, TokenInterface>,
     signer_seeds: &[&[&[u8]]],
     amount: u64,
 ) -> Result<()> {
-    anchor_spl::token_interface::transfer_checked(
+    anchor_spl::token_interface::mint_to(
         CpiContext::new_with_signer(
             token_program.to_account_info(),
-            TransferChecked {
-                from: from.to_account_info(),
-                mint: mint.to_account_info(),
+            MintTo {
+                mint: lp_mint.to_account_info(),
                 to: to.to_account_info(),
                 authority: authority.clone(),
             },
             signer_seeds,
         ),
         amount,
-        mint.decimals,
     )
-}
\ No newline at end of file
+}
+
+/// Whichever of `token_program` and `token_2022_program` owns `mint`.
+pub fn token_program_for<'a, 'info>(
+    mint: &InterfaceAccount<'info, Mint>,
+    token_program: &'a Interface<'info, TokenInterface>,
+    token_2022_program: &'a Interface<'info, TokenInterface>,
+) -> &'a Interface<'info, TokenInterface> {
+    // Loading the mint as an `InterfaceAccount` already restricted its owner to these two
+    if *mint.to_account_info().owner == token_2022_program.key() {
+        token_2022_program
+    } else {
+        token_program
+    }
+}
+
+/// Rejects the instruction once `deadline`, a unix timestamp, has passed.
+pub fn check_deadline(deadline: Option<i64>) -> Result<()> {
+    if let Some(deadline) = deadline {
+        require!(
+            Clock::get()?.unix_timestamp <= deadline,
+            AmmError::DeadlineExceeded
+        );
+    }
+    Ok(())
+}
+
+/// Program invoked by the mint's Token-2022 transfer hook, if it has one.
+pub fn transfer_hook_program(mint: &InterfaceAccount<Mint>) -> Option<Pubkey> {
+    let mint_info = mint.to_account_info();
+    if *mint_info.owner != token_2022::ID {
+        return None;
+    }
+
+    let data = mint_info.try_borrow_data().ok()?;
+    let mint_state = StateWithExtensions::<MintState>::unpack(&data).ok()?;
+    transfer_hook::get_program_id(&mint_state)
+}
+
+/// Runs `f` on the mint's TransferFeeConfig extension, if it has one.
+fn with_transfer_fee_config<T>(
+    mint: &InterfaceAccount<Mint>,
+    f: impl FnOnce(&TransferFeeConfig, u64) -> Option<T>,
+) -> Result<Option<T>> {
+    let mint_info = mint.to_account_info();
+    if *mint_info.owner != token_2022::ID {
+        return Ok(None);
+    }
+
+    let data = mint_info.try_borrow_data()?;
+    let mint_state = StateWithExtensions::<MintState>::unpack(&data)?;
+    let Ok(config) = mint_state.get_extension::<TransferFeeConfig>() else {
+        return Ok(None);
+    };
+
+    let epoch = Clock::get()?.epoch;
+    f(config, epoch)
+        .map(Some)
+        .ok_or_else(|| error!(AmmError::MathOverflow))
+}
+
+/// Amount that arrives when `amount` of `mint` is transferred, after any
+/// Token-2022 transfer fee is withheld.
+pub fn amount_received(mint: &InterfaceAccount<Mint>, amount: u64) -> Result<u64> {
+    let fee = with_transfer_fee_config(mint, |config, epoch| {
+        config.calculate_epoch_fee(epoch, amount)
+    })?;
+    Ok(amount - fee.unwrap_or(0))
+}
+
+/// Amount of `mint` that must be transferred for `amount` to arrive, after
+/// any Token-2022 transfer fee is withheld.
+pub fn amount_to_send(mint: &InterfaceAccount<Mint>, amount: u64) -> Result<u64> {
+    if amount == 0 {
+        return Ok(0);
+    }
+
+    let fee = with_transfer_fee_config(mint, |config, epoch| {
+        config.calculate_inverse_epoch_fee(epoch, amount)
+    })?;
+    amount
+        .checked_add(fee.unwrap_or(0))
+        .ok_or_else(|| error!(AmmError::MathOverflow))
+}
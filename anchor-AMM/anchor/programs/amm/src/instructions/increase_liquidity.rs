@@ -0,0 +1,214 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+
+use crate::{
+    errors::AmmError,
+    events::{emit_event, ClLiquidityAdded},
+    instructions::{
+        amount_received, amount_to_send, check_deadline, transfer_hook_program, transfer_tokens,
+    },
+    math::{concentrated_liquidity, tick_math, Rounding},
+    states::{ClPool, Position, TickArray},
+};
+
+/// Accounts shared by every instruction that touches a concentrated
+/// liquidity position.
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+pub struct ModifyLiquidity<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"cl_pool",
+            cl_pool.token_a_mint.as_ref(),
+            cl_pool.token_b_mint.as_ref(),
+            &cl_pool.fee_rate.to_le_bytes(),
+        ],
+        bump = cl_pool.bump,
+    )]
+    pub cl_pool: Account<'info, ClPool>,
+
+    #[account(
+        mut,
+        has_one = owner @ AmmError::Unauthorized,
+        constraint = position.pool == cl_pool.key() @ AmmError::InvalidPoolState,
+    )]
+    pub position: Account<'info, Position>,
+
+    #[account(
+        mut,
+        constraint = tick_array_lower.pool == cl_pool.key() @ AmmError::InvalidTickArray,
+        constraint = tick_array_lower.contains(position.tick_lower, cl_pool.tick_spacing) @ AmmError::InvalidTickArray,
+    )]
+    pub tick_array_lower: Box<Account<'info, TickArray>>,
+
+    #[account(
+        mut,
+        constraint = tick_array_upper.pool == cl_pool.key() @ AmmError::InvalidTickArray,
+        constraint = tick_array_upper.contains(position.tick_upper, cl_pool.tick_spacing) @ AmmError::InvalidTickArray,
+    )]
+    pub tick_array_upper: Box<Account<'info, TickArray>>,
+
+    #[account(
+        mut,
+        address = cl_pool.token_a_vault @ AmmError::InvalidPoolState,
+    )]
+    pub token_a_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        address = cl_pool.token_b_vault @ AmmError::InvalidPoolState,
+    )]
+    pub token_b_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = user_token_a.mint == token_a_mint.key() @ AmmError::InvalidTokenMint,
+        constraint = user_token_a.owner == owner.key() @ AmmError::Unauthorized,
+    )]
+    pub user_token_a: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = user_token_b.mint == token_b_mint.key() @ AmmError::InvalidTokenMint,
+        constraint = user_token_b.owner == owner.key() @ AmmError::Unauthorized,
+    )]
+    pub user_token_b: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        address = cl_pool.token_a_mint @ AmmError::InvalidTokenMint,
+        mint::token_program = token_a_program,
+        constraint = cl_pool.accepts_transfer_hook(transfer_hook_program(&token_a_mint))
+            @ AmmError::TransferHookNotAllowed,
+    )]
+    pub token_a_mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        address = cl_pool.token_b_mint @ AmmError::InvalidTokenMint,
+        mint::token_program = token_b_program,
+        constraint = cl_pool.accepts_transfer_hook(transfer_hook_program(&token_b_mint))
+            @ AmmError::TransferHookNotAllowed,
+    )]
+    pub token_b_mint: InterfaceAccount<'info, Mint>,
+
+    pub token_a_program: Interface<'info, TokenInterface>,
+    pub token_b_program: Interface<'info, TokenInterface>,
+}
+
+impl<'info> ModifyLiquidity<'info> {
+    /// Applies `liquidity_delta` to the position and keeps both tick array
+    /// accounts consistent when the two bounds share one array.
+    pub fn modify_position(&mut self, liquidity_delta: i128) -> Result<()> {
+        let same_array = self.tick_array_lower.key() == self.tick_array_upper.key();
+        let tick_array_upper = if same_array {
+            None
+        } else {
+            Some(&mut **self.tick_array_upper)
+        };
+
+        self.cl_pool.modify_position(
+            &mut self.position,
+            &mut self.tick_array_lower,
+            tick_array_upper,
+            liquidity_delta,
+        )?;
+
+        // Both fields are written back on exit, so they must hold the same data
+        if same_array {
+            let tick_array = (**self.tick_array_lower).clone();
+            self.tick_array_upper.set_inner(tick_array);
+        }
+
+        Ok(())
+    }
+
+    /// Token amounts backing `liquidity` in the position's range at the
+    /// current price.
+    pub fn amounts_for_liquidity(&self, liquidity: u128, rounding: Rounding) -> Result<(u64, u64)> {
+        concentrated_liquidity::amounts_for_liquidity(
+            self.cl_pool.sqrt_price_x64,
+            tick_math::sqrt_price_at_tick(self.position.tick_lower)?,
+            tick_math::sqrt_price_at_tick(self.position.tick_upper)?,
+            liquidity,
+            rounding,
+        )
+    }
+}
+
+pub fn increase_liquidity<'info>(
+    ctx: Context<'_, '_, 'info, 'info, ModifyLiquidity<'info>>,
+    liquidity: u128,
+    max_amount_a: u64,
+    max_amount_b: u64,
+    deadline: Option<i64>,
+) -> Result<()> {
+    check_deadline(deadline)?;
+    require!(liquidity > 0, AmmError::InvalidAmount);
+    let liquidity_delta = i128::try_from(liquidity).map_err(|_| error!(AmmError::MathOverflow))?;
+
+    ctx.accounts.modify_position(liquidity_delta)?;
+
+    // The vaults must net the full backing amounts, so any transfer fee is on the owner
+    let (amount_a, amount_b) = ctx
+        .accounts
+        .amounts_for_liquidity(liquidity, Rounding::Up)?;
+    let amount_a = amount_to_send(&ctx.accounts.token_a_mint, amount_a)?;
+    let amount_b = amount_to_send(&ctx.accounts.token_b_mint, amount_b)?;
+    require!(
+        amount_a <= max_amount_a && amount_b <= max_amount_b,
+        AmmError::SlippageExceeded
+    );
+
+    if amount_a > 0 {
+        transfer_tokens(
+            &ctx.accounts.user_token_a,
+            &ctx.accounts.token_a_vault,
+            &ctx.accounts.owner,
+            &ctx.accounts.token_a_program,
+            &ctx.accounts.token_a_mint,
+            ctx.remaining_accounts,
+            amount_a,
+        )?;
+    }
+    if amount_b > 0 {
+        transfer_tokens(
+            &ctx.accounts.user_token_b,
+            &ctx.accounts.token_b_vault,
+            &ctx.accounts.owner,
+            &ctx.accounts.token_b_program,
+            &ctx.accounts.token_b_mint,
+            ctx.remaining_accounts,
+            amount_b,
+        )?;
+    }
+
+    let received_a = amount_received(&ctx.accounts.token_a_mint, amount_a)?;
+    let received_b = amount_received(&ctx.accounts.token_b_mint, amount_b)?;
+    emit_event!(
+        ctx,
+        ClLiquidityAdded {
+            pool: ctx.accounts.cl_pool.key(),
+            position: ctx.accounts.position.key(),
+            owner: ctx.accounts.owner.key(),
+            liquidity,
+            amount_a: received_a,
+            amount_b: received_b,
+            pool_liquidity: ctx.accounts.cl_pool.liquidity,
+            reserve_a: ctx
+                .accounts
+                .token_a_vault
+                .amount
+                .checked_add(received_a)
+                .ok_or(AmmError::MathOverflow)?,
+            reserve_b: ctx
+                .accounts
+                .token_b_vault
+                .amount
+                .checked_add(received_b)
+                .ok_or(AmmError::MathOverflow)?,
+        }
+    );
+
+    Ok(())
+}
@@ -0,0 +1,57 @@
+use anchor_lang::{prelude::*, solana_program::bpf_loader_upgradeable};
+
+use crate::{
+    errors::AmmError,
+    events::{emit_event, AmmConfigUpdated},
+    states::AmmConfig,
+};
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+pub struct InitializeAmmConfig<'info> {
+    /// Upgrade authority of the program, which becomes the first admin
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + AmmConfig::INIT_SPACE,
+        seeds = [b"amm_config"],
+        bump,
+    )]
+    pub amm_config: Account<'info, AmmConfig>,
+
+    /// Program data account of this program, which names its upgrade authority
+    #[account(
+        seeds = [crate::ID.as_ref()],
+        bump,
+        seeds::program = bpf_loader_upgradeable::ID,
+        constraint = program_data.upgrade_authority_address == Some(admin.key())
+            @ AmmError::Unauthorized,
+    )]
+    pub program_data: Account<'info, ProgramData>,
+
+    pub system_program: Program<'info, System>,
+}
+
+pub fn initialize_amm_config(
+    ctx: Context<InitializeAmmConfig>,
+    fee_tiers: Vec<u16>,
+    pool_creation_fee: u64,
+    fee_receiver: Pubkey,
+) -> Result<()> {
+    let config = &mut ctx.accounts.amm_config;
+
+    config.admin = ctx.accounts.admin.key();
+    config.pending_admin = None;
+    config.set_fee_tiers(fee_tiers)?;
+    config.pool_creation_fee = pool_creation_fee;
+    config.fee_receiver = fee_receiver;
+    config.pool_count = 0;
+    config.bump = ctx.bumps.amm_config;
+
+    emit_event!(ctx, AmmConfigUpdated::new(&ctx.accounts.amm_config));
+
+    Ok(())
+}
@@ -0,0 +1,166 @@
+use anchor_lang::{
+    prelude::*,
+    system_program::{transfer, Transfer},
+};
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
+
+use crate::{
+    errors::AmmError,
+    events::{emit_event, ClPoolInitialized},
+    math::tick_math::{self, MAX_SQRT_PRICE_X64, MIN_SQRT_PRICE_X64},
+    states::{AmmConfig, ClPool, PoolRecord, MAX_TICK_SPACING},
+};
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+#[instruction(tick_spacing: u16, fee_rate: u16)]
+pub struct InitializeClPool<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"amm_config"],
+        bump = amm_config.bump,
+    )]
+    pub amm_config: Box<Account<'info, AmmConfig>>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + PoolRecord::INIT_SPACE,
+        seeds = [b"pool_record", amm_config.pool_count.to_le_bytes().as_ref()],
+        bump,
+    )]
+    pub pool_record: Box<Account<'info, PoolRecord>>,
+
+    /// CHECK: only receives the pool creation fee, and must be the config's receiver
+    #[account(mut, address = amm_config.fee_receiver)]
+    pub fee_receiver: UncheckedAccount<'info>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + ClPool::INIT_SPACE,
+        seeds = [
+            b"cl_pool",
+            token_a_mint.key().as_ref(),
+            token_b_mint.key().as_ref(),
+            &fee_rate.to_le_bytes(),
+        ],
+        bump,
+        // As for `initialize_pool`, one canonical order gives every pair and
+        // fee tier exactly one pool address
+        constraint = token_a_mint.key() != token_b_mint.key() @ AmmError::IdenticalMints,
+        constraint = token_a_mint.key() < token_b_mint.key() @ AmmError::UnsortedMints,
+    )]
+    pub cl_pool: Account<'info, ClPool>,
+
+    #[account(mint::token_program = token_a_program)]
+    pub token_a_mint: InterfaceAccount<'info, Mint>,
+    #[account(mint::token_program = token_b_program)]
+    pub token_b_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        init,
+        payer = authority,
+        associated_token::mint = token_a_mint,
+        associated_token::authority = cl_pool,
+        associated_token::token_program = token_a_program,
+    )]
+    pub token_a_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        init,
+        payer = authority,
+        associated_token::mint = token_b_mint,
+        associated_token::authority = cl_pool,
+        associated_token::token_program = token_b_program,
+    )]
+    pub token_b_vault: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_a_program: Interface<'info, TokenInterface>,
+    pub token_b_program: Interface<'info, TokenInterface>,
+
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn initialize_cl_pool(
+    ctx: Context<InitializeClPool>,
+    tick_spacing: u16,
+    fee_rate: u16,
+    initial_sqrt_price_x64: u128,
+) -> Result<()> {
+    require!(
+        tick_spacing > 0 && tick_spacing <= MAX_TICK_SPACING,
+        AmmError::InvalidTickSpacing
+    );
+    require!(
+        ctx.accounts.amm_config.fee_tiers.contains(&fee_rate),
+        AmmError::FeeTierNotAllowed
+    );
+    require!(
+        initial_sqrt_price_x64 > MIN_SQRT_PRICE_X64 && initial_sqrt_price_x64 < MAX_SQRT_PRICE_X64,
+        AmmError::InvalidSqrtPrice
+    );
+
+    let cl_pool = &mut ctx.accounts.cl_pool;
+
+    cl_pool.authority = ctx.accounts.authority.key();
+    cl_pool.token_a_mint = ctx.accounts.token_a_mint.key();
+    cl_pool.token_b_mint = ctx.accounts.token_b_mint.key();
+    cl_pool.token_a_vault = ctx.accounts.token_a_vault.key();
+    cl_pool.token_b_vault = ctx.accounts.token_b_vault.key();
+    cl_pool.tick_spacing = tick_spacing;
+    cl_pool.fee_rate = fee_rate;
+    cl_pool.sqrt_price_x64 = initial_sqrt_price_x64;
+    cl_pool.tick_current = tick_math::tick_at_sqrt_price(initial_sqrt_price_x64)?;
+    cl_pool.liquidity = 0;
+    cl_pool.fee_growth_global_a_x64 = 0;
+    cl_pool.fee_growth_global_b_x64 = 0;
+    cl_pool.transfer_hook_programs = Vec::new();
+    cl_pool.bump = ctx.bumps.cl_pool;
+
+    let pool_creation_fee = ctx.accounts.amm_config.pool_creation_fee;
+    if pool_creation_fee > 0 {
+        transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.authority.to_account_info(),
+                    to: ctx.accounts.fee_receiver.to_account_info(),
+                },
+            ),
+            pool_creation_fee,
+        )?;
+    }
+
+    let config = &mut ctx.accounts.amm_config;
+    let index = config.pool_count;
+    config.pool_count = index.checked_add(1).ok_or(AmmError::MathOverflow)?;
+
+    let record = &mut ctx.accounts.pool_record;
+    record.pool = ctx.accounts.cl_pool.key();
+    record.index = index;
+    record.bump = ctx.bumps.pool_record;
+
+    let cl_pool = &ctx.accounts.cl_pool;
+    let initialized = ClPoolInitialized {
+        pool: cl_pool.key(),
+        index,
+        authority: cl_pool.authority,
+        token_a_mint: cl_pool.token_a_mint,
+        token_b_mint: cl_pool.token_b_mint,
+        tick_spacing,
+        fee_rate,
+        sqrt_price_x64: cl_pool.sqrt_price_x64,
+        tick_current: cl_pool.tick_current,
+    };
+    emit_event!(ctx, initialized);
+
+    Ok(())
+}
@@ -0,0 +1,102 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
+
+use crate::{
+    errors::AmmError,
+    events::{emit_event, FarmInitialized},
+    instructions::transfer_hook_program,
+    states::{Farm, Pool},
+};
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+pub struct InitializeFarm<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [
+            b"pool",
+            pool.token_a_mint.as_ref(),
+            pool.token_b_mint.as_ref(),
+            &pool.fee_tier.to_le_bytes(),
+        ],
+        bump = pool.bump,
+        has_one = authority @ AmmError::Unauthorized,
+    )]
+    pub pool: Account<'info, Pool>,
+
+    #[account(
+        address = pool.lp_mint @ AmmError::InvalidTokenMint,
+        mint::token_program = lp_token_program,
+    )]
+    pub lp_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mint::token_program = reward_token_program,
+        constraint = pool.accepts_transfer_hook(transfer_hook_program(&reward_mint)) @ AmmError::TransferHookNotAllowed,
+    )]
+    pub reward_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + Farm::INIT_SPACE,
+        seeds = [b"farm", pool.key().as_ref(), reward_mint.key().as_ref()],
+        bump,
+    )]
+    pub farm: Account<'info, Farm>,
+
+    #[account(
+        init,
+        payer = authority,
+        associated_token::mint = lp_mint,
+        associated_token::authority = farm,
+        associated_token::token_program = lp_token_program,
+    )]
+    pub stake_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        init,
+        payer = authority,
+        associated_token::mint = reward_mint,
+        associated_token::authority = farm,
+        associated_token::token_program = reward_token_program,
+    )]
+    pub reward_vault: InterfaceAccount<'info, TokenAccount>,
+
+    pub lp_token_program: Interface<'info, TokenInterface>,
+    pub reward_token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn initialize_farm(ctx: Context<InitializeFarm>, reward_rate: u64) -> Result<()> {
+    let farm = &mut ctx.accounts.farm;
+
+    farm.pool = ctx.accounts.pool.key();
+    farm.lp_mint = ctx.accounts.lp_mint.key();
+    farm.reward_mint = ctx.accounts.reward_mint.key();
+    farm.stake_vault = ctx.accounts.stake_vault.key();
+    farm.reward_vault = ctx.accounts.reward_vault.key();
+    farm.reward_rate = reward_rate;
+    farm.reward_per_share_x64 = 0;
+    farm.rewards_unallocated = 0;
+    farm.total_staked = 0;
+    farm.last_update_ts = Clock::get()?.unix_timestamp;
+    farm.bump = ctx.bumps.farm;
+
+    let initialized = FarmInitialized {
+        farm: farm.key(),
+        pool: farm.pool,
+        lp_mint: farm.lp_mint,
+        reward_mint: farm.reward_mint,
+        reward_rate,
+    };
+    emit_event!(ctx, initialized);
+
+    Ok(())
+}
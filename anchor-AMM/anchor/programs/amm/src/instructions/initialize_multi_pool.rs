@@ -0,0 +1,202 @@
+use anchor_lang::{
+    prelude::*,
+    system_program::{transfer, Transfer},
+};
+use anchor_spl::{
+    associated_token::{self, AssociatedToken, Create},
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
+
+use crate::{
+    errors::AmmError,
+    events::{emit_event, MultiPoolInitialized},
+    instructions::{token_program_for, transfer_hook_program},
+    states::{
+        AmmConfig, MultiPool, MultiPoolToken, PoolRecord, MAX_MULTI_POOL_TOKENS,
+        MIN_MULTI_POOL_TOKENS,
+    },
+};
+
+/// Accounts passed in `remaining_accounts` for every constituent, in `mints`
+/// order: mint, vault. The vault is the pool's associated token account for
+/// the mint and is created here.
+pub const ACCOUNTS_PER_NEW_TOKEN: usize = 2;
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+#[instruction(fee_rate: u16, mints: Vec<Pubkey>)]
+pub struct InitializeMultiPool<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"amm_config"],
+        bump = amm_config.bump,
+    )]
+    pub amm_config: Box<Account<'info, AmmConfig>>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + PoolRecord::INIT_SPACE,
+        seeds = [b"pool_record", amm_config.pool_count.to_le_bytes().as_ref()],
+        bump,
+    )]
+    pub pool_record: Box<Account<'info, PoolRecord>>,
+
+    /// CHECK: only receives the pool creation fee, and must be the config's receiver
+    #[account(mut, address = amm_config.fee_receiver)]
+    pub fee_receiver: UncheckedAccount<'info>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + MultiPool::INIT_SPACE,
+        seeds = [b"multi_pool", MultiPool::mints_hash(&mints).as_ref()],
+        bump,
+    )]
+    pub pool: Account<'info, MultiPool>,
+
+    #[account(
+        init,
+        payer = authority,
+        mint::decimals = 6,
+        mint::authority = pool,
+        seeds = [b"lp_mint", pool.key().as_ref()],
+        bump,
+    )]
+    pub lp_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        init,
+        payer = authority,
+        associated_token::mint = lp_mint,
+        associated_token::authority = pool,
+        associated_token::token_program = token_program,
+    )]
+    pub locked_lp_vault: InterfaceAccount<'info, TokenAccount>,
+
+    /// SPL Token program, which also owns the LP mint
+    #[account(address = anchor_spl::token::ID)]
+    pub token_program: Interface<'info, TokenInterface>,
+
+    /// Token-2022 program, used for every constituent mint it owns
+    #[account(address = anchor_spl::token_2022::ID)]
+    pub token_2022_program: Interface<'info, TokenInterface>,
+
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn initialize_multi_pool<'info>(
+    ctx: Context<'_, '_, 'info, 'info, InitializeMultiPool<'info>>,
+    fee_rate: u16,
+    mints: Vec<Pubkey>,
+) -> Result<()> {
+    require!(
+        ctx.accounts.amm_config.fee_tiers.contains(&fee_rate),
+        AmmError::FeeTierNotAllowed
+    );
+    require!(
+        (MIN_MULTI_POOL_TOKENS..=MAX_MULTI_POOL_TOKENS).contains(&mints.len()),
+        AmmError::InvalidTokenCount
+    );
+    require!(
+        ctx.remaining_accounts.len() == mints.len() * ACCOUNTS_PER_NEW_TOKEN,
+        AmmError::InvalidTokenCount
+    );
+
+    // A strict order gives every set of mints exactly one pool address
+    for pair in mints.windows(2) {
+        require_keys_neq!(pair[0], pair[1], AmmError::IdenticalMints);
+        require!(pair[0] < pair[1], AmmError::UnsortedMints);
+    }
+
+    let mut tokens = Vec::with_capacity(mints.len());
+    for (accounts, expected_mint) in ctx
+        .remaining_accounts
+        .chunks_exact(ACCOUNTS_PER_NEW_TOKEN)
+        .zip(&mints)
+    {
+        let mint = InterfaceAccount::<Mint>::try_from(&accounts[0])?;
+        let vault = &accounts[1];
+
+        require_keys_eq!(mint.key(), *expected_mint, AmmError::InvalidTokenMint);
+        require!(
+            transfer_hook_program(&mint).is_none(),
+            AmmError::TransferHookNotAllowed
+        );
+
+        // Idempotent, so a vault someone created ahead of the pool cannot block it
+        let token_program = token_program_for(
+            &mint,
+            &ctx.accounts.token_program,
+            &ctx.accounts.token_2022_program,
+        );
+        associated_token::create_idempotent(CpiContext::new(
+            ctx.accounts.associated_token_program.to_account_info(),
+            Create {
+                payer: ctx.accounts.authority.to_account_info(),
+                associated_token: vault.clone(),
+                authority: ctx.accounts.pool.to_account_info(),
+                mint: mint.to_account_info(),
+                system_program: ctx.accounts.system_program.to_account_info(),
+                token_program: token_program.to_account_info(),
+            },
+        ))?;
+
+        tokens.push(MultiPoolToken {
+            mint: mint.key(),
+            vault: vault.key(),
+        });
+    }
+
+    let pool = &mut ctx.accounts.pool;
+
+    pool.authority = ctx.accounts.authority.key();
+    pool.mints_hash = MultiPool::mints_hash(&mints);
+    pool.lp_mint = ctx.accounts.lp_mint.key();
+    pool.fee_rate = fee_rate;
+    pool.paused = false;
+    pool.tokens = tokens;
+    pool.bump = ctx.bumps.pool;
+    pool.lp_mint_bump = ctx.bumps.lp_mint;
+
+    let pool_creation_fee = ctx.accounts.amm_config.pool_creation_fee;
+    if pool_creation_fee > 0 {
+        transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.authority.to_account_info(),
+                    to: ctx.accounts.fee_receiver.to_account_info(),
+                },
+            ),
+            pool_creation_fee,
+        )?;
+    }
+
+    let config = &mut ctx.accounts.amm_config;
+    let index = config.pool_count;
+    config.pool_count = index.checked_add(1).ok_or(AmmError::MathOverflow)?;
+
+    let record = &mut ctx.accounts.pool_record;
+    record.pool = ctx.accounts.pool.key();
+    record.index = index;
+    record.bump = ctx.bumps.pool_record;
+
+    emit_event!(
+        ctx,
+        MultiPoolInitialized {
+            pool: ctx.accounts.pool.key(),
+            index,
+            authority: ctx.accounts.authority.key(),
+            mints,
+            lp_mint: ctx.accounts.lp_mint.key(),
+            fee_rate,
+        }
+    );
+
+    Ok(())
+}
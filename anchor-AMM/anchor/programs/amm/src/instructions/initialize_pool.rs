@@ -1,26 +1,72 @@
-use anchor_lang::prelude::*;
+use anchor_lang::{
+    prelude::*,
+    system_program::{transfer, Transfer},
+};
 use anchor_spl::{
     associated_token::AssociatedToken,
     token_interface::{Mint, TokenAccount, TokenInterface},
 };
 
-use crate::states::Pool;
+use crate::{
+    errors::AmmError,
+    events::{emit_event, PoolInitialized},
+    math::{
+        stable_swap::{MAX_AMP, MIN_AMP},
+        weighted::MIN_WEIGHT,
+        BPS_DENOMINATOR,
+    },
+    states::{AmmConfig, CurveType, Oracle, Pool, PoolRecord},
+};
 
+#[cfg_attr(feature = "event-cpi", event_cpi)]
 #[derive(Accounts)]
+#[instruction(fee_rate: u16)]
 pub struct InitializePool<'info> {
     #[account(mut)]
     pub authority: Signer<'info>,
 
+    #[account(
+        mut,
+        seeds = [b"amm_config"],
+        bump = amm_config.bump,
+    )]
+    pub amm_config: Box<Account<'info, AmmConfig>>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + PoolRecord::INIT_SPACE,
+        seeds = [b"pool_record", amm_config.pool_count.to_le_bytes().as_ref()],
+        bump,
+    )]
+    pub pool_record: Box<Account<'info, PoolRecord>>,
+
+    /// CHECK: only receives the pool creation fee, and must be the config's receiver
+    #[account(mut, address = amm_config.fee_receiver)]
+    pub fee_receiver: UncheckedAccount<'info>,
+
     #[account(
         init,
         payer = authority,
         space = 8 + Pool::INIT_SPACE,
-        seeds = [b"pool", token_a_mint.key().as_ref(), token_b_mint.key().as_ref()],
+        seeds = [
+            b"pool",
+            token_a_mint.key().as_ref(),
+            token_b_mint.key().as_ref(),
+            &fee_rate.to_le_bytes(),
+        ],
         bump,
+        // Checked here, before the vaults are created, so identical mints fail
+        // with their own error. One canonical order gives every pair and fee
+        // tier exactly one pool address.
+        constraint = token_a_mint.key() != token_b_mint.key() @ AmmError::IdenticalMints,
+        constraint = token_a_mint.key() < token_b_mint.key() @ AmmError::UnsortedMints,
     )]
     pub pool: Account<'info, Pool>,
 
+    #[account(mint::token_program = token_a_program)]
     pub token_a_mint: InterfaceAccount<'info, Mint>,
+    #[account(mint::token_program = token_b_program)]
     pub token_b_mint: InterfaceAccount<'info, Mint>,
 
     #[account(
@@ -33,6 +79,24 @@ pub struct InitializePool<'info> {
     )]
     pub lp_mint: InterfaceAccount<'info, Mint>,
 
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + Oracle::INIT_SPACE,
+        seeds = [b"oracle", pool.key().as_ref()],
+        bump,
+    )]
+    pub oracle: Box<Account<'info, Oracle>>,
+
+    #[account(
+        init,
+        payer = authority,
+        associated_token::mint = lp_mint,
+        associated_token::authority = pool,
+        associated_token::token_program = token_program,
+    )]
+    pub locked_lp_vault: InterfaceAccount<'info, TokenAccount>,
+
     #[account(
         init,
         payer = authority,
@@ -59,18 +123,125 @@ pub struct InitializePool<'info> {
     pub system_program: Program<'info, System>,
 }
 
-pub fn initialize_pool(ctx: Context<InitializePool>, fee_rate: u16) -> Result<()> {
+pub fn initialize_pool(
+    ctx: Context<InitializePool>,
+    fee_rate: u16,
+    protocol_fee_rate: u16,
+    curve_type: CurveType,
+    amp: u64,
+    weight_a: u16,
+) -> Result<()> {
+    require!(
+        ctx.accounts.amm_config.fee_tiers.contains(&fee_rate),
+        AmmError::FeeTierNotAllowed
+    );
+    require!(
+        (protocol_fee_rate as u64) <= BPS_DENOMINATOR,
+        AmmError::InvalidFeeRate
+    );
+
+    let amp = match curve_type {
+        CurveType::ConstantProduct | CurveType::Weighted => 0,
+        CurveType::StableSwap => {
+            require!(
+                (MIN_AMP..=MAX_AMP).contains(&amp),
+                AmmError::InvalidAmplification
+            );
+            amp
+        }
+    };
+
+    let total_weight = BPS_DENOMINATOR as u16;
+    let weight_a = match curve_type {
+        CurveType::Weighted => {
+            require!(
+                (MIN_WEIGHT..=total_weight - MIN_WEIGHT).contains(&weight_a),
+                AmmError::InvalidWeight
+            );
+            weight_a
+        }
+        _ => total_weight / 2,
+    };
+    let weight_b = total_weight - weight_a;
+
     let pool = &mut ctx.accounts.pool;
 
     pool.authority = ctx.accounts.authority.key();
+    pool.pending_authority = None;
     pool.token_a_mint = ctx.accounts.token_a_mint.key();
     pool.token_b_mint = ctx.accounts.token_b_mint.key();
     pool.token_a_vault = ctx.accounts.token_a_vault.key();
     pool.token_b_vault = ctx.accounts.token_b_vault.key();
     pool.lp_mint = ctx.accounts.lp_mint.key();
     pool.fee_rate = fee_rate;
+    pool.fee_tier = fee_rate;
+    pool.curve_type = curve_type;
+    pool.amp_initial = amp;
+    pool.amp_target = amp;
+    pool.amp_ramp_start_ts = 0;
+    pool.amp_ramp_stop_ts = 0;
+    pool.weight_a = weight_a;
+    pool.weight_b = weight_b;
+    pool.protocol_fee_rate = protocol_fee_rate;
+    pool.protocol_fees_a = 0;
+    pool.protocol_fees_b = 0;
+    pool.flash_loan_active = false;
+    pool.flash_loan_amount_a = 0;
+    pool.flash_loan_amount_b = 0;
+    pool.paused = false;
+    pool.transfer_hook_programs = Vec::new();
+    pool.dynamic_fee = None;
+    pool.volatility = 0;
+    pool.volatility_updated_ts = 0;
     pool.bump = ctx.bumps.pool;
     pool.lp_mint_bump = ctx.bumps.lp_mint;
 
+    let pool_creation_fee = ctx.accounts.amm_config.pool_creation_fee;
+    if pool_creation_fee > 0 {
+        transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.authority.to_account_info(),
+                    to: ctx.accounts.fee_receiver.to_account_info(),
+                },
+            ),
+            pool_creation_fee,
+        )?;
+    }
+
+    let config = &mut ctx.accounts.amm_config;
+    let index = config.pool_count;
+    config.pool_count = index.checked_add(1).ok_or(AmmError::MathOverflow)?;
+
+    let record = &mut ctx.accounts.pool_record;
+    record.pool = ctx.accounts.pool.key();
+    record.index = index;
+    record.bump = ctx.bumps.pool_record;
+
+    ctx.accounts.oracle.initialize(
+        ctx.accounts.pool.key(),
+        Clock::get()?.unix_timestamp,
+        ctx.bumps.oracle,
+    );
+
+    emit_event!(
+        ctx,
+        PoolInitialized {
+            pool: ctx.accounts.pool.key(),
+            index,
+            authority: ctx.accounts.authority.key(),
+            token_a_mint: ctx.accounts.token_a_mint.key(),
+            token_b_mint: ctx.accounts.token_b_mint.key(),
+            lp_mint: ctx.accounts.lp_mint.key(),
+            fee_rate,
+            protocol_fee_rate,
+            curve_type,
+            amp,
+            weight_a,
+            weight_b,
+        }
+    );
+
     Ok(())
 }
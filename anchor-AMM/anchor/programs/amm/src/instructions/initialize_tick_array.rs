@@ -0,0 +1,60 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    errors::AmmError,
+    math::tick_math::{MAX_TICK, MIN_TICK},
+    states::{ClPool, Tick, TickArray, TICK_ARRAY_SIZE},
+};
+
+#[derive(Accounts)]
+#[instruction(start_tick_index: i32)]
+pub struct InitializeTickArray<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        seeds = [
+            b"cl_pool",
+            cl_pool.token_a_mint.as_ref(),
+            cl_pool.token_b_mint.as_ref(),
+            &cl_pool.fee_rate.to_le_bytes(),
+        ],
+        bump = cl_pool.bump,
+    )]
+    pub cl_pool: Account<'info, ClPool>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + TickArray::INIT_SPACE,
+        seeds = [b"tick_array", cl_pool.key().as_ref(), &start_tick_index.to_le_bytes()],
+        bump,
+    )]
+    pub tick_array: Box<Account<'info, TickArray>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+pub fn initialize_tick_array(
+    ctx: Context<InitializeTickArray>,
+    start_tick_index: i32,
+) -> Result<()> {
+    let tick_spacing = ctx.accounts.cl_pool.tick_spacing;
+
+    require!(
+        start_tick_index == TickArray::start_index_for(start_tick_index, tick_spacing),
+        AmmError::InvalidTickArray
+    );
+    require!(
+        start_tick_index >= TickArray::start_index_for(MIN_TICK, tick_spacing)
+            && start_tick_index <= MAX_TICK,
+        AmmError::InvalidTickArray
+    );
+
+    let tick_array = &mut ctx.accounts.tick_array;
+    tick_array.pool = ctx.accounts.cl_pool.key();
+    tick_array.start_tick_index = start_tick_index;
+    tick_array.ticks = [Tick::default(); TICK_ARRAY_SIZE];
+
+    Ok(())
+}
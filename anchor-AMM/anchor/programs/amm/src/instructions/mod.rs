@@ -1,11 +1,99 @@
-pub mod initialize_pool;
+pub mod accept_amm_admin;
+pub mod accept_authority;
 pub mod add_liquidity;
+pub mod add_multi_liquidity;
+pub mod cl_swap;
+pub mod claim_farm_rewards;
+pub mod close_farm_position;
+pub mod close_position;
+pub mod collect_position_fees;
+pub mod collect_protocol_fees;
+pub mod decrease_liquidity;
+pub mod flash_borrow;
+pub mod flash_repay;
+pub mod fund_farm;
+pub mod helper;
+pub mod increase_liquidity;
+pub mod initialize_amm_config;
+pub mod initialize_cl_pool;
+pub mod initialize_farm;
+pub mod initialize_multi_pool;
+pub mod initialize_pool;
+pub mod initialize_tick_array;
+pub mod multi_swap;
+pub mod multi_zap_in;
+pub mod multi_zap_out;
+pub mod observe;
+pub mod open_farm_position;
+pub mod open_position;
+pub mod propose_amm_admin;
+pub mod propose_authority;
+pub mod ramp_amp;
 pub mod remove_liquidity;
+pub mod remove_multi_liquidity;
+pub mod route_swap;
+pub mod set_cl_transfer_hook_programs;
+pub mod set_dynamic_fee;
+pub mod set_farm_reward_rate;
+pub mod set_fee_tiers;
+pub mod set_multi_pool_paused;
+pub mod set_paused;
+pub mod set_pool_creation_fee;
+pub mod set_transfer_hook_programs;
+pub mod stake;
 pub mod swap;
-pub mod helper;
+pub mod swap_exact_out;
+pub mod unstake;
+pub mod update_fee_rate;
+pub mod zap_in;
+pub mod zap_out;
 
-pub use initialize_pool::*;
+pub use accept_amm_admin::*;
+pub use accept_authority::*;
 pub use add_liquidity::*;
+pub use add_multi_liquidity::*;
+pub use cl_swap::*;
+pub use claim_farm_rewards::*;
+pub use close_farm_position::*;
+pub use close_position::*;
+pub use collect_position_fees::*;
+pub use collect_protocol_fees::*;
+pub use decrease_liquidity::*;
+pub use flash_borrow::*;
+pub use flash_repay::*;
+pub use fund_farm::*;
+pub use helper::*;
+pub use increase_liquidity::*;
+pub use initialize_amm_config::*;
+pub use initialize_cl_pool::*;
+pub use initialize_farm::*;
+pub use initialize_multi_pool::*;
+pub use initialize_pool::*;
+pub use initialize_tick_array::*;
+pub use multi_swap::*;
+pub use multi_zap_in::*;
+pub use multi_zap_out::*;
+pub use observe::*;
+pub use open_farm_position::*;
+pub use open_position::*;
+pub use propose_amm_admin::*;
+pub use propose_authority::*;
+pub use ramp_amp::*;
 pub use remove_liquidity::*;
+pub use remove_multi_liquidity::*;
+pub use route_swap::*;
+pub use set_cl_transfer_hook_programs::*;
+pub use set_dynamic_fee::*;
+pub use set_farm_reward_rate::*;
+pub use set_fee_tiers::*;
+pub use set_multi_pool_paused::*;
+pub use set_paused::*;
+pub use set_pool_creation_fee::*;
+pub use set_transfer_hook_programs::*;
+pub use stake::*;
 pub use swap::*;
-pub use helper::*;
\ No newline at end of file
+pub use swap_exact_out::*;
+pub use unstake::*;
+pub use update_fee_rate::*;
+pub use zap_in::*;
+pub use zap_out::*;
@@ -0,0 +1,142 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+
+use crate::{
+    errors::AmmError,
+    events::{emit_event, MultiSwapped},
+    instructions::{amount_received, check_deadline, transfer_tokens, transfer_tokens_from_vault},
+    math::{self, Curve},
+    states::MultiPool,
+};
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+pub struct MultiSwap<'info> {
+    pub user: Signer<'info>,
+
+    #[account(
+        seeds = [b"multi_pool", pool.mints_hash.as_ref()],
+        bump = pool.bump,
+        constraint = !pool.paused @ AmmError::PoolPaused,
+    )]
+    pub pool: Account<'info, MultiPool>,
+
+    #[account(mut)]
+    pub vault_in: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub vault_out: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = user_source.mint == mint_in.key() @ AmmError::InvalidTokenMint,
+        constraint = user_source.owner == user.key() @ AmmError::Unauthorized,
+    )]
+    pub user_source: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = user_destination.mint == mint_out.key() @ AmmError::InvalidTokenMint,
+        constraint = user_destination.owner == user.key() @ AmmError::Unauthorized,
+    )]
+    pub user_destination: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mint::token_program = token_in_program)]
+    pub mint_in: InterfaceAccount<'info, Mint>,
+    #[account(mint::token_program = token_out_program)]
+    pub mint_out: InterfaceAccount<'info, Mint>,
+
+    pub token_in_program: Interface<'info, TokenInterface>,
+    pub token_out_program: Interface<'info, TokenInterface>,
+}
+
+/// Swaps `amount_in` of one token of a multi-asset pool for another. With
+/// equal weights only the two reserves involved set the price, on the
+/// constant product curve.
+pub fn multi_swap<'info>(
+    ctx: Context<'_, '_, 'info, 'info, MultiSwap<'info>>,
+    amount_in: u64,
+    min_amount_out: u64,
+    deadline: Option<i64>,
+) -> Result<()> {
+    check_deadline(deadline)?;
+    require!(amount_in > 0, AmmError::InvalidAmount);
+
+    let pool = &ctx.accounts.pool;
+    let mint_in = &ctx.accounts.mint_in;
+    let mint_out = &ctx.accounts.mint_out;
+
+    require_keys_neq!(mint_in.key(), mint_out.key(), AmmError::IdenticalMints);
+    let token_in = pool.tokens[pool.token_index(&mint_in.key())?];
+    let token_out = pool.tokens[pool.token_index(&mint_out.key())?];
+    require_keys_eq!(
+        ctx.accounts.vault_in.key(),
+        token_in.vault,
+        AmmError::InvalidPoolState
+    );
+    require_keys_eq!(
+        ctx.accounts.vault_out.key(),
+        token_out.vault,
+        AmmError::InvalidPoolState
+    );
+
+    let reserve_in = ctx.accounts.vault_in.amount;
+    let reserve_out = ctx.accounts.vault_out.amount;
+    let swap = math::swap_exact_in(
+        Curve::ConstantProduct,
+        amount_received(mint_in, amount_in)?,
+        reserve_in,
+        reserve_out,
+        pool.fee_rate,
+        0,
+    )?;
+
+    // Slippage bounds apply to what lands in the user's account after any transfer fee
+    require!(
+        amount_received(mint_out, swap.amount_out)? >= min_amount_out,
+        AmmError::SlippageExceeded
+    );
+    require!(swap.amount_out > 0, AmmError::InvalidAmount);
+
+    transfer_tokens(
+        &ctx.accounts.user_source,
+        &ctx.accounts.vault_in,
+        &ctx.accounts.user,
+        &ctx.accounts.token_in_program,
+        mint_in,
+        &[],
+        amount_in,
+    )?;
+
+    let seeds = &[b"multi_pool", pool.mints_hash.as_ref(), &[pool.bump]];
+    let signer = &[&seeds[..]];
+
+    transfer_tokens_from_vault(
+        &ctx.accounts.vault_out,
+        &ctx.accounts.user_destination,
+        &pool.to_account_info(),
+        &ctx.accounts.token_out_program,
+        mint_out,
+        &[],
+        signer,
+        swap.amount_out,
+    )?;
+
+    let (reserve_in, reserve_out) = math::reserves_after_swap(&swap, reserve_in, reserve_out)?;
+    emit_event!(
+        ctx,
+        MultiSwapped {
+            pool: ctx.accounts.pool.key(),
+            user: ctx.accounts.user.key(),
+            mint_in: mint_in.key(),
+            mint_out: mint_out.key(),
+            amount_in: swap.amount_in,
+            amount_out: swap.amount_out,
+            fee: swap.fee,
+            reserve_in,
+            reserve_out,
+        }
+    );
+
+    Ok(())
+}
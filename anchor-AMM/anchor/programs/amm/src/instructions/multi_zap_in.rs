@@ -0,0 +1,89 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    errors::AmmError,
+    events::{emit_event, MultiLiquidityAdded},
+    instructions::{
+        amount_received, check_deadline, mint_lp_tokens, token_program_for, transfer_tokens,
+        AddMultiLiquidity, Constituent,
+    },
+    math::multi_asset,
+};
+
+/// Deposits `amount_in` of a single token of a multi-asset pool, passed as
+/// the only constituent in `remaining_accounts`. The swap fee is charged on
+/// the part a proportional deposit would have brought in other tokens.
+pub fn multi_zap_in<'info>(
+    ctx: Context<'_, '_, 'info, 'info, AddMultiLiquidity<'info>>,
+    amount_in: u64,
+    min_lp_tokens: u64,
+    deadline: Option<i64>,
+) -> Result<()> {
+    check_deadline(deadline)?;
+    require!(amount_in > 0, AmmError::InvalidAmount);
+
+    let pool = &ctx.accounts.pool;
+    let user = ctx.accounts.user.key();
+    let token = Constituent::load_one(pool, &user, ctx.remaining_accounts)?;
+
+    // A zap has no ratio of its own to seed an empty pool with
+    let lp_supply = ctx.accounts.lp_mint.supply;
+    require!(lp_supply > 0, AmmError::InsufficientLiquidity);
+
+    let received = amount_received(&token.mint, amount_in)?;
+    let (lp_tokens_to_mint, fee) = multi_asset::single_asset_deposit(
+        pool.tokens.len(),
+        received,
+        token.vault.amount,
+        lp_supply,
+        pool.fee_rate,
+    )?;
+
+    require!(
+        lp_tokens_to_mint >= min_lp_tokens,
+        AmmError::SlippageExceeded
+    );
+    require!(lp_tokens_to_mint > 0, AmmError::InvalidAmount);
+
+    transfer_tokens(
+        &token.user_token,
+        &token.vault,
+        &ctx.accounts.user,
+        token_program_for(
+            &token.mint,
+            &ctx.accounts.token_program,
+            &ctx.accounts.token_2022_program,
+        ),
+        &token.mint,
+        &[],
+        amount_in,
+    )?;
+
+    let seeds = &[b"multi_pool", pool.mints_hash.as_ref(), &[pool.bump]];
+    let signer = &[&seeds[..]];
+
+    mint_lp_tokens(
+        &ctx.accounts.lp_mint,
+        &ctx.accounts.user_lp_token,
+        &pool.to_account_info(),
+        &ctx.accounts.token_program,
+        signer,
+        lp_tokens_to_mint,
+    )?;
+
+    let mut amounts = vec![0; pool.tokens.len()];
+    amounts[token.index] = received;
+    emit_event!(
+        ctx,
+        MultiLiquidityAdded {
+            pool: ctx.accounts.pool.key(),
+            user,
+            amounts,
+            lp_tokens: lp_tokens_to_mint,
+            fee,
+            lp_supply: lp_supply + lp_tokens_to_mint,
+        }
+    );
+
+    Ok(())
+}
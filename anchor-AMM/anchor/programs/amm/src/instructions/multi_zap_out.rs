@@ -0,0 +1,60 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    errors::AmmError,
+    events::{emit_event, MultiLiquidityRemoved},
+    instructions::{amount_received, check_deadline, Constituent, RemoveMultiLiquidity},
+    math::multi_asset,
+};
+
+/// Burns `lp_tokens` for a single token of a multi-asset pool, passed as the
+/// only constituent in `remaining_accounts`. The swap fee is kept from the
+/// part a proportional withdrawal would have paid in other tokens.
+pub fn multi_zap_out<'info>(
+    ctx: Context<'_, '_, 'info, 'info, RemoveMultiLiquidity<'info>>,
+    lp_tokens: u64,
+    min_amount_out: u64,
+    deadline: Option<i64>,
+) -> Result<()> {
+    check_deadline(deadline)?;
+    require!(lp_tokens > 0, AmmError::InvalidAmount);
+
+    let pool = &ctx.accounts.pool;
+    let user = ctx.accounts.user.key();
+    let token = Constituent::load_one(pool, &user, ctx.remaining_accounts)?;
+
+    let lp_supply = ctx.accounts.lp_mint.supply;
+    let (amount_out, fee) = multi_asset::single_asset_withdraw(
+        pool.tokens.len(),
+        lp_tokens,
+        token.vault.amount,
+        lp_supply,
+        pool.fee_rate,
+    )?;
+
+    // Slippage bounds apply to what lands in the user's account after any transfer fee
+    require!(
+        amount_received(&token.mint, amount_out)? >= min_amount_out,
+        AmmError::SlippageExceeded
+    );
+    require!(amount_out > 0, AmmError::InvalidAmount);
+
+    ctx.accounts.burn_lp_tokens(lp_tokens)?;
+    ctx.accounts.pay_out(&token, amount_out)?;
+
+    let mut amounts = vec![0; ctx.accounts.pool.tokens.len()];
+    amounts[token.index] = amount_out;
+    emit_event!(
+        ctx,
+        MultiLiquidityRemoved {
+            pool: ctx.accounts.pool.key(),
+            user,
+            amounts,
+            lp_tokens,
+            fee,
+            lp_supply: lp_supply - lp_tokens,
+        }
+    );
+
+    Ok(())
+}
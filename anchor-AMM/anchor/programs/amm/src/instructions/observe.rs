@@ -0,0 +1,59 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::TokenAccount;
+
+use crate::{
+    errors::AmmError,
+    states::{Observation, Oracle, Pool, MAX_OBSERVE_ENTRIES},
+};
+
+#[derive(Accounts)]
+pub struct Observe<'info> {
+    #[account(
+        seeds = [
+            b"pool",
+            pool.token_a_mint.as_ref(),
+            pool.token_b_mint.as_ref(),
+            &pool.fee_tier.to_le_bytes(),
+        ],
+        bump = pool.bump,
+    )]
+    pub pool: Account<'info, Pool>,
+
+    #[account(
+        seeds = [b"oracle", pool.key().as_ref()],
+        bump = oracle.bump,
+    )]
+    pub oracle: Box<Account<'info, Oracle>>,
+
+    #[account(address = pool.token_a_vault @ AmmError::InvalidPoolState)]
+    pub token_a_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(address = pool.token_b_vault @ AmmError::InvalidPoolState)]
+    pub token_b_vault: InterfaceAccount<'info, TokenAccount>,
+}
+
+/// Returns the price cumulatives `seconds_ago` seconds before now, one per
+/// entry, through the instruction's return data. Callers derive a TWAP from
+/// the difference between two of them. At most `MAX_OBSERVE_ENTRIES` fit.
+pub fn observe(ctx: Context<Observe>, seconds_ago: Vec<u32>) -> Result<Vec<Observation>> {
+    require!(
+        !seconds_ago.is_empty() && seconds_ago.len() <= MAX_OBSERVE_ENTRIES,
+        AmmError::InvalidAmount
+    );
+
+    let (reserve_a, reserve_b) = ctx.accounts.pool.reserves(
+        ctx.accounts.token_a_vault.amount,
+        ctx.accounts.token_b_vault.amount,
+    )?;
+    let (price_a, price_b) = ctx.accounts.pool.spot_prices_x64(reserve_a, reserve_b);
+    let now = Clock::get()?.unix_timestamp;
+
+    seconds_ago
+        .iter()
+        .map(|ago| {
+            ctx.accounts
+                .oracle
+                .observe_at(now - *ago as i64, price_a, price_b)
+        })
+        .collect()
+}
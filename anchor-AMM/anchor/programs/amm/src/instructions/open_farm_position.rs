@@ -0,0 +1,52 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    events::{emit_event, FarmPositionOpened},
+    states::{Farm, FarmPosition},
+};
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+pub struct OpenFarmPosition<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [b"farm", farm.pool.as_ref(), farm.reward_mint.as_ref()],
+        bump = farm.bump,
+    )]
+    pub farm: Account<'info, Farm>,
+
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + FarmPosition::INIT_SPACE,
+        seeds = [b"farm_position", farm.key().as_ref(), owner.key().as_ref()],
+        bump,
+    )]
+    pub position: Account<'info, FarmPosition>,
+
+    pub system_program: Program<'info, System>,
+}
+
+pub fn open_farm_position(ctx: Context<OpenFarmPosition>) -> Result<()> {
+    let position = &mut ctx.accounts.position;
+
+    position.farm = ctx.accounts.farm.key();
+    position.owner = ctx.accounts.owner.key();
+    position.amount = 0;
+    position.reward_per_share_last_x64 = 0;
+    position.rewards_owed = 0;
+    position.bump = ctx.bumps.position;
+
+    emit_event!(
+        ctx,
+        FarmPositionOpened {
+            farm: ctx.accounts.farm.key(),
+            position: ctx.accounts.position.key(),
+            owner: ctx.accounts.owner.key(),
+        }
+    );
+
+    Ok(())
+}
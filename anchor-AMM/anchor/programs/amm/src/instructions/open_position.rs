@@ -0,0 +1,82 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    errors::AmmError,
+    events::{emit_event, PositionOpened},
+    math::tick_math::{MAX_TICK, MIN_TICK},
+    states::{ClPool, Position},
+};
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+#[instruction(tick_lower: i32, tick_upper: i32)]
+pub struct OpenPosition<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [
+            b"cl_pool",
+            cl_pool.token_a_mint.as_ref(),
+            cl_pool.token_b_mint.as_ref(),
+            &cl_pool.fee_rate.to_le_bytes(),
+        ],
+        bump = cl_pool.bump,
+    )]
+    pub cl_pool: Account<'info, ClPool>,
+
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + Position::INIT_SPACE,
+        seeds = [
+            b"position",
+            cl_pool.key().as_ref(),
+            owner.key().as_ref(),
+            &tick_lower.to_le_bytes(),
+            &tick_upper.to_le_bytes(),
+        ],
+        bump,
+    )]
+    pub position: Account<'info, Position>,
+
+    pub system_program: Program<'info, System>,
+}
+
+pub fn open_position(ctx: Context<OpenPosition>, tick_lower: i32, tick_upper: i32) -> Result<()> {
+    let tick_spacing = ctx.accounts.cl_pool.tick_spacing as i32;
+
+    require!(
+        tick_lower < tick_upper && tick_lower >= MIN_TICK && tick_upper <= MAX_TICK,
+        AmmError::InvalidTickIndex
+    );
+    require!(
+        tick_lower % tick_spacing == 0 && tick_upper % tick_spacing == 0,
+        AmmError::InvalidTickIndex
+    );
+
+    let position = &mut ctx.accounts.position;
+    position.pool = ctx.accounts.cl_pool.key();
+    position.owner = ctx.accounts.owner.key();
+    position.tick_lower = tick_lower;
+    position.tick_upper = tick_upper;
+    position.liquidity = 0;
+    position.fee_growth_inside_a_last_x64 = 0;
+    position.fee_growth_inside_b_last_x64 = 0;
+    position.tokens_owed_a = 0;
+    position.tokens_owed_b = 0;
+    position.bump = ctx.bumps.position;
+
+    emit_event!(
+        ctx,
+        PositionOpened {
+            pool: ctx.accounts.cl_pool.key(),
+            position: ctx.accounts.position.key(),
+            owner: ctx.accounts.owner.key(),
+            tick_lower,
+            tick_upper,
+        }
+    );
+
+    Ok(())
+}
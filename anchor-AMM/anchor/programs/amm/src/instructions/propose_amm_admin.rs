@@ -0,0 +1,16 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    events::{emit_event, AmmConfigUpdated},
+    instructions::UpdateAmmConfig,
+};
+
+/// First step of an admin transfer. Proposing again replaces the pending
+/// admin, and proposing `None` cancels the transfer.
+pub fn propose_amm_admin(ctx: Context<UpdateAmmConfig>, new_admin: Option<Pubkey>) -> Result<()> {
+    ctx.accounts.amm_config.pending_admin = new_admin;
+
+    emit_event!(ctx, AmmConfigUpdated::new(&ctx.accounts.amm_config));
+
+    Ok(())
+}
@@ -0,0 +1,23 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    events::{emit_event, AuthorityProposed},
+    instructions::UpdatePool,
+};
+
+/// First step of an authority transfer. Proposing again replaces the pending
+/// authority, and proposing `None` cancels the transfer.
+pub fn propose_authority(ctx: Context<UpdatePool>, new_authority: Option<Pubkey>) -> Result<()> {
+    ctx.accounts.pool.pending_authority = new_authority;
+
+    emit_event!(
+        ctx,
+        AuthorityProposed {
+            pool: ctx.accounts.pool.key(),
+            authority: ctx.accounts.authority.key(),
+            pending_authority: new_authority,
+        }
+    );
+
+    Ok(())
+}
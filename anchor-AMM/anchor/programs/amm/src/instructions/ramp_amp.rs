@@ -0,0 +1,70 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    errors::AmmError,
+    events::{emit_event, AmpRampStarted},
+    math::stable_swap::{MAX_AMP, MAX_AMP_CHANGE, MIN_AMP, MIN_RAMP_DURATION},
+    states::{CurveType, Pool},
+};
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+pub struct RampAmp<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"pool",
+            pool.token_a_mint.as_ref(),
+            pool.token_b_mint.as_ref(),
+            &pool.fee_tier.to_le_bytes(),
+        ],
+        bump = pool.bump,
+        has_one = authority @ AmmError::Unauthorized,
+    )]
+    pub pool: Account<'info, Pool>,
+}
+
+pub fn ramp_amp(ctx: Context<RampAmp>, target_amp: u64, ramp_stop_ts: i64) -> Result<()> {
+    let pool = &mut ctx.accounts.pool;
+
+    require!(
+        pool.curve_type == CurveType::StableSwap,
+        AmmError::InvalidPoolState
+    );
+    require!(
+        (MIN_AMP..=MAX_AMP).contains(&target_amp),
+        AmmError::InvalidAmplification
+    );
+
+    let now = Clock::get()?.unix_timestamp;
+    require!(
+        ramp_stop_ts >= now.saturating_add(MIN_RAMP_DURATION),
+        AmmError::InvalidAmpRamp
+    );
+
+    // A new ramp starts from wherever the current one has got to
+    let current_amp = pool.current_amp(now);
+    require!(
+        target_amp <= current_amp.saturating_mul(MAX_AMP_CHANGE)
+            && target_amp.saturating_mul(MAX_AMP_CHANGE) >= current_amp,
+        AmmError::InvalidAmpRamp
+    );
+
+    pool.amp_initial = current_amp;
+    pool.amp_target = target_amp;
+    pool.amp_ramp_start_ts = now;
+    pool.amp_ramp_stop_ts = ramp_stop_ts;
+
+    let ramp_started = AmpRampStarted {
+        pool: pool.key(),
+        amp_initial: current_amp,
+        amp_target: target_amp,
+        ramp_start_ts: now,
+        ramp_stop_ts,
+    };
+    emit_event!(ctx, ramp_started);
+
+    Ok(())
+}
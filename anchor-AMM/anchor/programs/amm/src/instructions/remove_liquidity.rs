@@ -1,13 +1,17 @@
 use anchor_lang::prelude::*;
-use anchor_spl::{
-    token_interface::{
-        burn, Burn, Mint, TokenAccount, TokenInterface,
+use anchor_spl::token_interface::{burn, Burn, Mint, TokenAccount, TokenInterface};
+
+use crate::states::{Oracle, Pool};
+use crate::{
+    errors::AmmError,
+    events::{emit_event, LiquidityRemoved},
+    instructions::{
+        amount_received, check_deadline, transfer_hook_program, transfer_tokens_from_vault,
     },
+    math::{self, MINIMUM_LIQUIDITY},
 };
 
-use crate::{errors::AmmError, instructions::transfer_tokens_from_vault};
-use crate::states::Pool;
-
+#[cfg_attr(feature = "event-cpi", event_cpi)]
 #[derive(Accounts)]
 pub struct RemoveLiquidity<'info> {
     #[account(mut)]
@@ -15,11 +19,23 @@ pub struct RemoveLiquidity<'info> {
 
     #[account(
         mut,
-        seeds = [b"pool", pool.token_a_mint.as_ref(), pool.token_b_mint.as_ref()],
+        seeds = [
+            b"pool",
+            pool.token_a_mint.as_ref(),
+            pool.token_b_mint.as_ref(),
+            &pool.fee_tier.to_le_bytes(),
+        ],
         bump = pool.bump,
+        constraint = !pool.flash_loan_active @ AmmError::FlashLoanActive,
     )]
     pub pool: Account<'info, Pool>,
 
+    #[account(
+        mut,
+        seeds = [b"oracle", pool.key().as_ref()],
+        bump = oracle.bump,
+    )]
+    pub oracle: Box<Account<'info, Oracle>>,
 
     #[account(
         mut,
@@ -30,66 +46,101 @@ pub struct RemoveLiquidity<'info> {
 
     #[account(
         mut,
+        address = pool.token_a_vault @ AmmError::InvalidPoolState,
     )]
     pub token_a_vault: InterfaceAccount<'info, TokenAccount>,
 
     #[account(
         mut,
+        address = pool.token_b_vault @ AmmError::InvalidPoolState,
     )]
     pub token_b_vault: InterfaceAccount<'info, TokenAccount>,
 
     #[account(
         mut,
+        constraint = user_token_a.mint == token_a_mint.key() @ AmmError::InvalidTokenMint,
+        constraint = user_token_a.owner == user.key() @ AmmError::Unauthorized,
     )]
     pub user_token_a: InterfaceAccount<'info, TokenAccount>,
 
     #[account(
         mut,
+        constraint = user_token_b.mint == token_b_mint.key() @ AmmError::InvalidTokenMint,
+        constraint = user_token_b.owner == user.key() @ AmmError::Unauthorized,
     )]
     pub user_token_b: InterfaceAccount<'info, TokenAccount>,
 
     #[account(
         mut,
+        constraint = user_lp_token.mint == lp_mint.key() @ AmmError::InvalidTokenMint,
+        constraint = user_lp_token.owner == user.key() @ AmmError::Unauthorized,
     )]
     pub user_lp_token: InterfaceAccount<'info, TokenAccount>,
 
+    #[account(
+        address = pool.token_a_mint @ AmmError::InvalidTokenMint,
+        mint::token_program = token_a_program,
+        constraint = pool.accepts_transfer_hook(transfer_hook_program(&token_a_mint))
+            @ AmmError::TransferHookNotAllowed,
+    )]
     pub token_a_mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        address = pool.token_b_mint @ AmmError::InvalidTokenMint,
+        mint::token_program = token_b_program,
+        constraint = pool.accepts_transfer_hook(transfer_hook_program(&token_b_mint))
+            @ AmmError::TransferHookNotAllowed,
+    )]
     pub token_b_mint: InterfaceAccount<'info, Mint>,
+
+    pub token_a_program: Interface<'info, TokenInterface>,
+    pub token_b_program: Interface<'info, TokenInterface>,
+
+    /// Token program of the LP mint
     pub token_program: Interface<'info, TokenInterface>,
 }
 
-pub fn remove_liquidity(
-    ctx: Context<RemoveLiquidity>,
+pub fn remove_liquidity<'info>(
+    ctx: Context<'_, '_, 'info, 'info, RemoveLiquidity<'info>>,
     lp_tokens: u64,
     min_amount_a: u64,
     min_amount_b: u64,
+    deadline: Option<i64>,
 ) -> Result<()> {
+    check_deadline(deadline)?;
+
     let pool = &ctx.accounts.pool;
 
     require!(lp_tokens > 0, AmmError::InvalidAmount);
 
-    let reserve_a = ctx.accounts.token_a_vault.amount;
-    let reserve_b = ctx.accounts.token_b_vault.amount;
+    let (reserve_a, reserve_b) = pool.reserves(
+        ctx.accounts.token_a_vault.amount,
+        ctx.accounts.token_b_vault.amount,
+    )?;
+
+    let now = Clock::get()?.unix_timestamp;
+    let (price_a, price_b) = pool.spot_prices_x64(reserve_a, reserve_b);
+    ctx.accounts.oracle.update(now, price_a, price_b);
+
     let lp_supply = ctx.accounts.lp_mint.supply;
 
-    require!(lp_supply > 0, AmmError::InsufficientLiquidity);
-    require!(lp_tokens <= lp_supply, AmmError::InvalidAmount);
+    // The locked minimum liquidity can never be withdrawn
+    require!(
+        lp_supply.saturating_sub(lp_tokens) >= MINIMUM_LIQUIDITY,
+        AmmError::InsufficientLiquidity
+    );
 
     // Calculate proportional amounts to withdraw
-    let amount_a = lp_tokens
-        .checked_mul(reserve_a)
-        .unwrap()
-        .checked_div(lp_supply)
-        .unwrap();
-
-    let amount_b = lp_tokens
-        .checked_mul(reserve_b)
-        .unwrap()
-        .checked_div(lp_supply)
-        .unwrap();
-
-    require!(amount_a >= min_amount_a, AmmError::SlippageExceeded);
-    require!(amount_b >= min_amount_b, AmmError::SlippageExceeded);
+    let (amount_a, amount_b) = math::withdraw_amounts(lp_tokens, reserve_a, reserve_b, lp_supply)?;
+
+    // Slippage bounds apply to what lands in the user's accounts after any transfer fee
+    require!(
+        amount_received(&ctx.accounts.token_a_mint, amount_a)? >= min_amount_a,
+        AmmError::SlippageExceeded
+    );
+    require!(
+        amount_received(&ctx.accounts.token_b_mint, amount_b)? >= min_amount_b,
+        AmmError::SlippageExceeded
+    );
     require!(amount_a > 0 && amount_b > 0, AmmError::InvalidAmount);
 
     // Burn LP tokens from user
@@ -105,10 +156,12 @@ pub fn remove_liquidity(
     burn(cpi_ctx, lp_tokens)?;
 
     // Transfer tokens from pool to user
+    let fee_tier_bytes = pool.fee_tier.to_le_bytes();
     let seeds = &[
         b"pool",
         pool.token_a_mint.as_ref(),
         pool.token_b_mint.as_ref(),
+        fee_tier_bytes.as_ref(),
         &[pool.bump],
     ];
     let signer = &[&seeds[..]];
@@ -117,8 +170,9 @@ pub fn remove_liquidity(
         &ctx.accounts.token_a_vault,
         &ctx.accounts.user_token_a,
         &ctx.accounts.pool.to_account_info(),
-        &ctx.accounts.token_program,
+        &ctx.accounts.token_a_program,
         &ctx.accounts.token_a_mint,
+        ctx.remaining_accounts,
         signer,
         amount_a,
     )?;
@@ -127,11 +181,26 @@ pub fn remove_liquidity(
         &ctx.accounts.token_b_vault,
         &ctx.accounts.user_token_b,
         &ctx.accounts.pool.to_account_info(),
-        &ctx.accounts.token_program,
+        &ctx.accounts.token_b_program,
         &ctx.accounts.token_b_mint,
+        ctx.remaining_accounts,
         signer,
         amount_b,
     )?;
 
+    emit_event!(
+        ctx,
+        LiquidityRemoved {
+            pool: ctx.accounts.pool.key(),
+            user: ctx.accounts.user.key(),
+            amount_a,
+            amount_b,
+            lp_tokens,
+            reserve_a: reserve_a - amount_a,
+            reserve_b: reserve_b - amount_b,
+            lp_supply: lp_supply - lp_tokens,
+        }
+    );
+
     Ok(())
 }
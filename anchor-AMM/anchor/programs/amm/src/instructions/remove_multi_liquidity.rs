@@ -0,0 +1,143 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{burn, Burn, Mint, TokenAccount, TokenInterface};
+
+use crate::{
+    errors::AmmError,
+    events::{emit_event, MultiLiquidityRemoved},
+    instructions::{
+        amount_received, check_deadline, token_program_for, transfer_tokens_from_vault, Constituent,
+    },
+    math::{multi_asset, MINIMUM_LIQUIDITY},
+    states::MultiPool,
+};
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+pub struct RemoveMultiLiquidity<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        seeds = [b"multi_pool", pool.mints_hash.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Account<'info, MultiPool>,
+
+    #[account(
+        mut,
+        seeds = [b"lp_mint", pool.key().as_ref()],
+        bump = pool.lp_mint_bump,
+    )]
+    pub lp_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        constraint = user_lp_token.mint == lp_mint.key() @ AmmError::InvalidTokenMint,
+        constraint = user_lp_token.owner == user.key() @ AmmError::Unauthorized,
+    )]
+    pub user_lp_token: InterfaceAccount<'info, TokenAccount>,
+
+    /// SPL Token program, which also owns the LP mint
+    #[account(address = anchor_spl::token::ID)]
+    pub token_program: Interface<'info, TokenInterface>,
+
+    /// Token-2022 program, used for every constituent mint it owns
+    #[account(address = anchor_spl::token_2022::ID)]
+    pub token_2022_program: Interface<'info, TokenInterface>,
+}
+
+impl<'info> RemoveMultiLiquidity<'info> {
+    /// Burns `lp_tokens` from the user, keeping the locked minimum liquidity
+    /// out of reach.
+    pub fn burn_lp_tokens(&self, lp_tokens: u64) -> Result<()> {
+        require!(
+            self.lp_mint.supply.saturating_sub(lp_tokens) >= MINIMUM_LIQUIDITY,
+            AmmError::InsufficientLiquidity
+        );
+
+        let cpi_accounts = Burn {
+            mint: self.lp_mint.to_account_info(),
+            from: self.user_lp_token.to_account_info(),
+            authority: self.user.to_account_info(),
+        };
+        burn(
+            CpiContext::new(self.token_program.to_account_info(), cpi_accounts),
+            lp_tokens,
+        )
+    }
+
+    /// Pays `amount` of `token` out of its vault to the user.
+    pub fn pay_out(&self, token: &Constituent<'info>, amount: u64) -> Result<()> {
+        let pool = &self.pool;
+        let seeds = &[b"multi_pool", pool.mints_hash.as_ref(), &[pool.bump]];
+        let signer = &[&seeds[..]];
+
+        transfer_tokens_from_vault(
+            &token.vault,
+            &token.user_token,
+            &pool.to_account_info(),
+            token_program_for(&token.mint, &self.token_program, &self.token_2022_program),
+            &token.mint,
+            &[],
+            signer,
+            amount,
+        )
+    }
+}
+
+/// Burns `lp_tokens` for a proportional share of every token in a
+/// multi-asset pool. `min_amounts` are in the pool's token order.
+pub fn remove_multi_liquidity<'info>(
+    ctx: Context<'_, '_, 'info, 'info, RemoveMultiLiquidity<'info>>,
+    lp_tokens: u64,
+    min_amounts: Vec<u64>,
+    deadline: Option<i64>,
+) -> Result<()> {
+    check_deadline(deadline)?;
+    require!(lp_tokens > 0, AmmError::InvalidAmount);
+
+    let user = ctx.accounts.user.key();
+    let constituents = Constituent::load_all(&ctx.accounts.pool, &user, ctx.remaining_accounts)?;
+    require!(
+        min_amounts.len() == constituents.len(),
+        AmmError::InvalidTokenCount
+    );
+
+    let reserves: Vec<u64> = constituents
+        .iter()
+        .map(|token| token.vault.amount)
+        .collect();
+    let lp_supply = ctx.accounts.lp_mint.supply;
+    let amounts = multi_asset::withdraw_amounts(lp_tokens, &reserves, lp_supply)?;
+
+    // Slippage bounds apply to what lands in the user's accounts after any transfer fee
+    for ((token, amount), min_amount) in constituents.iter().zip(&amounts).zip(&min_amounts) {
+        require!(
+            amount_received(&token.mint, *amount)? >= *min_amount,
+            AmmError::SlippageExceeded
+        );
+    }
+    require!(
+        amounts.iter().any(|amount| *amount > 0),
+        AmmError::InvalidAmount
+    );
+
+    ctx.accounts.burn_lp_tokens(lp_tokens)?;
+    for (token, amount) in constituents.iter().zip(&amounts) {
+        ctx.accounts.pay_out(token, *amount)?;
+    }
+
+    emit_event!(
+        ctx,
+        MultiLiquidityRemoved {
+            pool: ctx.accounts.pool.key(),
+            user,
+            amounts,
+            lp_tokens,
+            fee: 0,
+            lp_supply: lp_supply - lp_tokens,
+        }
+    );
+
+    Ok(())
+}
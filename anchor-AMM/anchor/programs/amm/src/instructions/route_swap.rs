@@ -0,0 +1,302 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+
+use crate::{
+    errors::AmmError,
+    events::{emit_event, Swapped},
+    instructions::{
+        amount_received, check_deadline, token_program_for, transfer_hook_program, transfer_tokens,
+        transfer_tokens_from_vault,
+    },
+    math,
+    states::{Oracle, Pool},
+};
+
+/// Accounts passed in `remaining_accounts` for each of the `hop_count` hops,
+/// in order: pool, oracle, input vault, output vault, input mint, output
+/// mint. Extra accounts for Token-2022 transfer hooks follow the last hop.
+pub const ACCOUNTS_PER_HOP: usize = 6;
+
+/// Longest route a single instruction accepts.
+pub const MAX_HOPS: usize = 4;
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+pub struct RouteSwap<'info> {
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = user_source.owner == user.key() @ AmmError::Unauthorized,
+    )]
+    pub user_source: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = user_destination.owner == user.key() @ AmmError::Unauthorized,
+    )]
+    pub user_destination: InterfaceAccount<'info, TokenAccount>,
+
+    /// SPL Token program, used for every mint it owns along the route
+    #[account(address = anchor_spl::token::ID)]
+    pub token_program: Interface<'info, TokenInterface>,
+
+    /// Token-2022 program, used for every mint it owns along the route
+    #[account(address = anchor_spl::token_2022::ID)]
+    pub token_2022_program: Interface<'info, TokenInterface>,
+}
+
+/// One leg of a route, priced before any tokens move.
+struct Hop<'info> {
+    pool: Account<'info, Pool>,
+    oracle: Box<Account<'info, Oracle>>,
+    vault_in: InterfaceAccount<'info, TokenAccount>,
+    vault_out: InterfaceAccount<'info, TokenAccount>,
+    mint_in: InterfaceAccount<'info, Mint>,
+    mint_out: InterfaceAccount<'info, Mint>,
+    a_to_b: bool,
+    amount_out: u64,
+    /// Filled in by `quote` for the hop's `Swapped` event.
+    swapped: Option<Swapped>,
+}
+
+impl<'info> Hop<'info> {
+    fn load(accounts: &'info [AccountInfo<'info>]) -> Result<Self> {
+        let pool = Account::<Pool>::try_from(&accounts[0])?;
+        let oracle = Box::new(Account::<Oracle>::try_from(&accounts[1])?);
+        let vault_in = InterfaceAccount::<TokenAccount>::try_from(&accounts[2])?;
+        let vault_out = InterfaceAccount::<TokenAccount>::try_from(&accounts[3])?;
+        let mint_in = InterfaceAccount::<Mint>::try_from(&accounts[4])?;
+        let mint_out = InterfaceAccount::<Mint>::try_from(&accounts[5])?;
+
+        require_keys_eq!(oracle.pool, pool.key(), AmmError::InvalidPoolState);
+        require!(!pool.flash_loan_active, AmmError::FlashLoanActive);
+        require!(!pool.paused, AmmError::PoolPaused);
+
+        let a_to_b = if vault_in.key() == pool.token_a_vault
+            && vault_out.key() == pool.token_b_vault
+        {
+            true
+        } else if vault_in.key() == pool.token_b_vault && vault_out.key() == pool.token_a_vault {
+            false
+        } else {
+            return err!(AmmError::InvalidPoolState);
+        };
+
+        let (expected_mint_in, expected_mint_out) = if a_to_b {
+            (pool.token_a_mint, pool.token_b_mint)
+        } else {
+            (pool.token_b_mint, pool.token_a_mint)
+        };
+        require_keys_eq!(mint_in.key(), expected_mint_in, AmmError::InvalidTokenMint);
+        require_keys_eq!(
+            mint_out.key(),
+            expected_mint_out,
+            AmmError::InvalidTokenMint
+        );
+        require!(
+            pool.accepts_transfer_hook(transfer_hook_program(&mint_in))
+                && pool.accepts_transfer_hook(transfer_hook_program(&mint_out)),
+            AmmError::TransferHookNotAllowed
+        );
+
+        Ok(Self {
+            pool,
+            oracle,
+            vault_in,
+            vault_out,
+            mint_in,
+            mint_out,
+            a_to_b,
+            amount_out: 0,
+            swapped: None,
+        })
+    }
+
+    /// Prices this hop against the vault balances loaded before the route ran.
+    /// `amount_in` is what was sent to the input vault, before any transfer fee.
+    fn quote(&mut self, user: Pubkey, amount_in: u64, now: i64) -> Result<()> {
+        let (vault_a, vault_b) = if self.a_to_b {
+            (&self.vault_in, &self.vault_out)
+        } else {
+            (&self.vault_out, &self.vault_in)
+        };
+        let (reserve_a, reserve_b) = self.pool.reserves(vault_a.amount, vault_b.amount)?;
+        let (price_a, price_b) = self.pool.spot_prices_x64(reserve_a, reserve_b);
+        self.oracle.update(now, price_a, price_b);
+
+        let (reserve_in, reserve_out) = if self.a_to_b {
+            (reserve_a, reserve_b)
+        } else {
+            (reserve_b, reserve_a)
+        };
+
+        let swap = math::swap_exact_in(
+            self.pool.swap_curve(now, self.a_to_b),
+            amount_received(&self.mint_in, amount_in)?,
+            reserve_in,
+            reserve_out,
+            self.pool.swap_fee_rate(now),
+            self.pool.protocol_fee_rate,
+        )?;
+
+        self.pool
+            .accrue_protocol_fee(swap.protocol_fee, self.a_to_b)?;
+        self.pool
+            .record_swap(&swap, self.a_to_b, reserve_in, reserve_out, now)?;
+        self.amount_out = swap.amount_out;
+        self.swapped = Some(Swapped::new(
+            self.pool.key(),
+            user,
+            self.a_to_b,
+            &swap,
+            reserve_in,
+            reserve_out,
+        )?);
+        Ok(())
+    }
+
+    /// Pays this hop's output out of its vault into `to`.
+    fn pay_out(
+        &self,
+        to: &InterfaceAccount<'info, TokenAccount>,
+        token_program: &Interface<'info, TokenInterface>,
+        remaining_accounts: &[AccountInfo<'info>],
+    ) -> Result<()> {
+        let pool = &self.pool;
+        let fee_tier_bytes = pool.fee_tier.to_le_bytes();
+        let seeds = &[
+            b"pool",
+            pool.token_a_mint.as_ref(),
+            pool.token_b_mint.as_ref(),
+            fee_tier_bytes.as_ref(),
+            &[pool.bump],
+        ];
+        let signer = &[&seeds[..]];
+
+        transfer_tokens_from_vault(
+            &self.vault_out,
+            to,
+            &pool.to_account_info(),
+            token_program,
+            &self.mint_out,
+            remaining_accounts,
+            signer,
+            self.amount_out,
+        )
+    }
+}
+
+pub fn route_swap<'info>(
+    ctx: Context<'_, '_, 'info, 'info, RouteSwap<'info>>,
+    amount_in: u64,
+    min_amount_out: u64,
+    hop_count: u8,
+    deadline: Option<i64>,
+) -> Result<()> {
+    check_deadline(deadline)?;
+    require!(amount_in > 0, AmmError::InvalidAmount);
+
+    let remaining_accounts = ctx.remaining_accounts;
+    let hop_count = hop_count as usize;
+    require!(
+        hop_count > 0 && hop_count <= MAX_HOPS,
+        AmmError::InvalidRoute
+    );
+    require!(
+        hop_count * ACCOUNTS_PER_HOP <= remaining_accounts.len(),
+        AmmError::InvalidRoute
+    );
+
+    let mut hops = remaining_accounts
+        .chunks_exact(ACCOUNTS_PER_HOP)
+        .take(hop_count)
+        .map(Hop::load)
+        .collect::<Result<Vec<_>>>()?;
+
+    // Each pool is priced from balances loaded up front, so none may repeat
+    for (i, hop) in hops.iter().enumerate() {
+        require!(
+            hops[..i]
+                .iter()
+                .all(|other| other.pool.key() != hop.pool.key()),
+            AmmError::InvalidRoute
+        );
+    }
+    for pair in hops.windows(2) {
+        require_keys_eq!(
+            pair[0].mint_out.key(),
+            pair[1].mint_in.key(),
+            AmmError::InvalidRoute
+        );
+    }
+    require_keys_eq!(
+        ctx.accounts.user_source.mint,
+        hops[0].mint_in.key(),
+        AmmError::InvalidTokenMint
+    );
+    require_keys_eq!(
+        ctx.accounts.user_destination.mint,
+        hops[hop_count - 1].mint_out.key(),
+        AmmError::InvalidTokenMint
+    );
+
+    let now = Clock::get()?.unix_timestamp;
+    let mut amount = amount_in;
+    for hop in hops.iter_mut() {
+        hop.quote(ctx.accounts.user.key(), amount, now)?;
+        amount = hop.amount_out;
+    }
+    let last = &hops[hop_count - 1];
+    require!(
+        amount_received(&last.mint_out, amount)? >= min_amount_out,
+        AmmError::SlippageExceeded
+    );
+
+    let first = &hops[0];
+    transfer_tokens(
+        &ctx.accounts.user_source,
+        &first.vault_in,
+        &ctx.accounts.user,
+        token_program_for(
+            &first.mint_in,
+            &ctx.accounts.token_program,
+            &ctx.accounts.token_2022_program,
+        ),
+        &first.mint_in,
+        remaining_accounts,
+        amount_in,
+    )?;
+
+    // Intermediate amounts move vault to vault and never touch the user
+    for pair in hops.windows(2) {
+        pair[0].pay_out(
+            &pair[1].vault_in,
+            token_program_for(
+                &pair[0].mint_out,
+                &ctx.accounts.token_program,
+                &ctx.accounts.token_2022_program,
+            ),
+            remaining_accounts,
+        )?;
+    }
+    last.pay_out(
+        &ctx.accounts.user_destination,
+        token_program_for(
+            &last.mint_out,
+            &ctx.accounts.token_program,
+            &ctx.accounts.token_2022_program,
+        ),
+        remaining_accounts,
+    )?;
+
+    for hop in hops.iter_mut() {
+        hop.pool.exit(&crate::ID)?;
+        hop.oracle.exit(&crate::ID)?;
+    }
+    for swapped in hops.into_iter().filter_map(|hop| hop.swapped) {
+        emit_event!(ctx, swapped);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,49 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    errors::AmmError,
+    events::{emit_event, TransferHookProgramsSet},
+    states::{ClPool, MAX_TRANSFER_HOOK_PROGRAMS},
+};
+
+/// Accounts for the authority-only settings of a concentrated liquidity pool.
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+pub struct UpdateClPool<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"cl_pool",
+            cl_pool.token_a_mint.as_ref(),
+            cl_pool.token_b_mint.as_ref(),
+            &cl_pool.fee_rate.to_le_bytes(),
+        ],
+        bump = cl_pool.bump,
+        has_one = authority @ AmmError::Unauthorized,
+    )]
+    pub cl_pool: Account<'info, ClPool>,
+}
+
+pub fn set_cl_transfer_hook_programs(
+    ctx: Context<UpdateClPool>,
+    transfer_hook_programs: Vec<Pubkey>,
+) -> Result<()> {
+    require!(
+        transfer_hook_programs.len() <= MAX_TRANSFER_HOOK_PROGRAMS,
+        AmmError::TooManyTransferHookPrograms
+    );
+
+    ctx.accounts.cl_pool.transfer_hook_programs = transfer_hook_programs.clone();
+
+    emit_event!(
+        ctx,
+        TransferHookProgramsSet {
+            pool: ctx.accounts.cl_pool.key(),
+            transfer_hook_programs,
+        }
+    );
+
+    Ok(())
+}
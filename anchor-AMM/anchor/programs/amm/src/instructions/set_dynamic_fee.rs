@@ -0,0 +1,44 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    errors::AmmError,
+    events::{emit_event, DynamicFeeUpdated},
+    instructions::UpdateFeeRate,
+    states::DynamicFeeConfig,
+};
+
+/// Switches the pool to the volatility-driven swap fee described by
+/// `dynamic_fee`, or back to the fixed `fee_rate` when it is `None`.
+/// Volatility recorded so far is discarded either way. Both bounds must stay
+/// within the pool's fee band, like a fixed `fee_rate`.
+pub fn set_dynamic_fee(
+    ctx: Context<UpdateFeeRate>,
+    dynamic_fee: Option<DynamicFeeConfig>,
+) -> Result<()> {
+    if let Some(config) = dynamic_fee {
+        require!(
+            config.min_fee_rate <= config.max_fee_rate && config.decay_period > 0,
+            AmmError::InvalidDynamicFee
+        );
+        let fee_band = ctx.accounts.amm_config.fee_band(ctx.accounts.pool.fee_tier);
+        require!(
+            fee_band.contains(&config.min_fee_rate) && fee_band.contains(&config.max_fee_rate),
+            AmmError::FeeRateOutsideTier
+        );
+    }
+
+    let pool = &mut ctx.accounts.pool;
+    pool.dynamic_fee = dynamic_fee;
+    pool.volatility = 0;
+    pool.volatility_updated_ts = Clock::get()?.unix_timestamp;
+
+    emit_event!(
+        ctx,
+        DynamicFeeUpdated {
+            pool: ctx.accounts.pool.key(),
+            dynamic_fee,
+        }
+    );
+
+    Ok(())
+}
@@ -0,0 +1,54 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    errors::AmmError,
+    events::{emit_event, RewardRateUpdated},
+    states::{Farm, Pool},
+};
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+pub struct UpdateFarm<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [
+            b"pool",
+            pool.token_a_mint.as_ref(),
+            pool.token_b_mint.as_ref(),
+            &pool.fee_tier.to_le_bytes(),
+        ],
+        bump = pool.bump,
+        has_one = authority @ AmmError::Unauthorized,
+    )]
+    pub pool: Account<'info, Pool>,
+
+    #[account(
+        mut,
+        seeds = [b"farm", farm.pool.as_ref(), farm.reward_mint.as_ref()],
+        bump = farm.bump,
+        has_one = pool @ AmmError::InvalidPoolState,
+    )]
+    pub farm: Account<'info, Farm>,
+}
+
+/// Changes the emission rate from now on. Rewards accrued at the old rate
+/// are emitted first.
+pub fn set_farm_reward_rate(ctx: Context<UpdateFarm>, reward_rate: u64) -> Result<()> {
+    let farm = &mut ctx.accounts.farm;
+
+    farm.update(Clock::get()?.unix_timestamp);
+    let old_reward_rate = farm.reward_rate;
+    farm.reward_rate = reward_rate;
+
+    emit_event!(
+        ctx,
+        RewardRateUpdated {
+            farm: ctx.accounts.farm.key(),
+            old_reward_rate,
+            new_reward_rate: reward_rate,
+        }
+    );
+
+    Ok(())
+}
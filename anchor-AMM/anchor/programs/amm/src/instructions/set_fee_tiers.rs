@@ -0,0 +1,32 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    errors::AmmError,
+    events::{emit_event, AmmConfigUpdated},
+    states::AmmConfig,
+};
+
+/// Accounts for the admin-only settings of the program-wide config.
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+pub struct UpdateAmmConfig<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"amm_config"],
+        bump = amm_config.bump,
+        has_one = admin @ AmmError::Unauthorized,
+    )]
+    pub amm_config: Account<'info, AmmConfig>,
+}
+
+/// Replaces the fee tiers new pools may pick from. Existing pools keep
+/// their fee rate.
+pub fn set_fee_tiers(ctx: Context<UpdateAmmConfig>, fee_tiers: Vec<u16>) -> Result<()> {
+    ctx.accounts.amm_config.set_fee_tiers(fee_tiers)?;
+
+    emit_event!(ctx, AmmConfigUpdated::new(&ctx.accounts.amm_config));
+
+    Ok(())
+}
@@ -0,0 +1,36 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    errors::AmmError,
+    events::{emit_event, PausedSet},
+    states::MultiPool,
+};
+
+/// Accounts for the authority-only settings of a multi-asset pool.
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+pub struct UpdateMultiPool<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"multi_pool", pool.mints_hash.as_ref()],
+        bump = pool.bump,
+        has_one = authority @ AmmError::Unauthorized,
+    )]
+    pub pool: Account<'info, MultiPool>,
+}
+
+pub fn set_multi_pool_paused(ctx: Context<UpdateMultiPool>, paused: bool) -> Result<()> {
+    ctx.accounts.pool.paused = paused;
+
+    emit_event!(
+        ctx,
+        PausedSet {
+            pool: ctx.accounts.pool.key(),
+            paused,
+        }
+    );
+
+    Ok(())
+}
@@ -0,0 +1,20 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    events::{emit_event, PausedSet},
+    instructions::UpdatePool,
+};
+
+pub fn set_paused(ctx: Context<UpdatePool>, paused: bool) -> Result<()> {
+    ctx.accounts.pool.paused = paused;
+
+    emit_event!(
+        ctx,
+        PausedSet {
+            pool: ctx.accounts.pool.key(),
+            paused,
+        }
+    );
+
+    Ok(())
+}
@@ -0,0 +1,20 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    events::{emit_event, AmmConfigUpdated},
+    instructions::UpdateAmmConfig,
+};
+
+pub fn set_pool_creation_fee(
+    ctx: Context<UpdateAmmConfig>,
+    pool_creation_fee: u64,
+    fee_receiver: Pubkey,
+) -> Result<()> {
+    let config = &mut ctx.accounts.amm_config;
+    config.pool_creation_fee = pool_creation_fee;
+    config.fee_receiver = fee_receiver;
+
+    emit_event!(ctx, AmmConfigUpdated::new(&ctx.accounts.amm_config));
+
+    Ok(())
+}
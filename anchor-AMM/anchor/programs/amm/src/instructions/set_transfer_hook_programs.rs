@@ -0,0 +1,30 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    errors::AmmError,
+    events::{emit_event, TransferHookProgramsSet},
+    instructions::UpdatePool,
+    states::MAX_TRANSFER_HOOK_PROGRAMS,
+};
+
+pub fn set_transfer_hook_programs(
+    ctx: Context<UpdatePool>,
+    transfer_hook_programs: Vec<Pubkey>,
+) -> Result<()> {
+    require!(
+        transfer_hook_programs.len() <= MAX_TRANSFER_HOOK_PROGRAMS,
+        AmmError::TooManyTransferHookPrograms
+    );
+
+    ctx.accounts.pool.transfer_hook_programs = transfer_hook_programs.clone();
+
+    emit_event!(
+        ctx,
+        TransferHookProgramsSet {
+            pool: ctx.accounts.pool.key(),
+            transfer_hook_programs,
+        }
+    );
+
+    Ok(())
+}
@@ -0,0 +1,98 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+
+use crate::{
+    errors::AmmError,
+    events::{emit_event, Staked},
+    instructions::transfer_tokens,
+    states::{Farm, FarmPosition},
+};
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+pub struct ModifyStake<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"farm", farm.pool.as_ref(), farm.reward_mint.as_ref()],
+        bump = farm.bump,
+    )]
+    pub farm: Account<'info, Farm>,
+
+    #[account(
+        mut,
+        has_one = owner @ AmmError::Unauthorized,
+        has_one = farm @ AmmError::InvalidPoolState,
+    )]
+    pub position: Account<'info, FarmPosition>,
+
+    #[account(
+        address = farm.lp_mint @ AmmError::InvalidTokenMint,
+        mint::token_program = lp_token_program,
+    )]
+    pub lp_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        address = farm.stake_vault @ AmmError::InvalidPoolState,
+    )]
+    pub stake_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = user_lp_token.mint == lp_mint.key() @ AmmError::InvalidTokenMint,
+        constraint = user_lp_token.owner == owner.key() @ AmmError::Unauthorized,
+    )]
+    pub user_lp_token: InterfaceAccount<'info, TokenAccount>,
+
+    pub lp_token_program: Interface<'info, TokenInterface>,
+}
+
+impl ModifyStake<'_> {
+    /// Emits the farm's rewards up to now and credits this position's share.
+    pub fn settle_rewards(&mut self) -> Result<()> {
+        self.farm.update(Clock::get()?.unix_timestamp);
+        self.position.settle_rewards(self.farm.reward_per_share_x64)
+    }
+}
+
+pub fn stake(ctx: Context<ModifyStake>, amount: u64) -> Result<()> {
+    require!(amount > 0, AmmError::InvalidAmount);
+
+    ctx.accounts.settle_rewards()?;
+
+    // LP mints are created by the pool without transfer fees or hooks
+    transfer_tokens(
+        &ctx.accounts.user_lp_token,
+        &ctx.accounts.stake_vault,
+        &ctx.accounts.owner,
+        &ctx.accounts.lp_token_program,
+        &ctx.accounts.lp_mint,
+        &[],
+        amount,
+    )?;
+
+    let position = &mut ctx.accounts.position;
+    position.amount = position
+        .amount
+        .checked_add(amount)
+        .ok_or(AmmError::MathOverflow)?;
+    let farm = &mut ctx.accounts.farm;
+    farm.total_staked = farm
+        .total_staked
+        .checked_add(amount)
+        .ok_or(AmmError::MathOverflow)?;
+
+    emit_event!(
+        ctx,
+        Staked {
+            farm: ctx.accounts.farm.key(),
+            user: ctx.accounts.owner.key(),
+            amount,
+            total_staked: ctx.accounts.farm.total_staked,
+        }
+    );
+
+    Ok(())
+}
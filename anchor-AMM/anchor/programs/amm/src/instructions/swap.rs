@@ -1,11 +1,18 @@
 use anchor_lang::prelude::*;
-use anchor_spl::{
-    token_interface::{ Mint, TokenAccount, TokenInterface, },
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+
+use crate::states::{Oracle, Pool};
+use crate::{
+    errors::AmmError,
+    events::{emit_event, Swapped},
+    instructions::{
+        amount_received, check_deadline, transfer_hook_program, transfer_tokens,
+        transfer_tokens_from_vault,
+    },
+    math,
 };
 
-use crate::{errors::AmmError, instructions::{transfer_tokens, transfer_tokens_from_vault}};
-use crate::states::Pool;
-
+#[cfg_attr(feature = "event-cpi", event_cpi)]
 #[derive(Accounts)]
 pub struct Swap<'info> {
     #[account(mut)]
@@ -13,106 +20,191 @@ pub struct Swap<'info> {
 
     #[account(
         mut,
-        seeds = [b"pool", pool.token_a_mint.as_ref(), pool.token_b_mint.as_ref()],
+        seeds = [
+            b"pool",
+            pool.token_a_mint.as_ref(),
+            pool.token_b_mint.as_ref(),
+            &pool.fee_tier.to_le_bytes(),
+        ],
         bump = pool.bump,
+        constraint = !pool.flash_loan_active @ AmmError::FlashLoanActive,
+        constraint = !pool.paused @ AmmError::PoolPaused,
     )]
     pub pool: Account<'info, Pool>,
 
     #[account(
         mut,
+        seeds = [b"oracle", pool.key().as_ref()],
+        bump = oracle.bump,
+    )]
+    pub oracle: Box<Account<'info, Oracle>>,
+
+    #[account(
+        mut,
+        address = pool.token_a_vault @ AmmError::InvalidPoolState,
     )]
     pub token_a_vault: InterfaceAccount<'info, TokenAccount>,
 
     #[account(
         mut,
+        address = pool.token_b_vault @ AmmError::InvalidPoolState,
     )]
     pub token_b_vault: InterfaceAccount<'info, TokenAccount>,
 
     #[account(
         mut,
+        constraint = user_token_a.mint == token_a_mint.key() @ AmmError::InvalidTokenMint,
+        constraint = user_token_a.owner == user.key() @ AmmError::Unauthorized,
     )]
     pub user_token_a: InterfaceAccount<'info, TokenAccount>,
 
     #[account(
         mut,
+        constraint = user_token_b.mint == token_b_mint.key() @ AmmError::InvalidTokenMint,
+        constraint = user_token_b.owner == user.key() @ AmmError::Unauthorized,
     )]
     pub user_token_b: InterfaceAccount<'info, TokenAccount>,
 
+    #[account(
+        address = pool.token_a_mint @ AmmError::InvalidTokenMint,
+        mint::token_program = token_a_program,
+        constraint = pool.accepts_transfer_hook(transfer_hook_program(&token_a_mint))
+            @ AmmError::TransferHookNotAllowed,
+    )]
     pub token_a_mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        address = pool.token_b_mint @ AmmError::InvalidTokenMint,
+        mint::token_program = token_b_program,
+        constraint = pool.accepts_transfer_hook(transfer_hook_program(&token_b_mint))
+            @ AmmError::TransferHookNotAllowed,
+    )]
     pub token_b_mint: InterfaceAccount<'info, Mint>,
 
-    pub token_program: Interface<'info, TokenInterface>,
+    pub token_a_program: Interface<'info, TokenInterface>,
+    pub token_b_program: Interface<'info, TokenInterface>,
 }
 
-pub fn swap(
-    ctx: Context<Swap>, 
-    amount_in: u64, 
+pub fn swap<'info>(
+    ctx: Context<'_, '_, 'info, 'info, Swap<'info>>,
+    amount_in: u64,
     min_amount_out: u64,
     a_to_b: bool,
+    deadline: Option<i64>,
 ) -> Result<()> {
+    check_deadline(deadline)?;
+
     let pool = &ctx.accounts.pool;
-    
-    let (reserve_in, reserve_out) = if a_to_b {
-            (ctx.accounts.token_a_vault.amount, ctx.accounts.token_b_vault.amount)
-        } else {
-            (ctx.accounts.token_b_vault.amount, ctx.accounts.token_a_vault.amount)
-        };
 
-    let amount_in_with_fee = amount_in.checked_mul(10000_u64.checked_sub(pool.fee_rate as u64).unwrap()).unwrap().checked_div(10000).unwrap();
-    let amount_out = amount_in_with_fee.checked_mul(reserve_out).unwrap()
-            .checked_div(reserve_in.checked_add(amount_in_with_fee).unwrap()).unwrap();
+    let (reserve_a, reserve_b) = pool.reserves(
+        ctx.accounts.token_a_vault.amount,
+        ctx.accounts.token_b_vault.amount,
+    )?;
 
-    require!(amount_out >= min_amount_out, AmmError::SlippageExceeded);
+    let now = Clock::get()?.unix_timestamp;
+    let (price_a, price_b) = pool.spot_prices_x64(reserve_a, reserve_b);
+    ctx.accounts.oracle.update(now, price_a, price_b);
 
+    let (reserve_in, reserve_out) = if a_to_b {
+        (reserve_a, reserve_b)
+    } else {
+        (reserve_b, reserve_a)
+    };
+    let (mint_in, mint_out) = if a_to_b {
+        (&ctx.accounts.token_a_mint, &ctx.accounts.token_b_mint)
+    } else {
+        (&ctx.accounts.token_b_mint, &ctx.accounts.token_a_mint)
+    };
+
+    // Price what the vault actually receives, net of any transfer fee
+    let swap_result = math::swap_exact_in(
+        pool.swap_curve(now, a_to_b),
+        amount_received(mint_in, amount_in)?,
+        reserve_in,
+        reserve_out,
+        pool.swap_fee_rate(now),
+        pool.protocol_fee_rate,
+    )?;
+    let math::SwapResult {
+        amount_out,
+        protocol_fee,
+        ..
+    } = swap_result;
+
+    require!(
+        amount_received(mint_out, amount_out)? >= min_amount_out,
+        AmmError::SlippageExceeded
+    );
+
+    let fee_tier_bytes = pool.fee_tier.to_le_bytes();
     let seeds = &[
-            b"pool",
-            pool.token_a_mint.as_ref(),
-            pool.token_b_mint.as_ref(),
-            &[pool.bump],
-        ];
-        let signer = &[&seeds[..]];
-    
-    if a_to_b {
+        b"pool",
+        pool.token_a_mint.as_ref(),
+        pool.token_b_mint.as_ref(),
+        fee_tier_bytes.as_ref(),
+        &[pool.bump],
+    ];
+    let signer = &[&seeds[..]];
 
-            transfer_tokens(
-                &ctx.accounts.user_token_a,
-                &ctx.accounts.token_a_vault,
-                &ctx.accounts.user,
-                &ctx.accounts.token_program,
-                &ctx.accounts.token_a_mint,
-                amount_in,
-            )?;
-
-            transfer_tokens_from_vault(
-                &ctx.accounts.token_b_vault,
-                &ctx.accounts.user_token_b,
-                &pool.to_account_info(),
-                &ctx.accounts.token_program,
-                &ctx.accounts.token_b_mint,
-                signer,
-                amount_out,
-            )?;
+    if a_to_b {
+        transfer_tokens(
+            &ctx.accounts.user_token_a,
+            &ctx.accounts.token_a_vault,
+            &ctx.accounts.user,
+            &ctx.accounts.token_a_program,
+            &ctx.accounts.token_a_mint,
+            ctx.remaining_accounts,
+            amount_in,
+        )?;
+
+        transfer_tokens_from_vault(
+            &ctx.accounts.token_b_vault,
+            &ctx.accounts.user_token_b,
+            &pool.to_account_info(),
+            &ctx.accounts.token_b_program,
+            &ctx.accounts.token_b_mint,
+            ctx.remaining_accounts,
+            signer,
+            amount_out,
+        )?;
     } else {
-            
-            transfer_tokens(
-                &ctx.accounts.user_token_b,
-                &ctx.accounts.token_b_vault,
-                &ctx.accounts.user,
-                &ctx.accounts.token_program,
-                &ctx.accounts.token_b_mint,
-                amount_in,
-            )?;
-            
-            transfer_tokens_from_vault(
-                &ctx.accounts.token_a_vault,
-                &ctx.accounts.user_token_a,
-                &pool.to_account_info(),
-                &ctx.accounts.token_program,
-                &ctx.accounts.token_a_mint,
-                signer,
-                amount_out,
-            )?;
+        transfer_tokens(
+            &ctx.accounts.user_token_b,
+            &ctx.accounts.token_b_vault,
+            &ctx.accounts.user,
+            &ctx.accounts.token_b_program,
+            &ctx.accounts.token_b_mint,
+            ctx.remaining_accounts,
+            amount_in,
+        )?;
+
+        transfer_tokens_from_vault(
+            &ctx.accounts.token_a_vault,
+            &ctx.accounts.user_token_a,
+            &pool.to_account_info(),
+            &ctx.accounts.token_a_program,
+            &ctx.accounts.token_a_mint,
+            ctx.remaining_accounts,
+            signer,
+            amount_out,
+        )?;
     }
+
+    ctx.accounts
+        .pool
+        .accrue_protocol_fee(protocol_fee, a_to_b)?;
+    ctx.accounts
+        .pool
+        .record_swap(&swap_result, a_to_b, reserve_in, reserve_out, now)?;
+
+    let swapped = Swapped::new(
+        ctx.accounts.pool.key(),
+        ctx.accounts.user.key(),
+        a_to_b,
+        &swap_result,
+        reserve_in,
+        reserve_out,
+    )?;
+    emit_event!(ctx, swapped);
+
     Ok(())
 }
-
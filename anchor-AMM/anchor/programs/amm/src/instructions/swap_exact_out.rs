@@ -0,0 +1,137 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    errors::AmmError,
+    events::{emit_event, Swapped},
+    instructions::{
+        amount_to_send, check_deadline, transfer_tokens, transfer_tokens_from_vault, Swap,
+    },
+    math,
+};
+
+pub fn swap_exact_out<'info>(
+    ctx: Context<'_, '_, 'info, 'info, Swap<'info>>,
+    amount_out: u64,
+    max_amount_in: u64,
+    a_to_b: bool,
+    deadline: Option<i64>,
+) -> Result<()> {
+    check_deadline(deadline)?;
+    require!(amount_out > 0, AmmError::InvalidAmount);
+
+    let pool = &ctx.accounts.pool;
+
+    let (reserve_a, reserve_b) = pool.reserves(
+        ctx.accounts.token_a_vault.amount,
+        ctx.accounts.token_b_vault.amount,
+    )?;
+
+    let now = Clock::get()?.unix_timestamp;
+    let (price_a, price_b) = pool.spot_prices_x64(reserve_a, reserve_b);
+    ctx.accounts.oracle.update(now, price_a, price_b);
+
+    let (reserve_in, reserve_out) = if a_to_b {
+        (reserve_a, reserve_b)
+    } else {
+        (reserve_b, reserve_a)
+    };
+    let (mint_in, mint_out) = if a_to_b {
+        (&ctx.accounts.token_a_mint, &ctx.accounts.token_b_mint)
+    } else {
+        (&ctx.accounts.token_b_mint, &ctx.accounts.token_a_mint)
+    };
+
+    // The vault pays out enough for the user to net `amount_out` after any transfer fee
+    let gross_amount_out = amount_to_send(mint_out, amount_out)?;
+
+    let swap_result = math::swap_exact_out(
+        pool.swap_curve(now, a_to_b),
+        gross_amount_out,
+        reserve_in,
+        reserve_out,
+        pool.swap_fee_rate(now),
+        pool.protocol_fee_rate,
+    )?;
+    let math::SwapResult {
+        amount_in,
+        protocol_fee,
+        ..
+    } = swap_result;
+
+    // ...and the user sends enough for the vault to net the curve's required input
+    let amount_in = amount_to_send(mint_in, amount_in)?;
+    require!(amount_in <= max_amount_in, AmmError::SlippageExceeded);
+
+    let fee_tier_bytes = pool.fee_tier.to_le_bytes();
+    let seeds = &[
+        b"pool",
+        pool.token_a_mint.as_ref(),
+        pool.token_b_mint.as_ref(),
+        fee_tier_bytes.as_ref(),
+        &[pool.bump],
+    ];
+    let signer = &[&seeds[..]];
+
+    if a_to_b {
+        transfer_tokens(
+            &ctx.accounts.user_token_a,
+            &ctx.accounts.token_a_vault,
+            &ctx.accounts.user,
+            &ctx.accounts.token_a_program,
+            &ctx.accounts.token_a_mint,
+            ctx.remaining_accounts,
+            amount_in,
+        )?;
+
+        transfer_tokens_from_vault(
+            &ctx.accounts.token_b_vault,
+            &ctx.accounts.user_token_b,
+            &pool.to_account_info(),
+            &ctx.accounts.token_b_program,
+            &ctx.accounts.token_b_mint,
+            ctx.remaining_accounts,
+            signer,
+            gross_amount_out,
+        )?;
+    } else {
+        transfer_tokens(
+            &ctx.accounts.user_token_b,
+            &ctx.accounts.token_b_vault,
+            &ctx.accounts.user,
+            &ctx.accounts.token_b_program,
+            &ctx.accounts.token_b_mint,
+            ctx.remaining_accounts,
+            amount_in,
+        )?;
+
+        transfer_tokens_from_vault(
+            &ctx.accounts.token_a_vault,
+            &ctx.accounts.user_token_a,
+            &pool.to_account_info(),
+            &ctx.accounts.token_a_program,
+            &ctx.accounts.token_a_mint,
+            ctx.remaining_accounts,
+            signer,
+            gross_amount_out,
+        )?;
+    }
+
+    ctx.accounts
+        .pool
+        .accrue_protocol_fee(protocol_fee, a_to_b)?;
+    ctx.accounts
+        .pool
+        .record_swap(&swap_result, a_to_b, reserve_in, reserve_out, now)?;
+
+    let swapped = Swapped::new(
+        ctx.accounts.pool.key(),
+        ctx.accounts.user.key(),
+        a_to_b,
+        &swap_result,
+        reserve_in,
+        reserve_out,
+    )?;
+    emit_event!(ctx, swapped);
+
+    Ok(())
+}
@@ -0,0 +1,52 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    errors::AmmError,
+    events::{emit_event, Unstaked},
+    instructions::{transfer_tokens_from_vault, ModifyStake},
+};
+
+pub fn unstake(ctx: Context<ModifyStake>, amount: u64) -> Result<()> {
+    require!(amount > 0, AmmError::InvalidAmount);
+    require!(
+        amount <= ctx.accounts.position.amount,
+        AmmError::InsufficientStake
+    );
+
+    ctx.accounts.settle_rewards()?;
+
+    let farm = &ctx.accounts.farm;
+    let seeds = &[
+        b"farm",
+        farm.pool.as_ref(),
+        farm.reward_mint.as_ref(),
+        &[farm.bump],
+    ];
+    let signer = &[&seeds[..]];
+
+    transfer_tokens_from_vault(
+        &ctx.accounts.stake_vault,
+        &ctx.accounts.user_lp_token,
+        &farm.to_account_info(),
+        &ctx.accounts.lp_token_program,
+        &ctx.accounts.lp_mint,
+        &[],
+        signer,
+        amount,
+    )?;
+
+    ctx.accounts.position.amount -= amount;
+    ctx.accounts.farm.total_staked -= amount;
+
+    emit_event!(
+        ctx,
+        Unstaked {
+            farm: ctx.accounts.farm.key(),
+            user: ctx.accounts.owner.key(),
+            amount,
+            total_staked: ctx.accounts.farm.total_staked,
+        }
+    );
+
+    Ok(())
+}
@@ -0,0 +1,70 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    errors::AmmError,
+    events::{emit_event, FeeRateUpdated},
+    states::{AmmConfig, Pool},
+};
+
+/// Accounts for the authority-only settings of a pool.
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+pub struct UpdatePool<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"pool",
+            pool.token_a_mint.as_ref(),
+            pool.token_b_mint.as_ref(),
+            &pool.fee_tier.to_le_bytes(),
+        ],
+        bump = pool.bump,
+        has_one = authority @ AmmError::Unauthorized,
+    )]
+    pub pool: Account<'info, Pool>,
+}
+
+/// Accounts for `update_fee_rate` and `set_dynamic_fee`, which also need the
+/// fee tiers that bound the pool's rate.
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+pub struct UpdateFeeRate<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(seeds = [b"amm_config"], bump = amm_config.bump)]
+    pub amm_config: Account<'info, AmmConfig>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"pool",
+            pool.token_a_mint.as_ref(),
+            pool.token_b_mint.as_ref(),
+            &pool.fee_tier.to_le_bytes(),
+        ],
+        bump = pool.bump,
+        has_one = authority @ AmmError::Unauthorized,
+    )]
+    pub pool: Account<'info, Pool>,
+}
+
+pub fn update_fee_rate(ctx: Context<UpdateFeeRate>, fee_rate: u16) -> Result<()> {
+    let fee_band = ctx.accounts.amm_config.fee_band(ctx.accounts.pool.fee_tier);
+    require!(fee_band.contains(&fee_rate), AmmError::FeeRateOutsideTier);
+
+    let old_fee_rate = ctx.accounts.pool.fee_rate;
+    ctx.accounts.pool.fee_rate = fee_rate;
+
+    emit_event!(
+        ctx,
+        FeeRateUpdated {
+            pool: ctx.accounts.pool.key(),
+            old_fee_rate,
+            new_fee_rate: fee_rate,
+        }
+    );
+
+    Ok(())
+}
@@ -0,0 +1,161 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    errors::AmmError,
+    events::{emit_event, LiquidityAdded, Swapped},
+    instructions::{
+        amount_received, check_deadline, mint_lp_tokens, transfer_tokens, AddLiquidity,
+    },
+    math,
+};
+
+/// Deposits `amount_in` of a single token. The part that `math::zap_swap`
+/// picks is swapped inside the vaults and the rest is added at the new pool
+/// ratio, so only the input vault receives tokens.
+pub fn zap_in<'info>(
+    ctx: Context<'_, '_, 'info, 'info, AddLiquidity<'info>>,
+    amount_in: u64,
+    a_side: bool,
+    min_lp_tokens: u64,
+    deadline: Option<i64>,
+) -> Result<()> {
+    check_deadline(deadline)?;
+    require!(amount_in > 0, AmmError::InvalidAmount);
+
+    let pool = &ctx.accounts.pool;
+
+    let (reserve_a, reserve_b) = pool.reserves(
+        ctx.accounts.token_a_vault.amount,
+        ctx.accounts.token_b_vault.amount,
+    )?;
+
+    let now = Clock::get()?.unix_timestamp;
+    let (price_a, price_b) = pool.spot_prices_x64(reserve_a, reserve_b);
+    ctx.accounts.oracle.update(now, price_a, price_b);
+
+    // A zap has no ratio of its own to seed an empty pool with
+    let lp_supply = ctx.accounts.lp_mint.supply;
+    require!(lp_supply > 0, AmmError::InsufficientLiquidity);
+
+    let (reserve_in, reserve_out) = if a_side {
+        (reserve_a, reserve_b)
+    } else {
+        (reserve_b, reserve_a)
+    };
+    let (mint_in, user_source, vault_in, program_in) = if a_side {
+        (
+            &ctx.accounts.token_a_mint,
+            &ctx.accounts.user_token_a,
+            &ctx.accounts.token_a_vault,
+            &ctx.accounts.token_a_program,
+        )
+    } else {
+        (
+            &ctx.accounts.token_b_mint,
+            &ctx.accounts.user_token_b,
+            &ctx.accounts.token_b_vault,
+            &ctx.accounts.token_b_program,
+        )
+    };
+
+    let received = amount_received(mint_in, amount_in)?;
+    let swap = math::zap_swap(
+        pool.swap_curve(now, a_side),
+        received,
+        reserve_in,
+        reserve_out,
+        pool.swap_fee_rate(now),
+        pool.protocol_fee_rate,
+    )?;
+    let (reserve_in_after, reserve_out_after) =
+        math::reserves_after_swap(&swap, reserve_in, reserve_out)?;
+
+    // The swap output never leaves the output vault; it is deposited as is
+    let deposit_in = received - swap.amount_in;
+    let lp_tokens_to_mint = math::lp_tokens_for_deposit(
+        deposit_in,
+        swap.amount_out,
+        reserve_in_after,
+        reserve_out_after,
+        lp_supply,
+    )?;
+
+    require!(
+        lp_tokens_to_mint >= min_lp_tokens,
+        AmmError::SlippageExceeded
+    );
+    require!(lp_tokens_to_mint > 0, AmmError::InvalidAmount);
+
+    transfer_tokens(
+        user_source,
+        vault_in,
+        &ctx.accounts.user,
+        program_in,
+        mint_in,
+        ctx.remaining_accounts,
+        amount_in,
+    )?;
+
+    let fee_tier_bytes = pool.fee_tier.to_le_bytes();
+    let seeds = &[
+        b"pool",
+        pool.token_a_mint.as_ref(),
+        pool.token_b_mint.as_ref(),
+        fee_tier_bytes.as_ref(),
+        &[pool.bump],
+    ];
+    let signer = &[&seeds[..]];
+
+    mint_lp_tokens(
+        &ctx.accounts.lp_mint,
+        &ctx.accounts.user_lp_token,
+        &pool.to_account_info(),
+        &ctx.accounts.token_program,
+        signer,
+        lp_tokens_to_mint,
+    )?;
+
+    ctx.accounts
+        .pool
+        .accrue_protocol_fee(swap.protocol_fee, a_side)?;
+    ctx.accounts
+        .pool
+        .record_swap(&swap, a_side, reserve_in, reserve_out, now)?;
+
+    let pool_key = ctx.accounts.pool.key();
+    let user_key = ctx.accounts.user.key();
+    let swapped = Swapped::new(pool_key, user_key, a_side, &swap, reserve_in, reserve_out)?;
+    emit_event!(ctx, swapped);
+
+    // The swap output is deposited where it already sits, so that side ends where it began
+    let (amount_a, amount_b, reserve_a, reserve_b) = if a_side {
+        (
+            deposit_in,
+            swap.amount_out,
+            reserve_in_after + deposit_in,
+            reserve_out,
+        )
+    } else {
+        (
+            swap.amount_out,
+            deposit_in,
+            reserve_out,
+            reserve_in_after + deposit_in,
+        )
+    };
+    emit_event!(
+        ctx,
+        LiquidityAdded {
+            pool: pool_key,
+            user: user_key,
+            amount_a,
+            amount_b,
+            lp_tokens: lp_tokens_to_mint,
+            reserve_a,
+            reserve_b,
+            lp_supply: lp_supply + lp_tokens_to_mint,
+        }
+    );
+
+    Ok(())
+}
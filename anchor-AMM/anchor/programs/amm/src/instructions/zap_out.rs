@@ -0,0 +1,157 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{burn, Burn};
+
+use crate::{
+    errors::AmmError,
+    events::{emit_event, LiquidityRemoved, Swapped},
+    instructions::{amount_received, check_deadline, transfer_tokens_from_vault, RemoveLiquidity},
+    math::{self, MINIMUM_LIQUIDITY},
+};
+
+/// Burns `lp_tokens` and pays the whole withdrawal out in one token. The
+/// other side's share is swapped inside the vaults against the reserves
+/// left after the withdrawal, so only the output vault pays out.
+pub fn zap_out<'info>(
+    ctx: Context<'_, '_, 'info, 'info, RemoveLiquidity<'info>>,
+    lp_tokens: u64,
+    to_a: bool,
+    min_amount_out: u64,
+    deadline: Option<i64>,
+) -> Result<()> {
+    check_deadline(deadline)?;
+    require!(lp_tokens > 0, AmmError::InvalidAmount);
+
+    let pool = &ctx.accounts.pool;
+    // Plain withdrawals stay open while paused, but this one also swaps
+    require!(!pool.paused, AmmError::PoolPaused);
+
+    let (reserve_a, reserve_b) = pool.reserves(
+        ctx.accounts.token_a_vault.amount,
+        ctx.accounts.token_b_vault.amount,
+    )?;
+
+    let now = Clock::get()?.unix_timestamp;
+    let (price_a, price_b) = pool.spot_prices_x64(reserve_a, reserve_b);
+    ctx.accounts.oracle.update(now, price_a, price_b);
+
+    let lp_supply = ctx.accounts.lp_mint.supply;
+
+    // The locked minimum liquidity can never be withdrawn
+    require!(
+        lp_supply.saturating_sub(lp_tokens) >= MINIMUM_LIQUIDITY,
+        AmmError::InsufficientLiquidity
+    );
+
+    let (amount_a, amount_b) = math::withdraw_amounts(lp_tokens, reserve_a, reserve_b, lp_supply)?;
+    let (withdrawn_out, withdrawn_in, reserve_out, reserve_in) = if to_a {
+        (
+            amount_a,
+            amount_b,
+            reserve_a - amount_a,
+            reserve_b - amount_b,
+        )
+    } else {
+        (
+            amount_b,
+            amount_a,
+            reserve_b - amount_b,
+            reserve_a - amount_a,
+        )
+    };
+
+    let swap = math::swap_exact_in(
+        pool.swap_curve(now, !to_a),
+        withdrawn_in,
+        reserve_in,
+        reserve_out,
+        pool.swap_fee_rate(now),
+        pool.protocol_fee_rate,
+    )?;
+    let amount_out = withdrawn_out
+        .checked_add(swap.amount_out)
+        .ok_or(AmmError::MathOverflow)?;
+
+    let (vault_out, user_destination, mint_out, program_out) = if to_a {
+        (
+            &ctx.accounts.token_a_vault,
+            &ctx.accounts.user_token_a,
+            &ctx.accounts.token_a_mint,
+            &ctx.accounts.token_a_program,
+        )
+    } else {
+        (
+            &ctx.accounts.token_b_vault,
+            &ctx.accounts.user_token_b,
+            &ctx.accounts.token_b_mint,
+            &ctx.accounts.token_b_program,
+        )
+    };
+
+    require!(
+        amount_received(mint_out, amount_out)? >= min_amount_out,
+        AmmError::SlippageExceeded
+    );
+    require!(amount_out > 0, AmmError::InvalidAmount);
+
+    burn(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            Burn {
+                mint: ctx.accounts.lp_mint.to_account_info(),
+                from: ctx.accounts.user_lp_token.to_account_info(),
+                authority: ctx.accounts.user.to_account_info(),
+            },
+        ),
+        lp_tokens,
+    )?;
+
+    let fee_tier_bytes = pool.fee_tier.to_le_bytes();
+    let seeds = &[
+        b"pool",
+        pool.token_a_mint.as_ref(),
+        pool.token_b_mint.as_ref(),
+        fee_tier_bytes.as_ref(),
+        &[pool.bump],
+    ];
+    let signer = &[&seeds[..]];
+
+    transfer_tokens_from_vault(
+        vault_out,
+        user_destination,
+        &pool.to_account_info(),
+        program_out,
+        mint_out,
+        ctx.remaining_accounts,
+        signer,
+        amount_out,
+    )?;
+
+    // The swap's input side never left its vault
+    ctx.accounts
+        .pool
+        .accrue_protocol_fee(swap.protocol_fee, !to_a)?;
+    ctx.accounts
+        .pool
+        .record_swap(&swap, !to_a, reserve_in, reserve_out, now)?;
+
+    let pool_key = ctx.accounts.pool.key();
+    let user_key = ctx.accounts.user.key();
+    emit_event!(
+        ctx,
+        LiquidityRemoved {
+            pool: pool_key,
+            user: user_key,
+            amount_a,
+            amount_b,
+            lp_tokens,
+            reserve_a: reserve_a - amount_a,
+            reserve_b: reserve_b - amount_b,
+            lp_supply: lp_supply - lp_tokens,
+        }
+    );
+
+    let swapped = Swapped::new(pool_key, user_key, !to_a, &swap, reserve_in, reserve_out)?;
+    emit_event!(ctx, swapped);
+
+    Ok(())
+}
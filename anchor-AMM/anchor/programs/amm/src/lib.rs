@@ -5,43 +5,372 @@ use anchor_lang::prelude::*;
 declare_id!("FqzkXZdwYjurnUKetJCAvaUw5WAqbwzU6gZEwydeEfqS");
 
 pub mod errors;
+pub mod events;
 pub mod instructions;
+pub mod math;
 pub mod states;
 
 use instructions::*;
+use states::{CurveType, DynamicFeeConfig, Observation};
 
 #[program]
 pub mod amm {
     use super::*;
 
-    pub fn initialize_pool(ctx: Context<InitializePool>, fee_rate: u16) -> Result<()> {
-        instructions::initialize_pool(ctx, fee_rate)
+    pub fn initialize_amm_config(
+        ctx: Context<InitializeAmmConfig>,
+        fee_tiers: Vec<u16>,
+        pool_creation_fee: u64,
+        fee_receiver: Pubkey,
+    ) -> Result<()> {
+        instructions::initialize_amm_config(ctx, fee_tiers, pool_creation_fee, fee_receiver)
     }
 
-    pub fn add_liquidity(
-        ctx: Context<AddLiquidity>,
-        amount_a: u64,
-        amount_b: u64,
+    pub fn set_fee_tiers(ctx: Context<UpdateAmmConfig>, fee_tiers: Vec<u16>) -> Result<()> {
+        instructions::set_fee_tiers(ctx, fee_tiers)
+    }
+
+    pub fn set_pool_creation_fee(
+        ctx: Context<UpdateAmmConfig>,
+        pool_creation_fee: u64,
+        fee_receiver: Pubkey,
+    ) -> Result<()> {
+        instructions::set_pool_creation_fee(ctx, pool_creation_fee, fee_receiver)
+    }
+
+    pub fn propose_amm_admin(
+        ctx: Context<UpdateAmmConfig>,
+        new_admin: Option<Pubkey>,
+    ) -> Result<()> {
+        instructions::propose_amm_admin(ctx, new_admin)
+    }
+
+    pub fn accept_amm_admin(ctx: Context<AcceptAmmAdmin>) -> Result<()> {
+        instructions::accept_amm_admin(ctx)
+    }
+
+    pub fn initialize_pool(
+        ctx: Context<InitializePool>,
+        fee_rate: u16,
+        protocol_fee_rate: u16,
+        curve_type: CurveType,
+        amp: u64,
+        weight_a: u16,
+    ) -> Result<()> {
+        instructions::initialize_pool(ctx, fee_rate, protocol_fee_rate, curve_type, amp, weight_a)
+    }
+
+    pub fn add_liquidity<'info>(
+        ctx: Context<'_, '_, 'info, 'info, AddLiquidity<'info>>,
+        amount_a_desired: u64,
+        amount_b_desired: u64,
+        min_amount_a: u64,
+        min_amount_b: u64,
         min_lp_tokens: u64,
+        deadline: Option<i64>,
     ) -> Result<()> {
-        instructions::add_liquidity(ctx, amount_a, amount_b, min_lp_tokens)
+        instructions::add_liquidity(
+            ctx,
+            amount_a_desired,
+            amount_b_desired,
+            min_amount_a,
+            min_amount_b,
+            min_lp_tokens,
+            deadline,
+        )
     }
 
-    pub fn remove_liquidity(
-        ctx: Context<RemoveLiquidity>,
+    pub fn remove_liquidity<'info>(
+        ctx: Context<'_, '_, 'info, 'info, RemoveLiquidity<'info>>,
         lp_tokens: u64,
         min_amount_a: u64,
         min_amount_b: u64,
+        deadline: Option<i64>,
     ) -> Result<()> {
-        instructions::remove_liquidity(ctx, lp_tokens, min_amount_a, min_amount_b)
+        instructions::remove_liquidity(ctx, lp_tokens, min_amount_a, min_amount_b, deadline)
     }
 
-    pub fn swap(
-        ctx: Context<Swap>,
+    pub fn swap<'info>(
+        ctx: Context<'_, '_, 'info, 'info, Swap<'info>>,
         amount_in: u64,
         min_amount_out: u64,
         a_to_b: bool,
+        deadline: Option<i64>,
+    ) -> Result<()> {
+        instructions::swap(ctx, amount_in, min_amount_out, a_to_b, deadline)
+    }
+
+    pub fn swap_exact_out<'info>(
+        ctx: Context<'_, '_, 'info, 'info, Swap<'info>>,
+        amount_out: u64,
+        max_amount_in: u64,
+        a_to_b: bool,
+        deadline: Option<i64>,
+    ) -> Result<()> {
+        instructions::swap_exact_out(ctx, amount_out, max_amount_in, a_to_b, deadline)
+    }
+
+    pub fn route_swap<'info>(
+        ctx: Context<'_, '_, 'info, 'info, RouteSwap<'info>>,
+        amount_in: u64,
+        min_amount_out: u64,
+        hop_count: u8,
+        deadline: Option<i64>,
+    ) -> Result<()> {
+        instructions::route_swap(ctx, amount_in, min_amount_out, hop_count, deadline)
+    }
+
+    pub fn zap_in<'info>(
+        ctx: Context<'_, '_, 'info, 'info, AddLiquidity<'info>>,
+        amount_in: u64,
+        a_side: bool,
+        min_lp_tokens: u64,
+        deadline: Option<i64>,
+    ) -> Result<()> {
+        instructions::zap_in(ctx, amount_in, a_side, min_lp_tokens, deadline)
+    }
+
+    pub fn zap_out<'info>(
+        ctx: Context<'_, '_, 'info, 'info, RemoveLiquidity<'info>>,
+        lp_tokens: u64,
+        to_a: bool,
+        min_amount_out: u64,
+        deadline: Option<i64>,
+    ) -> Result<()> {
+        instructions::zap_out(ctx, lp_tokens, to_a, min_amount_out, deadline)
+    }
+
+    pub fn observe(ctx: Context<Observe>, seconds_ago: Vec<u32>) -> Result<Vec<Observation>> {
+        instructions::observe(ctx, seconds_ago)
+    }
+
+    pub fn flash_borrow<'info>(
+        ctx: Context<'_, '_, 'info, 'info, FlashBorrow<'info>>,
+        amount_a: u64,
+        amount_b: u64,
+    ) -> Result<()> {
+        instructions::flash_borrow(ctx, amount_a, amount_b)
+    }
+
+    pub fn flash_repay<'info>(ctx: Context<'_, '_, 'info, 'info, FlashRepay<'info>>) -> Result<()> {
+        instructions::flash_repay(ctx)
+    }
+
+    pub fn collect_protocol_fees<'info>(
+        ctx: Context<'_, '_, 'info, 'info, CollectProtocolFees<'info>>,
+    ) -> Result<()> {
+        instructions::collect_protocol_fees(ctx)
+    }
+
+    pub fn ramp_amp(ctx: Context<RampAmp>, target_amp: u64, ramp_stop_ts: i64) -> Result<()> {
+        instructions::ramp_amp(ctx, target_amp, ramp_stop_ts)
+    }
+
+    pub fn update_fee_rate(ctx: Context<UpdateFeeRate>, fee_rate: u16) -> Result<()> {
+        instructions::update_fee_rate(ctx, fee_rate)
+    }
+
+    pub fn set_dynamic_fee(
+        ctx: Context<UpdateFeeRate>,
+        dynamic_fee: Option<DynamicFeeConfig>,
+    ) -> Result<()> {
+        instructions::set_dynamic_fee(ctx, dynamic_fee)
+    }
+
+    pub fn set_paused(ctx: Context<UpdatePool>, paused: bool) -> Result<()> {
+        instructions::set_paused(ctx, paused)
+    }
+
+    pub fn set_transfer_hook_programs(
+        ctx: Context<UpdatePool>,
+        transfer_hook_programs: Vec<Pubkey>,
+    ) -> Result<()> {
+        instructions::set_transfer_hook_programs(ctx, transfer_hook_programs)
+    }
+
+    pub fn propose_authority(
+        ctx: Context<UpdatePool>,
+        new_authority: Option<Pubkey>,
+    ) -> Result<()> {
+        instructions::propose_authority(ctx, new_authority)
+    }
+
+    pub fn accept_authority(ctx: Context<AcceptAuthority>) -> Result<()> {
+        instructions::accept_authority(ctx)
+    }
+
+    pub fn initialize_cl_pool(
+        ctx: Context<InitializeClPool>,
+        tick_spacing: u16,
+        fee_rate: u16,
+        initial_sqrt_price_x64: u128,
+    ) -> Result<()> {
+        instructions::initialize_cl_pool(ctx, tick_spacing, fee_rate, initial_sqrt_price_x64)
+    }
+
+    pub fn initialize_tick_array(
+        ctx: Context<InitializeTickArray>,
+        start_tick_index: i32,
     ) -> Result<()> {
-        instructions::swap(ctx, amount_in, min_amount_out, a_to_b)
+        instructions::initialize_tick_array(ctx, start_tick_index)
+    }
+
+    pub fn open_position(
+        ctx: Context<OpenPosition>,
+        tick_lower: i32,
+        tick_upper: i32,
+    ) -> Result<()> {
+        instructions::open_position(ctx, tick_lower, tick_upper)
+    }
+
+    pub fn increase_liquidity<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ModifyLiquidity<'info>>,
+        liquidity: u128,
+        max_amount_a: u64,
+        max_amount_b: u64,
+        deadline: Option<i64>,
+    ) -> Result<()> {
+        instructions::increase_liquidity(ctx, liquidity, max_amount_a, max_amount_b, deadline)
+    }
+
+    pub fn decrease_liquidity<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ModifyLiquidity<'info>>,
+        liquidity: u128,
+        min_amount_a: u64,
+        min_amount_b: u64,
+        deadline: Option<i64>,
+    ) -> Result<()> {
+        instructions::decrease_liquidity(ctx, liquidity, min_amount_a, min_amount_b, deadline)
+    }
+
+    pub fn collect_position_fees<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ModifyLiquidity<'info>>,
+    ) -> Result<()> {
+        instructions::collect_position_fees(ctx)
+    }
+
+    pub fn close_position(ctx: Context<ClosePosition>) -> Result<()> {
+        instructions::close_position(ctx)
+    }
+
+    pub fn cl_swap<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ClSwap<'info>>,
+        amount_in: u64,
+        min_amount_out: u64,
+        sqrt_price_limit_x64: u128,
+        a_to_b: bool,
+        tick_array_count: u8,
+        deadline: Option<i64>,
+    ) -> Result<()> {
+        instructions::cl_swap(
+            ctx,
+            amount_in,
+            min_amount_out,
+            sqrt_price_limit_x64,
+            a_to_b,
+            tick_array_count,
+            deadline,
+        )
+    }
+
+    pub fn set_cl_transfer_hook_programs(
+        ctx: Context<UpdateClPool>,
+        transfer_hook_programs: Vec<Pubkey>,
+    ) -> Result<()> {
+        instructions::set_cl_transfer_hook_programs(ctx, transfer_hook_programs)
+    }
+
+    pub fn initialize_farm(ctx: Context<InitializeFarm>, reward_rate: u64) -> Result<()> {
+        instructions::initialize_farm(ctx, reward_rate)
+    }
+
+    pub fn fund_farm<'info>(
+        ctx: Context<'_, '_, 'info, 'info, FundFarm<'info>>,
+        amount: u64,
+    ) -> Result<()> {
+        instructions::fund_farm(ctx, amount)
+    }
+
+    pub fn set_farm_reward_rate(ctx: Context<UpdateFarm>, reward_rate: u64) -> Result<()> {
+        instructions::set_farm_reward_rate(ctx, reward_rate)
+    }
+
+    pub fn open_farm_position(ctx: Context<OpenFarmPosition>) -> Result<()> {
+        instructions::open_farm_position(ctx)
+    }
+
+    pub fn stake(ctx: Context<ModifyStake>, amount: u64) -> Result<()> {
+        instructions::stake(ctx, amount)
+    }
+
+    pub fn unstake(ctx: Context<ModifyStake>, amount: u64) -> Result<()> {
+        instructions::unstake(ctx, amount)
+    }
+
+    pub fn claim_farm_rewards<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ClaimFarmRewards<'info>>,
+    ) -> Result<()> {
+        instructions::claim_farm_rewards(ctx)
+    }
+
+    pub fn close_farm_position(ctx: Context<CloseFarmPosition>) -> Result<()> {
+        instructions::close_farm_position(ctx)
+    }
+
+    pub fn initialize_multi_pool<'info>(
+        ctx: Context<'_, '_, 'info, 'info, InitializeMultiPool<'info>>,
+        fee_rate: u16,
+        mints: Vec<Pubkey>,
+    ) -> Result<()> {
+        instructions::initialize_multi_pool(ctx, fee_rate, mints)
+    }
+
+    pub fn add_multi_liquidity<'info>(
+        ctx: Context<'_, '_, 'info, 'info, AddMultiLiquidity<'info>>,
+        max_amounts: Vec<u64>,
+        min_lp_tokens: u64,
+        deadline: Option<i64>,
+    ) -> Result<()> {
+        instructions::add_multi_liquidity(ctx, max_amounts, min_lp_tokens, deadline)
+    }
+
+    pub fn remove_multi_liquidity<'info>(
+        ctx: Context<'_, '_, 'info, 'info, RemoveMultiLiquidity<'info>>,
+        lp_tokens: u64,
+        min_amounts: Vec<u64>,
+        deadline: Option<i64>,
+    ) -> Result<()> {
+        instructions::remove_multi_liquidity(ctx, lp_tokens, min_amounts, deadline)
+    }
+
+    pub fn multi_swap<'info>(
+        ctx: Context<'_, '_, 'info, 'info, MultiSwap<'info>>,
+        amount_in: u64,
+        min_amount_out: u64,
+        deadline: Option<i64>,
+    ) -> Result<()> {
+        instructions::multi_swap(ctx, amount_in, min_amount_out, deadline)
+    }
+
+    pub fn multi_zap_in<'info>(
+        ctx: Context<'_, '_, 'info, 'info, AddMultiLiquidity<'info>>,
+        amount_in: u64,
+        min_lp_tokens: u64,
+        deadline: Option<i64>,
+    ) -> Result<()> {
+        instructions::multi_zap_in(ctx, amount_in, min_lp_tokens, deadline)
+    }
+
+    pub fn multi_zap_out<'info>(
+        ctx: Context<'_, '_, 'info, 'info, RemoveMultiLiquidity<'info>>,
+        lp_tokens: u64,
+        min_amount_out: u64,
+        deadline: Option<i64>,
+    ) -> Result<()> {
+        instructions::multi_zap_out(ctx, lp_tokens, min_amount_out, deadline)
+    }
+
+    pub fn set_multi_pool_paused(ctx: Context<UpdateMultiPool>, paused: bool) -> Result<()> {
+        instructions::set_multi_pool_paused(ctx, paused)
     }
-}
\ No newline at end of file
+}
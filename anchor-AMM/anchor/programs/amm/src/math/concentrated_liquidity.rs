@@ -0,0 +1,342 @@
+//! Token amounts, price movement and fee growth for concentrated liquidity
+//! pools. Square-root prices are Q64.64, fee growth is Q64.64 per unit of
+//! liquidity and wraps on overflow like Uniswap v3.
+
+use anchor_lang::prelude::*;
+
+use crate::errors::AmmError;
+use crate::math::{mul_div, mul_div_u256, Rounding, BPS_DENOMINATOR, U256};
+
+const Q64: u128 = 1 << 64;
+
+fn to_u64(value: U256) -> Result<u64> {
+    require!(value <= U256::from(u64::MAX), AmmError::MathOverflow);
+    Ok(value.as_u64())
+}
+
+fn amount_a_delta_wide(
+    sqrt_price_a: u128,
+    sqrt_price_b: u128,
+    liquidity: u128,
+    rounding: Rounding,
+) -> Result<U256> {
+    let (lower, upper) = if sqrt_price_a < sqrt_price_b {
+        (sqrt_price_a, sqrt_price_b)
+    } else {
+        (sqrt_price_b, sqrt_price_a)
+    };
+    require!(lower > 0, AmmError::InvalidSqrtPrice);
+
+    // L * (upper - lower) / (upper * lower), with L shifted into Q64.64
+    let numerator = U256::from(liquidity) << 64;
+    let scaled = mul_div_u256(
+        numerator,
+        U256::from(upper - lower),
+        U256::from(upper),
+        rounding,
+    )?;
+    mul_div_u256(scaled, U256::one(), U256::from(lower), rounding)
+}
+
+fn amount_b_delta_wide(
+    sqrt_price_a: u128,
+    sqrt_price_b: u128,
+    liquidity: u128,
+    rounding: Rounding,
+) -> Result<U256> {
+    let diff = sqrt_price_a.abs_diff(sqrt_price_b);
+    mul_div_u256(
+        U256::from(liquidity),
+        U256::from(diff),
+        U256::from(Q64),
+        rounding,
+    )
+}
+
+/// Amount of token A spanned by `liquidity` between two square-root prices.
+pub fn amount_a_delta(
+    sqrt_price_a: u128,
+    sqrt_price_b: u128,
+    liquidity: u128,
+    rounding: Rounding,
+) -> Result<u64> {
+    to_u64(amount_a_delta_wide(
+        sqrt_price_a,
+        sqrt_price_b,
+        liquidity,
+        rounding,
+    )?)
+}
+
+/// Amount of token B spanned by `liquidity` between two square-root prices.
+pub fn amount_b_delta(
+    sqrt_price_a: u128,
+    sqrt_price_b: u128,
+    liquidity: u128,
+    rounding: Rounding,
+) -> Result<u64> {
+    to_u64(amount_b_delta_wide(
+        sqrt_price_a,
+        sqrt_price_b,
+        liquidity,
+        rounding,
+    )?)
+}
+
+/// Token amounts backing `liquidity` over `[sqrt_price_lower, sqrt_price_upper)`
+/// when the pool trades at `sqrt_price`.
+pub fn amounts_for_liquidity(
+    sqrt_price: u128,
+    sqrt_price_lower: u128,
+    sqrt_price_upper: u128,
+    liquidity: u128,
+    rounding: Rounding,
+) -> Result<(u64, u64)> {
+    if sqrt_price <= sqrt_price_lower {
+        let amount_a = amount_a_delta(sqrt_price_lower, sqrt_price_upper, liquidity, rounding)?;
+        Ok((amount_a, 0))
+    } else if sqrt_price < sqrt_price_upper {
+        let amount_a = amount_a_delta(sqrt_price, sqrt_price_upper, liquidity, rounding)?;
+        let amount_b = amount_b_delta(sqrt_price_lower, sqrt_price, liquidity, rounding)?;
+        Ok((amount_a, amount_b))
+    } else {
+        let amount_b = amount_b_delta(sqrt_price_lower, sqrt_price_upper, liquidity, rounding)?;
+        Ok((0, amount_b))
+    }
+}
+
+/// Square-root price after adding `amount_in` of the input token. Rounded
+/// so the price moves no further than the input pays for.
+pub fn next_sqrt_price_from_input(
+    sqrt_price: u128,
+    liquidity: u128,
+    amount_in: u64,
+    a_to_b: bool,
+) -> Result<u128> {
+    require!(liquidity > 0, AmmError::InsufficientLiquidity);
+    if amount_in == 0 {
+        return Ok(sqrt_price);
+    }
+
+    let next = if a_to_b {
+        // L * P / (L + amount * P), rounded up
+        let numerator = U256::from(liquidity) << 64;
+        let denominator = numerator + U256::from(amount_in) * U256::from(sqrt_price);
+        mul_div_u256(numerator, U256::from(sqrt_price), denominator, Rounding::Up)?
+    } else {
+        // P + amount / L, rounded down
+        let delta = (U256::from(amount_in) << 64) / U256::from(liquidity);
+        U256::from(sqrt_price) + delta
+    };
+
+    require!(next <= U256::from(u128::MAX), AmmError::InvalidSqrtPrice);
+    Ok(next.as_u128())
+}
+
+/// Result of swapping within a single price range of constant liquidity.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SwapStep {
+    pub sqrt_price_next: u128,
+    pub amount_in: u64,
+    pub amount_out: u64,
+    pub fee_amount: u64,
+}
+
+/// Swaps as much of `amount_remaining` as fits between `sqrt_price` and
+/// `sqrt_price_target`. The direction follows from which side of the
+/// current price the target lies on.
+pub fn compute_swap_step(
+    sqrt_price: u128,
+    sqrt_price_target: u128,
+    liquidity: u128,
+    amount_remaining: u64,
+    fee_rate: u16,
+) -> Result<SwapStep> {
+    require!(
+        (fee_rate as u64) < BPS_DENOMINATOR,
+        AmmError::InvalidFeeRate
+    );
+
+    let a_to_b = sqrt_price_target <= sqrt_price;
+    let fee_complement = BPS_DENOMINATOR - fee_rate as u64;
+    let amount_remaining_less_fee = mul_div(
+        amount_remaining,
+        fee_complement,
+        BPS_DENOMINATOR,
+        Rounding::Down,
+    )?;
+
+    let amount_to_target = if a_to_b {
+        amount_a_delta_wide(sqrt_price_target, sqrt_price, liquidity, Rounding::Up)?
+    } else {
+        amount_b_delta_wide(sqrt_price, sqrt_price_target, liquidity, Rounding::Up)?
+    };
+
+    let reached_target = U256::from(amount_remaining_less_fee) >= amount_to_target;
+    let sqrt_price_next = if reached_target {
+        sqrt_price_target
+    } else {
+        next_sqrt_price_from_input(sqrt_price, liquidity, amount_remaining_less_fee, a_to_b)?
+    };
+
+    let (amount_in, amount_out) = if a_to_b {
+        (
+            amount_a_delta(sqrt_price_next, sqrt_price, liquidity, Rounding::Up)?,
+            amount_b_delta(sqrt_price_next, sqrt_price, liquidity, Rounding::Down)?,
+        )
+    } else {
+        (
+            amount_b_delta(sqrt_price, sqrt_price_next, liquidity, Rounding::Up)?,
+            amount_a_delta(sqrt_price, sqrt_price_next, liquidity, Rounding::Down)?,
+        )
+    };
+
+    // Whatever is left of the input when the range is not exhausted is the fee
+    let fee_amount = if reached_target {
+        mul_div(amount_in, fee_rate as u64, fee_complement, Rounding::Up)?
+            .min(amount_remaining - amount_in)
+    } else {
+        amount_remaining - amount_in
+    };
+
+    Ok(SwapStep {
+        sqrt_price_next,
+        amount_in,
+        amount_out,
+        fee_amount,
+    })
+}
+
+/// Applies a signed liquidity change.
+pub fn add_liquidity_delta(liquidity: u128, delta: i128) -> Result<u128> {
+    let result = if delta >= 0 {
+        liquidity.checked_add(delta.unsigned_abs())
+    } else {
+        liquidity.checked_sub(delta.unsigned_abs())
+    };
+    result.ok_or_else(|| error!(AmmError::MathOverflow))
+}
+
+/// Fee growth per unit of liquidity accumulated inside `[tick_lower, tick_upper)`.
+pub fn fee_growth_inside(
+    tick_current: i32,
+    tick_lower: i32,
+    fee_growth_outside_lower_x64: u128,
+    tick_upper: i32,
+    fee_growth_outside_upper_x64: u128,
+    fee_growth_global_x64: u128,
+) -> u128 {
+    let below = if tick_current >= tick_lower {
+        fee_growth_outside_lower_x64
+    } else {
+        fee_growth_global_x64.wrapping_sub(fee_growth_outside_lower_x64)
+    };
+    let above = if tick_current < tick_upper {
+        fee_growth_outside_upper_x64
+    } else {
+        fee_growth_global_x64.wrapping_sub(fee_growth_outside_upper_x64)
+    };
+
+    fee_growth_global_x64
+        .wrapping_sub(below)
+        .wrapping_sub(above)
+}
+
+/// Fees earned by `liquidity` since fee growth inside its range was
+/// `fee_growth_inside_last_x64`, rounded down.
+pub fn fees_earned(
+    liquidity: u128,
+    fee_growth_inside_x64: u128,
+    fee_growth_inside_last_x64: u128,
+) -> Result<u64> {
+    let growth = fee_growth_inside_x64.wrapping_sub(fee_growth_inside_last_x64);
+    to_u64((U256::from(growth) * U256::from(liquidity)) >> 64)
+}
+
+/// Fee growth per unit of liquidity from charging `fee_amount` to `liquidity`.
+pub fn fee_growth_delta(fee_amount: u64, liquidity: u128) -> u128 {
+    if liquidity == 0 {
+        return 0;
+    }
+    ((fee_amount as u128) << 64) / liquidity
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::tick_math::sqrt_price_at_tick;
+    use proptest::prelude::*;
+
+    #[test]
+    fn amounts_for_liquidity_depend_on_price_position() {
+        let lower = sqrt_price_at_tick(-1000).unwrap();
+        let upper = sqrt_price_at_tick(1000).unwrap();
+        let liquidity = 1_000_000_000_000u128;
+
+        let (a, b) = amounts_for_liquidity(Q64, lower, upper, liquidity, Rounding::Up).unwrap();
+        assert!(a > 0 && b > 0);
+        // Symmetric range around price 1 holds roughly equal amounts
+        assert!(a.abs_diff(b) <= 1);
+
+        let (a, b) =
+            amounts_for_liquidity(lower - 1, lower, upper, liquidity, Rounding::Up).unwrap();
+        assert!(a > 0 && b == 0);
+        let (a, b) = amounts_for_liquidity(upper, lower, upper, liquidity, Rounding::Up).unwrap();
+        assert!(a == 0 && b > 0);
+    }
+
+    #[test]
+    fn swap_step_stops_at_target_when_input_suffices() {
+        let target = sqrt_price_at_tick(-10).unwrap();
+        let step = compute_swap_step(Q64, target, 1_000_000_000, u64::MAX / 2, 30).unwrap();
+        assert_eq!(step.sqrt_price_next, target);
+        assert!(step.amount_out < step.amount_in);
+    }
+
+    #[test]
+    fn swap_step_without_liquidity_moves_price_for_free() {
+        let target = sqrt_price_at_tick(100).unwrap();
+        let step = compute_swap_step(Q64, target, 0, 1_000, 30).unwrap();
+        assert_eq!(step.sqrt_price_next, target);
+        assert_eq!(step.amount_in, 0);
+        assert_eq!(step.amount_out, 0);
+    }
+
+    #[test]
+    fn fee_growth_inside_excludes_outside_growth() {
+        // Range [0, 10) with the price above it: only growth below the upper tick counts
+        assert_eq!(fee_growth_inside(20, 0, 100, 10, 300, 1000), 200);
+        // Price inside the range
+        assert_eq!(fee_growth_inside(5, 0, 100, 10, 300, 1000), 600);
+    }
+
+    proptest! {
+        #[test]
+        fn swap_step_conserves_input(
+            tick in -50_000i32..50_000,
+            liquidity in 1u128..1u128 << 96,
+            amount in 1u64..1u64 << 48,
+            fee_rate in 0u16..10000,
+            a_to_b in any::<bool>(),
+        ) {
+            let sqrt_price = sqrt_price_at_tick(tick).unwrap();
+            let target = sqrt_price_at_tick(if a_to_b { tick - 1000 } else { tick + 1000 }).unwrap();
+
+            let step = compute_swap_step(sqrt_price, target, liquidity, amount, fee_rate).unwrap();
+            prop_assert!(step.amount_in + step.fee_amount <= amount);
+            if a_to_b {
+                prop_assert!(step.sqrt_price_next >= target && step.sqrt_price_next <= sqrt_price);
+            } else {
+                prop_assert!(step.sqrt_price_next <= target && step.sqrt_price_next >= sqrt_price);
+            }
+
+            // The pool never pays out more than the liquidity it moved through holds
+            let held = if a_to_b {
+                amount_b_delta(step.sqrt_price_next, sqrt_price, liquidity, Rounding::Down).unwrap()
+            } else {
+                amount_a_delta(sqrt_price, step.sqrt_price_next, liquidity, Rounding::Down).unwrap()
+            };
+            prop_assert!(step.amount_out <= held);
+        }
+    }
+}
@@ -0,0 +1,270 @@
+//! Constant product (x * y = k) curve.
+
+use anchor_lang::prelude::*;
+
+use crate::errors::AmmError;
+use crate::math::{mul_div, sqrt, Rounding, BPS_DENOMINATOR, U256};
+
+/// LP tokens minted for the very first deposit into an empty pool.
+pub fn initial_lp_tokens(amount_a: u64, amount_b: u64) -> Result<u64> {
+    let lp_tokens = sqrt((amount_a as u128) * (amount_b as u128));
+    u64::try_from(lp_tokens).map_err(|_| error!(AmmError::MathOverflow))
+}
+
+/// LP tokens minted for a deposit into a pool that already holds liquidity.
+/// Each side is rounded down and the smaller share wins.
+pub fn lp_tokens_for_deposit(
+    amount_a: u64,
+    amount_b: u64,
+    reserve_a: u64,
+    reserve_b: u64,
+    lp_supply: u64,
+) -> Result<u64> {
+    require!(
+        reserve_a > 0 && reserve_b > 0,
+        AmmError::InsufficientLiquidity
+    );
+
+    let lp_from_a = mul_div(amount_a, lp_supply, reserve_a, Rounding::Down)?;
+    let lp_from_b = mul_div(amount_b, lp_supply, reserve_b, Rounding::Down)?;
+    Ok(std::cmp::min(lp_from_a, lp_from_b))
+}
+
+/// Largest deposit within the desired amounts that matches the pool ratio,
+/// as Uniswap's router picks it. The side scaled to the ratio is rounded up.
+pub fn optimal_deposit(
+    amount_a_desired: u64,
+    amount_b_desired: u64,
+    reserve_a: u64,
+    reserve_b: u64,
+) -> Result<(u64, u64)> {
+    require!(
+        reserve_a > 0 && reserve_b > 0,
+        AmmError::InsufficientLiquidity
+    );
+
+    let amount_b_optimal = mul_div(amount_a_desired, reserve_b, reserve_a, Rounding::Up);
+    if let Ok(amount_b) = amount_b_optimal {
+        if amount_b <= amount_b_desired {
+            return Ok((amount_a_desired, amount_b));
+        }
+    }
+
+    // B is the limiting side, so A scaled from it fits within its desired amount
+    let amount_a = mul_div(amount_b_desired, reserve_a, reserve_b, Rounding::Up)?;
+    require!(amount_a <= amount_a_desired, AmmError::MathOverflow);
+    Ok((amount_a, amount_b_desired))
+}
+
+/// Token amounts released when burning `lp_tokens`, rounded down.
+pub fn withdraw_amounts(
+    lp_tokens: u64,
+    reserve_a: u64,
+    reserve_b: u64,
+    lp_supply: u64,
+) -> Result<(u64, u64)> {
+    require!(lp_supply > 0, AmmError::InsufficientLiquidity);
+    require!(lp_tokens <= lp_supply, AmmError::InvalidAmount);
+
+    let amount_a = mul_div(lp_tokens, reserve_a, lp_supply, Rounding::Down)?;
+    let amount_b = mul_div(lp_tokens, reserve_b, lp_supply, Rounding::Down)?;
+    Ok((amount_a, amount_b))
+}
+
+/// Output of a swap for an input that has already had its fee removed,
+/// rounded down.
+pub fn swap_output(amount_in: u64, reserve_in: u64, reserve_out: u64) -> Result<u64> {
+    require!(
+        reserve_in > 0 && reserve_out > 0,
+        AmmError::InsufficientLiquidity
+    );
+
+    let denominator = (reserve_in as u128) + (amount_in as u128);
+    let amount_out = (amount_in as u128) * (reserve_out as u128) / denominator;
+
+    // Strictly below `reserve_out`, so it always fits back into a u64
+    Ok(amount_out as u64)
+}
+
+/// Input, after fees, needed for a swap to pay out exactly `amount_out`,
+/// rounded up.
+pub fn swap_input(amount_out: u64, reserve_in: u64, reserve_out: u64) -> Result<u64> {
+    require!(
+        reserve_in > 0 && amount_out < reserve_out,
+        AmmError::InsufficientLiquidity
+    );
+
+    mul_div(
+        reserve_in,
+        amount_out,
+        reserve_out - amount_out,
+        Rounding::Up,
+    )
+}
+
+/// Part of a single-sided deposit of `amount_in` to swap so that the output
+/// matches the rest at the ratio the swap leaves behind, in closed form:
+/// `s = (sqrt(R²(1 + γ)² + 4γκ·R·A) - R(1 + γ)) / 2γκ`, where `γ = 1 - fee`
+/// is the share of the input the curve prices and `κ = 1 - fee · protocol_fee`
+/// the share that joins the reserves. Fee rounding makes this an estimate
+/// within a few units of the exact split.
+pub fn zap_swap_estimate(
+    amount_in: u64,
+    reserve_in: u64,
+    fee_rate: u16,
+    protocol_fee_rate: u16,
+) -> u64 {
+    let denominator = U256::from(BPS_DENOMINATOR);
+    let gamma = U256::from(BPS_DENOMINATOR.saturating_sub(fee_rate as u64));
+    let kappa = denominator * denominator - U256::from(fee_rate) * U256::from(protocol_fee_rate);
+    if gamma.is_zero() {
+        return amount_in;
+    }
+
+    // γ is scaled by the denominator and κ by its square, so the quadratic
+    // is multiplied through by the denominator cubed. Every term stays below
+    // 2^213 for u64 inputs.
+    let reserve_in = U256::from(reserve_in);
+    let b = reserve_in * (denominator + gamma) * denominator * denominator;
+    let four_ac = U256::from(4)
+        * gamma
+        * kappa
+        * reserve_in
+        * U256::from(amount_in)
+        * denominator
+        * denominator
+        * denominator;
+    let estimate = ((b * b + four_ac).integer_sqrt() - b) / (U256::from(2) * gamma * kappa);
+    estimate.min(U256::from(amount_in)).as_u64()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    const NINE_DECIMALS: u64 = 1_000_000_000;
+
+    #[test]
+    fn initial_deposit_with_nine_decimals_does_not_overflow() {
+        let amount = 1_000_000 * NINE_DECIMALS;
+        assert_eq!(initial_lp_tokens(amount, amount).unwrap(), amount);
+        assert_eq!(initial_lp_tokens(u64::MAX, u64::MAX).unwrap(), u64::MAX);
+    }
+
+    #[test]
+    fn deposit_mints_smaller_share() {
+        let lp = lp_tokens_for_deposit(100, 300, 1000, 2000, 500).unwrap();
+        assert_eq!(lp, 50);
+        assert!(lp_tokens_for_deposit(1, 1, 0, 10, 10).is_err());
+    }
+
+    #[test]
+    fn optimal_deposit_keeps_pool_ratio() {
+        assert_eq!(optimal_deposit(100, 500, 1000, 2000).unwrap(), (100, 200));
+        assert_eq!(optimal_deposit(500, 100, 1000, 2000).unwrap(), (50, 100));
+        // 10 * 2000 / 3000 = 6.6..., charged as 7
+        assert_eq!(optimal_deposit(10, 100, 3000, 2000).unwrap(), (10, 7));
+        assert!(optimal_deposit(1, 1, 0, 10).is_err());
+    }
+
+    #[test]
+    fn withdraw_rounds_down() {
+        assert_eq!(withdraw_amounts(1, 10, 20, 3).unwrap(), (3, 6));
+        assert!(withdraw_amounts(4, 10, 20, 3).is_err());
+        assert!(withdraw_amounts(1, 10, 20, 0).is_err());
+    }
+
+    #[test]
+    fn swap_matches_reference_formula() {
+        assert_eq!(
+            swap_output(997, 10_000, 10_000).unwrap(),
+            997 * 10_000 / (10_000 + 997)
+        );
+
+        let large = 5_000_000 * NINE_DECIMALS;
+        assert!(swap_output(large, large, large).unwrap() < large);
+        assert!(swap_output(1, 0, large).is_err());
+    }
+
+    #[test]
+    fn swap_input_rounds_up() {
+        // 10_000 * 997 / (10_000 - 997) = 1107.4...
+        assert_eq!(swap_input(997, 10_000, 10_000).unwrap(), 1108);
+        assert!(swap_input(10_000, 10_000, 10_000).is_err());
+        assert!(swap_input(1, 0, 10_000).is_err());
+    }
+
+    proptest! {
+        #[test]
+        fn swap_never_decreases_k(
+            amount_in in 1..u64::MAX / 4,
+            reserve_in in 1..u64::MAX / 4,
+            reserve_out in 1..u64::MAX,
+        ) {
+            let amount_out = swap_output(amount_in, reserve_in, reserve_out).unwrap();
+            prop_assert!(amount_out < reserve_out);
+
+            let k_before = (reserve_in as u128) * (reserve_out as u128);
+            let k_after = ((reserve_in + amount_in) as u128) * ((reserve_out - amount_out) as u128);
+            prop_assert!(k_after >= k_before);
+        }
+
+        #[test]
+        fn swap_input_buys_at_least_amount_out(
+            amount_out in 1..1u64 << 40,
+            reserve_in in 1..1u64 << 40,
+            reserve_out in 1..1u64 << 40,
+        ) {
+            prop_assume!(amount_out < reserve_out);
+            let amount_in = swap_input(amount_out, reserve_in, reserve_out);
+            prop_assume!(amount_in.is_ok());
+            let amount_in = amount_in.unwrap();
+            prop_assert!(swap_output(amount_in, reserve_in, reserve_out).unwrap() >= amount_out);
+            if amount_in > 1 {
+                prop_assert!(swap_output(amount_in - 1, reserve_in, reserve_out).unwrap() < amount_out);
+            }
+        }
+
+        #[test]
+        fn optimal_deposit_stays_within_desired(
+            amount_a_desired in 1..1u64 << 40,
+            amount_b_desired in 1..1u64 << 40,
+            reserve_a in 1..1u64 << 40,
+            reserve_b in 1..1u64 << 40,
+        ) {
+            let (amount_a, amount_b) =
+                optimal_deposit(amount_a_desired, amount_b_desired, reserve_a, reserve_b).unwrap();
+            prop_assert!(amount_a <= amount_a_desired && amount_b <= amount_b_desired);
+            prop_assert!(amount_a == amount_a_desired || amount_b == amount_b_desired);
+
+            // Off the exact ratio by less than one unit of rounding
+            let scaled_a = amount_a as u128 * reserve_b as u128;
+            let scaled_b = amount_b as u128 * reserve_a as u128;
+            prop_assert!(scaled_a.abs_diff(scaled_b) < reserve_a.max(reserve_b) as u128);
+        }
+
+        #[test]
+        fn deposit_then_withdraw_never_profits(
+            amount_a in 1..1u64 << 40,
+            amount_b in 1..1u64 << 40,
+            reserve_a in 1..1u64 << 40,
+            reserve_b in 1..1u64 << 40,
+            lp_supply in 1..1u64 << 40,
+        ) {
+            let lp = lp_tokens_for_deposit(amount_a, amount_b, reserve_a, reserve_b, lp_supply);
+            prop_assume!(lp.as_ref().is_ok_and(|lp| *lp <= u64::MAX / 2));
+            let lp = lp.unwrap();
+
+            let (out_a, out_b) = withdraw_amounts(
+                lp,
+                reserve_a + amount_a,
+                reserve_b + amount_b,
+                lp_supply + lp,
+            )
+            .unwrap();
+            prop_assert!(out_a <= amount_a);
+            prop_assert!(out_b <= amount_b);
+        }
+    }
+}
@@ -0,0 +1,83 @@
+//! Volatility-driven swap fees. Every swap adds the relative move of the
+//! curve's spot price it caused, in basis points, to a volatility
+//! accumulator that decays linearly to zero, and the fee rises with that
+//! accumulator between a minimum and a maximum.
+
+use crate::math::{BPS_DENOMINATOR, U256};
+
+/// `volatility` recorded `elapsed` seconds ago, decayed linearly so that it
+/// reaches zero after `decay_period` seconds.
+pub fn decayed_volatility(volatility: u64, elapsed: i64, decay_period: u32) -> u64 {
+    let elapsed = elapsed.max(0) as u128;
+    let decay_period = decay_period as u128;
+    if elapsed >= decay_period {
+        return 0;
+    }
+    (volatility as u128 * (decay_period - elapsed) / decay_period) as u64
+}
+
+/// Relative fall, in basis points and rounded down, of the input token's
+/// spot price from `price_before_x64` to `price_after_x64`, both Q64.64.
+/// Zero if the price did not fall, so never above `BPS_DENOMINATOR`.
+pub fn price_move_bps(price_before_x64: u128, price_after_x64: u128) -> u64 {
+    if price_before_x64 == 0 || price_after_x64 >= price_before_x64 {
+        return 0;
+    }
+    let fall = U256::from(price_before_x64 - price_after_x64);
+    (fall * U256::from(BPS_DENOMINATOR) / U256::from(price_before_x64)).as_u64()
+}
+
+/// Fee rate for `volatility`: `min_fee_rate` plus `volatility_multiplier`
+/// basis points of it, capped at `max_fee_rate`.
+pub fn fee_rate(
+    min_fee_rate: u16,
+    max_fee_rate: u16,
+    volatility_multiplier: u16,
+    volatility: u64,
+) -> u16 {
+    let variable = volatility as u128 * volatility_multiplier as u128 / BPS_DENOMINATOR as u128;
+    (min_fee_rate as u128 + variable).min(max_fee_rate as u128) as u16
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    #[test]
+    fn volatility_decays_linearly_to_zero() {
+        assert_eq!(decayed_volatility(1_000, 0, 100), 1_000);
+        assert_eq!(decayed_volatility(1_000, 25, 100), 750);
+        assert_eq!(decayed_volatility(1_000, 100, 100), 0);
+        assert_eq!(decayed_volatility(1_000, 500, 100), 0);
+        assert_eq!(decayed_volatility(1_000, -5, 100), 1_000);
+    }
+
+    #[test]
+    fn price_move_measures_the_fall_of_the_input_price() {
+        assert_eq!(price_move_bps(10 << 64, 9 << 64), 1_000);
+        assert_eq!(price_move_bps(4 << 64, 1 << 64), 7_500);
+        assert_eq!(price_move_bps(1 << 64, 1 << 64), 0);
+        assert_eq!(price_move_bps(1 << 64, 2 << 64), 0);
+        assert_eq!(price_move_bps(0, 1 << 64), 0);
+    }
+
+    #[test]
+    fn fee_rate_stays_within_bounds() {
+        assert_eq!(fee_rate(5, 100, 1_000, 0), 5);
+        assert_eq!(fee_rate(5, 100, 1_000, 200), 25);
+        assert_eq!(fee_rate(5, 100, 1_000, 10_000), 100);
+        assert_eq!(fee_rate(5, 100, u16::MAX, u64::MAX), 100);
+    }
+
+    proptest! {
+        #[test]
+        fn price_move_is_at_most_one_hundred_percent(
+            price_before in 0u128..=u128::MAX,
+            price_after in 0u128..=u128::MAX,
+        ) {
+            let bps = price_move_bps(price_before, price_after);
+            prop_assert!(bps <= BPS_DENOMINATOR);
+        }
+    }
+}
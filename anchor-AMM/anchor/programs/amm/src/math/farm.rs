@@ -0,0 +1,82 @@
+//! Reward emission for LP farms. Rewards are spread over staked LP tokens
+//! through a Q64.64 reward-per-share accumulator that wraps on overflow,
+//! and every share of it is rounded down so stakers can never be owed more
+//! than was emitted.
+
+use anchor_lang::prelude::*;
+
+use crate::errors::AmmError;
+use crate::math::U256;
+
+/// Rewards emitted at `reward_rate` per second over `elapsed` seconds,
+/// capped at the funded rewards still `available`.
+pub fn rewards_emitted(reward_rate: u64, elapsed: u64, available: u64) -> u64 {
+    let emitted = (reward_rate as u128) * (elapsed as u128);
+    emitted.min(available as u128) as u64
+}
+
+/// Growth of the reward per staked token from spreading `rewards` over
+/// `total_staked`.
+pub fn reward_per_share_delta(rewards: u64, total_staked: u64) -> u128 {
+    if total_staked == 0 {
+        return 0;
+    }
+    ((rewards as u128) << 64) / total_staked as u128
+}
+
+/// Rewards earned by `staked` tokens since the reward per share was
+/// `reward_per_share_last_x64`, rounded down.
+pub fn rewards_earned(
+    staked: u64,
+    reward_per_share_x64: u128,
+    reward_per_share_last_x64: u128,
+) -> Result<u64> {
+    let growth = reward_per_share_x64.wrapping_sub(reward_per_share_last_x64);
+    let earned = (U256::from(growth) * U256::from(staked)) >> 64;
+    require!(earned <= U256::from(u64::MAX), AmmError::MathOverflow);
+    Ok(earned.as_u64())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    #[test]
+    fn emission_stops_when_funding_runs_out() {
+        assert_eq!(rewards_emitted(100, 10, 5_000), 1_000);
+        assert_eq!(rewards_emitted(100, 10, 600), 600);
+        assert_eq!(rewards_emitted(u64::MAX, u64::MAX, 7), 7);
+    }
+
+    #[test]
+    fn rewards_split_by_stake() {
+        let delta = reward_per_share_delta(1_000, 400);
+        assert_eq!(rewards_earned(100, delta, 0).unwrap(), 250);
+        assert_eq!(rewards_earned(300, delta, 0).unwrap(), 750);
+        assert_eq!(reward_per_share_delta(1_000, 0), 0);
+    }
+
+    #[test]
+    fn accumulator_wraps() {
+        let last = u128::MAX - (5u128 << 64) + 1;
+        let now = last.wrapping_add(10u128 << 64);
+        assert_eq!(rewards_earned(3, now, last).unwrap(), 30);
+    }
+
+    proptest! {
+        #[test]
+        fn stakers_never_earn_more_than_emitted(
+            rewards in 0u64..=u64::MAX / 2,
+            stakes in prop::collection::vec(1u64..=u64::MAX / 16, 1..8),
+        ) {
+            let total: u64 = stakes.iter().sum();
+            let delta = reward_per_share_delta(rewards, total);
+            let earned: u128 = stakes
+                .iter()
+                .map(|stake| rewards_earned(*stake, delta, 0).unwrap() as u128)
+                .sum();
+            prop_assert!(earned <= rewards as u128);
+        }
+    }
+}
@@ -0,0 +1,614 @@
+//! Integer-only arithmetic shared by every pool instruction.
+//!
+//! Intermediate products are computed in `u128` so that full-range `u64`
+//! amounts never overflow, and every division states which way it rounds.
+//! Rounding is always chosen in the pool's favour: amounts paid out are
+//! rounded down, amounts charged are rounded up.
+
+use anchor_lang::prelude::*;
+
+use crate::errors::AmmError;
+
+pub mod concentrated_liquidity;
+pub mod constant_product;
+pub mod dynamic_fee;
+pub mod farm;
+pub mod multi_asset;
+pub mod stable_swap;
+pub mod tick_math;
+pub mod weighted;
+
+pub use constant_product::*;
+
+#[allow(clippy::all)]
+mod uint_types {
+    uint::construct_uint! {
+        /// 256-bit unsigned integer used where `u128` intermediates can overflow.
+        pub struct U256(4);
+    }
+
+    uint::construct_uint! {
+        /// 512-bit unsigned integer for products of two `U256` values.
+        pub struct U512(8);
+    }
+}
+
+pub use uint_types::{U256, U512};
+
+/// Denominator for every rate expressed in basis points.
+pub const BPS_DENOMINATOR: u64 = 10000;
+
+/// LP tokens minted to the pool itself on the first deposit and never released.
+pub const MINIMUM_LIQUIDITY: u64 = 1000;
+
+/// Invariant used to price a pool, with any parameters resolved for the
+/// current slot.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Curve {
+    ConstantProduct,
+    StableSwap {
+        amp: u64,
+    },
+    /// Weighted product, oriented for swaps from the `weight_in` token to
+    /// the `weight_out` one.
+    Weighted {
+        weight_in: u16,
+        weight_out: u16,
+    },
+}
+
+impl Curve {
+    /// This curve oriented for swaps in the opposite direction.
+    pub fn reversed(self) -> Self {
+        match self {
+            Curve::Weighted {
+                weight_in,
+                weight_out,
+            } => Curve::Weighted {
+                weight_in: weight_out,
+                weight_out: weight_in,
+            },
+            curve => curve,
+        }
+    }
+
+    /// Output of a swap for an input that has already had its fee removed.
+    pub fn swap_output(&self, amount_in: u64, reserve_in: u64, reserve_out: u64) -> Result<u64> {
+        match *self {
+            Curve::ConstantProduct => {
+                constant_product::swap_output(amount_in, reserve_in, reserve_out)
+            }
+            Curve::StableSwap { amp } => {
+                stable_swap::swap_output(amp, amount_in, reserve_in, reserve_out)
+            }
+            Curve::Weighted {
+                weight_in,
+                weight_out,
+            } => weighted::swap_output(weight_in, weight_out, amount_in, reserve_in, reserve_out),
+        }
+    }
+
+    /// Input, after fees, needed for a swap to pay out exactly `amount_out`.
+    pub fn swap_input(&self, amount_out: u64, reserve_in: u64, reserve_out: u64) -> Result<u64> {
+        match *self {
+            Curve::ConstantProduct => {
+                constant_product::swap_input(amount_out, reserve_in, reserve_out)
+            }
+            Curve::StableSwap { amp } => {
+                stable_swap::swap_input(amp, amount_out, reserve_in, reserve_out)
+            }
+            Curve::Weighted {
+                weight_in,
+                weight_out,
+            } => weighted::swap_input(weight_in, weight_out, amount_out, reserve_in, reserve_out),
+        }
+    }
+
+    /// Price of one unit of the input token in output tokens as Q64.64, or
+    /// zero while the pool is empty.
+    pub fn spot_price_x64(&self, reserve_in: u64, reserve_out: u64) -> Result<u128> {
+        match *self {
+            Curve::ConstantProduct => Ok(spot_price_x64(reserve_in, reserve_out)),
+            Curve::StableSwap { amp } => stable_swap::spot_price_x64(amp, reserve_in, reserve_out),
+            Curve::Weighted {
+                weight_in,
+                weight_out,
+            } => Ok(weighted::spot_price_x64(
+                weight_in,
+                reserve_in,
+                weight_out,
+                reserve_out,
+            )),
+        }
+    }
+
+    /// LP tokens minted for the very first deposit into an empty pool. A
+    /// weighted curve must be oriented from A to B.
+    pub fn initial_lp_tokens(&self, amount_a: u64, amount_b: u64) -> Result<u64> {
+        match *self {
+            Curve::ConstantProduct => constant_product::initial_lp_tokens(amount_a, amount_b),
+            Curve::StableSwap { amp } => stable_swap::initial_lp_tokens(amp, amount_a, amount_b),
+            Curve::Weighted {
+                weight_in,
+                weight_out,
+            } => weighted::initial_lp_tokens(weight_in, weight_out, amount_a, amount_b),
+        }
+    }
+}
+
+/// Outcome of a swap.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SwapResult {
+    /// Tokens taken from the user, fee included.
+    pub amount_in: u64,
+    /// Tokens paid out of the output vault.
+    pub amount_out: u64,
+    /// Total fee charged on the input, LP and protocol share combined.
+    pub fee: u64,
+    /// Part of `fee` accrued to the protocol.
+    pub protocol_fee: u64,
+}
+
+/// Prices an exact-input swap on `curve`. The fee is taken from the input
+/// before it reaches the curve.
+pub fn swap_exact_in(
+    curve: Curve,
+    amount_in: u64,
+    reserve_in: u64,
+    reserve_out: u64,
+    fee_rate: u16,
+    protocol_fee_rate: u16,
+) -> Result<SwapResult> {
+    let fee = fee_amount(amount_in, fee_rate)?;
+    let protocol_fee = protocol_fee_amount(fee, protocol_fee_rate)?;
+    let amount_out = curve.swap_output(amount_in - fee, reserve_in, reserve_out)?;
+
+    Ok(SwapResult {
+        amount_in,
+        amount_out,
+        fee,
+        protocol_fee,
+    })
+}
+
+/// Prices an exact-output swap on `curve`: the input, fee included, that
+/// pays out exactly `amount_out`. Every step rounds the input up.
+pub fn swap_exact_out(
+    curve: Curve,
+    amount_out: u64,
+    reserve_in: u64,
+    reserve_out: u64,
+    fee_rate: u16,
+    protocol_fee_rate: u16,
+) -> Result<SwapResult> {
+    let amount_in_after_fee = curve.swap_input(amount_out, reserve_in, reserve_out)?;
+    let amount_in = amount_before_fee(amount_in_after_fee, fee_rate)?;
+    let fee = fee_amount(amount_in, fee_rate)?;
+    let protocol_fee = protocol_fee_amount(fee, protocol_fee_rate)?;
+
+    Ok(SwapResult {
+        amount_in,
+        amount_out,
+        fee,
+        protocol_fee,
+    })
+}
+
+/// The zap search on curves without a closed form stops once the smallest
+/// covering split is known to within `amount_in >> ZAP_SEARCH_PRECISION`,
+/// which bounds it to `ZAP_SEARCH_PRECISION + 1` curve evaluations.
+pub const ZAP_SEARCH_PRECISION: u32 = 24;
+
+/// Distance from the constant product closed form within which the zap
+/// search first looks for the exact split. One unit of output rounding moves
+/// the split by about `(1 + amount_in / reserve_in)²` units of input, so the
+/// margin grows with it.
+fn zap_estimate_margin(amount_in: u64, reserve_in: u64) -> u64 {
+    let depth = 1 + amount_in / reserve_in;
+    depth.saturating_mul(depth).saturating_mul(8)
+}
+
+/// Prices the swap leg of a single-sided deposit of `amount_in`: the smallest
+/// part to swap such that the swap output covers the rest at the pool ratio
+/// left behind by the swap. On the constant product curve this is exact, and
+/// any output-side excess is at most the output of one more input unit. On
+/// other curves the split may be up to `amount_in >> ZAP_SEARCH_PRECISION`
+/// larger. The excess stays in the pool either way.
+pub fn zap_swap(
+    curve: Curve,
+    amount_in: u64,
+    reserve_in: u64,
+    reserve_out: u64,
+    fee_rate: u16,
+    protocol_fee_rate: u16,
+) -> Result<SwapResult> {
+    zap_swap_with_steps(
+        curve,
+        amount_in,
+        reserve_in,
+        reserve_out,
+        fee_rate,
+        protocol_fee_rate,
+    )
+    .map(|(swap, _)| swap)
+}
+
+/// `zap_swap`, along with how many swaps it priced to find the split.
+fn zap_swap_with_steps(
+    curve: Curve,
+    amount_in: u64,
+    reserve_in: u64,
+    reserve_out: u64,
+    fee_rate: u16,
+    protocol_fee_rate: u16,
+) -> Result<(SwapResult, u32)> {
+    require!(
+        reserve_in > 0 && reserve_out > 0,
+        AmmError::InsufficientLiquidity
+    );
+
+    let mut steps = 0;
+    let mut quote = |amount: u64| {
+        steps += 1;
+        swap_exact_in(
+            curve,
+            amount,
+            reserve_in,
+            reserve_out,
+            fee_rate,
+            protocol_fee_rate,
+        )
+    };
+
+    let covers_rest = |swap: &SwapResult| -> Result<bool> {
+        let (reserve_in_after, reserve_out_after) =
+            reserves_after_swap(swap, reserve_in, reserve_out)?;
+        let rest = amount_in - swap.amount_in;
+        Ok(U256::from(swap.amount_out) * U256::from(reserve_in_after)
+            >= U256::from(rest) * U256::from(reserve_out_after))
+    };
+
+    // The smallest covering split lies in `low..=high`, and `best` prices `high`
+    let (mut low, mut high, mut best, tolerance) = if curve == Curve::ConstantProduct {
+        // The closed form is off only by fee and output rounding, so a bracket
+        // of `zap_estimate_margin` around it holds the split. Should rounding
+        // ever push it further, the rest of the range is searched.
+        let estimate =
+            constant_product::zap_swap_estimate(amount_in, reserve_in, fee_rate, protocol_fee_rate);
+        let margin = zap_estimate_margin(amount_in, reserve_in);
+        let swap = quote(estimate)?;
+        if covers_rest(&swap)? {
+            let probe = estimate.saturating_sub(margin);
+            let below = quote(probe)?;
+            if covers_rest(&below)? {
+                (0, probe, below, 0)
+            } else {
+                (probe + 1, estimate, swap, 0)
+            }
+        } else {
+            let probe = estimate.saturating_add(margin).min(amount_in);
+            let above = quote(probe)?;
+            if covers_rest(&above)? {
+                (estimate + 1, probe, above, 0)
+            } else {
+                // Swapping everything trivially covers an empty rest
+                (probe + 1, amount_in, quote(amount_in)?, 0)
+            }
+        }
+    } else {
+        // Swapping everything trivially covers an empty rest
+        (
+            0,
+            amount_in,
+            quote(amount_in)?,
+            amount_in >> ZAP_SEARCH_PRECISION,
+        )
+    };
+
+    while high - low > tolerance {
+        let mid = low + (high - low) / 2;
+        let swap = quote(mid)?;
+        if covers_rest(&swap)? {
+            high = mid;
+            best = swap;
+        } else {
+            low = mid + 1;
+        }
+    }
+
+    Ok((best, steps))
+}
+
+/// Reserves backing LP shares once `swap` has executed. The protocol's cut
+/// of the fee sits in the vault but outside the reserves.
+pub fn reserves_after_swap(
+    swap: &SwapResult,
+    reserve_in: u64,
+    reserve_out: u64,
+) -> Result<(u64, u64)> {
+    let reserve_in_after = reserve_in
+        .checked_add(swap.amount_in - swap.protocol_fee)
+        .ok_or(AmmError::MathOverflow)?;
+    Ok((reserve_in_after, reserve_out - swap.amount_out))
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Rounding {
+    Down,
+    Up,
+}
+
+/// Computes `a * b / denominator` without intermediate overflow.
+pub fn mul_div(a: u64, b: u64, denominator: u64, rounding: Rounding) -> Result<u64> {
+    require!(denominator > 0, AmmError::MathOverflow);
+
+    let product = (a as u128) * (b as u128);
+    let denominator = denominator as u128;
+    let mut quotient = product / denominator;
+    if rounding == Rounding::Up && quotient * denominator < product {
+        quotient += 1;
+    }
+
+    u64::try_from(quotient).map_err(|_| error!(AmmError::MathOverflow))
+}
+
+/// Computes `a * b / denominator` in 512-bit precision.
+pub fn mul_div_u256(a: U256, b: U256, denominator: U256, rounding: Rounding) -> Result<U256> {
+    require!(!denominator.is_zero(), AmmError::MathOverflow);
+
+    let widen = |x: U256| {
+        let mut words = [0u64; 8];
+        words[..4].copy_from_slice(&x.0);
+        U512(words)
+    };
+
+    let product = widen(a) * widen(b);
+    let denominator = widen(denominator);
+    let mut quotient = product / denominator;
+    if rounding == Rounding::Up && quotient * denominator < product {
+        quotient += U512::one();
+    }
+
+    require!(
+        quotient.0[4..].iter().all(|word| *word == 0),
+        AmmError::MathOverflow
+    );
+    let mut words = [0u64; 4];
+    words.copy_from_slice(&quotient.0[..4]);
+    Ok(U256(words))
+}
+
+/// Integer square root, rounded down.
+pub fn sqrt(n: u128) -> u128 {
+    if n < 2 {
+        return n;
+    }
+
+    // Newton's method from an initial guess that is guaranteed to be above the root
+    let mut x = 1u128 << ((128 - n.leading_zeros()).div_ceil(2));
+    loop {
+        let y = (x + n / x) >> 1;
+        if y >= x {
+            return x;
+        }
+        x = y;
+    }
+}
+
+/// Price of one unit of the base token in quote tokens as Q64.64, or zero
+/// while the pool is empty.
+pub fn spot_price_x64(reserve_base: u64, reserve_quote: u64) -> u128 {
+    if reserve_base == 0 {
+        return 0;
+    }
+    ((reserve_quote as u128) << 64) / reserve_base as u128
+}
+
+/// Swap fee charged on `amount_in`, rounded up.
+pub fn fee_amount(amount_in: u64, fee_rate: u16) -> Result<u64> {
+    require!(
+        (fee_rate as u64) < BPS_DENOMINATOR,
+        AmmError::InvalidFeeRate
+    );
+    mul_div(amount_in, fee_rate as u64, BPS_DENOMINATOR, Rounding::Up)
+}
+
+/// Smallest input that still leaves `amount_after_fee` once `fee_amount` is
+/// taken from it.
+pub fn amount_before_fee(amount_after_fee: u64, fee_rate: u16) -> Result<u64> {
+    require!(
+        (fee_rate as u64) < BPS_DENOMINATOR,
+        AmmError::InvalidFeeRate
+    );
+    mul_div(
+        amount_after_fee,
+        BPS_DENOMINATOR,
+        BPS_DENOMINATOR - fee_rate as u64,
+        Rounding::Up,
+    )
+}
+
+/// Part of `fee` owed to the protocol, rounded down so LPs keep the remainder.
+pub fn protocol_fee_amount(fee: u64, protocol_fee_rate: u16) -> Result<u64> {
+    require!(
+        (protocol_fee_rate as u64) <= BPS_DENOMINATOR,
+        AmmError::InvalidFeeRate
+    );
+    mul_div(
+        fee,
+        protocol_fee_rate as u64,
+        BPS_DENOMINATOR,
+        Rounding::Down,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    #[test]
+    fn sqrt_small_values() {
+        let expected = [0, 1, 1, 1, 2, 2, 2, 2, 2, 3];
+        for (n, root) in expected.iter().enumerate() {
+            assert_eq!(sqrt(n as u128), *root);
+        }
+        assert_eq!(sqrt(u128::MAX), u64::MAX as u128);
+    }
+
+    #[test]
+    fn mul_div_rounds_in_requested_direction() {
+        assert_eq!(mul_div(10, 10, 3, Rounding::Down).unwrap(), 33);
+        assert_eq!(mul_div(10, 10, 3, Rounding::Up).unwrap(), 34);
+        assert_eq!(mul_div(10, 9, 3, Rounding::Up).unwrap(), 30);
+        assert_eq!(
+            mul_div(u64::MAX, u64::MAX, u64::MAX, Rounding::Down).unwrap(),
+            u64::MAX
+        );
+        assert!(mul_div(u64::MAX, 2, 1, Rounding::Down).is_err());
+        assert!(mul_div(1, 1, 0, Rounding::Down).is_err());
+    }
+
+    #[test]
+    fn fees_round_in_favour_of_pool() {
+        assert_eq!(fee_amount(1, 30).unwrap(), 1);
+        assert_eq!(fee_amount(10000, 30).unwrap(), 30);
+        assert_eq!(fee_amount(10001, 30).unwrap(), 31);
+        assert_eq!(protocol_fee_amount(31, 2000).unwrap(), 6);
+        assert!(fee_amount(1, 10001).is_err());
+    }
+
+    #[test]
+    fn swap_exact_in_charges_fee_before_curve() {
+        let result = swap_exact_in(Curve::ConstantProduct, 1000, 10_000, 10_000, 30, 2000).unwrap();
+        assert_eq!(result.fee, 3);
+        assert_eq!(result.protocol_fee, 0);
+        assert_eq!(result.amount_out, 997 * 10_000 / (10_000 + 997));
+    }
+
+    #[test]
+    fn swap_exact_out_charges_rounded_up_input() {
+        let result = swap_exact_out(Curve::ConstantProduct, 906, 10_000, 10_000, 30, 2000).unwrap();
+        assert_eq!(result.amount_out, 906);
+        assert!(
+            result.amount_in - result.fee
+                >= constant_product::swap_input(906, 10_000, 10_000).unwrap()
+        );
+        assert!(swap_exact_out(Curve::ConstantProduct, 10_000, 10_000, 10_000, 30, 0).is_err());
+        assert!(swap_exact_out(Curve::ConstantProduct, 1, 10_000, 10_000, 10000, 0).is_err());
+    }
+
+    #[test]
+    fn zap_swap_splits_deposit_at_pool_ratio() {
+        // Roughly half of a small deposit is swapped into a balanced pool
+        let swap = zap_swap(Curve::ConstantProduct, 10_000, 1_000_000, 1_000_000, 30, 0).unwrap();
+        assert!((4_990..5_030).contains(&swap.amount_in));
+        assert!(zap_swap(Curve::ConstantProduct, 10_000, 0, 1_000_000, 30, 0).is_err());
+    }
+
+    #[test]
+    fn zap_swap_search_is_bounded_on_every_curve() {
+        let curves = [
+            Curve::ConstantProduct,
+            Curve::StableSwap { amp: 100 },
+            Curve::Weighted {
+                weight_in: 8000,
+                weight_out: 2000,
+            },
+        ];
+        for curve in curves {
+            for (amount_in, reserve) in [(1_000, 1_000_000), (u64::MAX >> 2, u64::MAX >> 2)] {
+                let (swap, steps) =
+                    zap_swap_with_steps(curve, amount_in, reserve, reserve, 30, 2000).unwrap();
+                assert!(swap.amount_in <= amount_in);
+                assert!(
+                    steps <= ZAP_SEARCH_PRECISION + 1,
+                    "{curve:?} took {steps} steps"
+                );
+            }
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn zap_swap_is_smallest_covering_split(
+            amount_in in 1..1u64 << 40,
+            reserve_in in 1..1u64 << 40,
+            reserve_out in 1..1u64 << 40,
+            fee_rate in 0u16..1000,
+        ) {
+            let swap = zap_swap(Curve::ConstantProduct, amount_in, reserve_in, reserve_out, fee_rate, 0).unwrap();
+            let (reserve_in_after, reserve_out_after) = reserves_after_swap(&swap, reserve_in, reserve_out).unwrap();
+            let rest = (amount_in - swap.amount_in) as u128;
+            prop_assert!(swap.amount_out as u128 * reserve_in_after as u128 >= rest * reserve_out_after as u128);
+
+            if swap.amount_in > 0 {
+                let smaller = swap_exact_in(Curve::ConstantProduct, swap.amount_in - 1, reserve_in, reserve_out, fee_rate, 0).unwrap();
+                let (reserve_in_after, reserve_out_after) = reserves_after_swap(&smaller, reserve_in, reserve_out).unwrap();
+                let rest = (amount_in - smaller.amount_in) as u128;
+                prop_assert!((smaller.amount_out as u128) * (reserve_in_after as u128) < rest * reserve_out_after as u128);
+            }
+        }
+
+        #[test]
+        fn zap_swap_estimate_brackets_constant_product_split_in_few_steps(
+            reserve_in in 1_000_000..1u64 << 52,
+            per_mille_of_reserve in 1..=1_000_000u64,
+            fee_rate in 0u16..1000,
+            protocol_fee_rate in 0u16..=10000,
+        ) {
+            // Deposits of up to a thousand times the input reserve
+            let amount_in = reserve_in / 1_000 * per_mille_of_reserve;
+            let (_, steps) = zap_swap_with_steps(
+                Curve::ConstantProduct,
+                amount_in,
+                reserve_in,
+                reserve_in,
+                fee_rate,
+                protocol_fee_rate,
+            )
+            .unwrap();
+            // The estimate, one bracket end and a search over the margin
+            let margin = zap_estimate_margin(amount_in, reserve_in);
+            let bound = 3 + (u64::BITS - margin.leading_zeros());
+            prop_assert!(steps <= bound, "took {} steps for a bound of {}", steps, bound);
+        }
+
+        #[test]
+        fn sqrt_is_floor_root(n in any::<u128>()) {
+            let root = sqrt(n);
+            prop_assert!(root * root <= n);
+            prop_assert!((root + 1).checked_mul(root + 1).is_none_or(|sq| sq > n));
+        }
+
+        #[test]
+        fn swap_exact_out_round_trips_through_exact_in(
+            amount_out in 1..1u64 << 40,
+            reserve_in in 1..1u64 << 40,
+            reserve_out in 1..1u64 << 40,
+            fee_rate in 0u16..10000,
+        ) {
+            prop_assume!(amount_out < reserve_out);
+            let exact_out = swap_exact_out(Curve::ConstantProduct, amount_out, reserve_in, reserve_out, fee_rate, 0);
+            prop_assume!(exact_out.is_ok());
+            let exact_out = exact_out.unwrap();
+            let exact_in = swap_exact_in(Curve::ConstantProduct, exact_out.amount_in, reserve_in, reserve_out, fee_rate, 0).unwrap();
+            prop_assert!(exact_in.amount_out >= amount_out);
+            prop_assert_eq!(exact_in.fee, exact_out.fee);
+        }
+
+        #[test]
+        fn mul_div_up_is_down_plus_remainder(a in any::<u64>(), b in any::<u64>(), d in 1..u64::MAX) {
+            let exact = (a as u128) * (b as u128);
+            let d_wide = d as u128;
+            match (mul_div(a, b, d, Rounding::Down), mul_div(a, b, d, Rounding::Up)) {
+                (Ok(down), Ok(up)) => {
+                    let bump = u64::from(exact / d_wide * d_wide < exact);
+                    prop_assert_eq!(up, down + bump);
+                }
+                (Ok(down), Err(_)) => prop_assert_eq!(down, u64::MAX),
+                (Err(_), up) => prop_assert!(up.is_err()),
+            }
+        }
+    }
+}
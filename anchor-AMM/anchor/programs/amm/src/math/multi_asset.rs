@@ -0,0 +1,253 @@
+//! Pools of three or more equally weighted tokens.
+//!
+//! The invariant is the geometric mean of the reserves, `V = (R_1 · … · R_n)^(1/n)`.
+//! A swap only moves the two reserves it touches, so with equal weights it
+//! keeps `R_in · R_out` constant and is priced by the constant product
+//! curve. Single-asset deposits and withdrawals follow Balancer's formulas
+//! and charge the swap fee on the part that is not proportional to the pool.
+
+use anchor_lang::prelude::*;
+
+use crate::errors::AmmError;
+use crate::math::{fee_amount, mul_div, weighted, Rounding, U256};
+
+const ONE_X64: u128 = 1 << 64;
+
+fn div_ceil(numerator: U256, denominator: U256) -> U256 {
+    (numerator + denominator - U256::one()) / denominator
+}
+
+/// LP tokens minted for the very first deposit into an empty pool: the
+/// geometric mean of the amounts, rounded down.
+pub fn initial_lp_tokens(amounts: &[u64]) -> Result<u64> {
+    require!(!amounts.is_empty(), AmmError::InvalidAmount);
+    require!(
+        amounts.iter().all(|amount| *amount > 0),
+        AmmError::InsufficientLiquidity
+    );
+
+    let mut log_sum = 0i128;
+    for amount in amounts {
+        log_sum += weighted::log2_x64((*amount as u128) << 64)?;
+    }
+
+    let invariant = weighted::exp2_x64(log_sum / amounts.len() as i128)?;
+    let invariant = invariant.saturating_sub((invariant >> weighted::POW_ERROR_SHIFT) + 2);
+    Ok((invariant >> 64) as u64)
+}
+
+/// LP tokens minted for a proportional deposit into a pool that already
+/// holds liquidity. Each token is rounded down and the smallest share wins.
+pub fn lp_tokens_for_deposit(amounts: &[u64], reserves: &[u64], lp_supply: u64) -> Result<u64> {
+    require!(amounts.len() == reserves.len(), AmmError::InvalidAmount);
+    require!(
+        reserves.iter().all(|reserve| *reserve > 0),
+        AmmError::InsufficientLiquidity
+    );
+
+    let mut lp_tokens = u64::MAX;
+    for (amount, reserve) in amounts.iter().zip(reserves) {
+        lp_tokens = lp_tokens.min(mul_div(*amount, lp_supply, *reserve, Rounding::Down)?);
+    }
+    Ok(lp_tokens)
+}
+
+/// Token amounts a proportional deposit must bring for `lp_tokens`, rounded up.
+pub fn deposit_amounts(lp_tokens: u64, reserves: &[u64], lp_supply: u64) -> Result<Vec<u64>> {
+    require!(lp_supply > 0, AmmError::InsufficientLiquidity);
+
+    reserves
+        .iter()
+        .map(|reserve| mul_div(lp_tokens, *reserve, lp_supply, Rounding::Up))
+        .collect()
+}
+
+/// Token amounts released when burning `lp_tokens`, rounded down.
+pub fn withdraw_amounts(lp_tokens: u64, reserves: &[u64], lp_supply: u64) -> Result<Vec<u64>> {
+    require!(lp_supply > 0, AmmError::InsufficientLiquidity);
+    require!(lp_tokens <= lp_supply, AmmError::InvalidAmount);
+
+    reserves
+        .iter()
+        .map(|reserve| mul_div(lp_tokens, *reserve, lp_supply, Rounding::Down))
+        .collect()
+}
+
+/// Swap fee charged on a single-asset deposit or withdrawal of `amount`
+/// into a pool of `token_count` tokens. Only the `1 - 1/n` of it that a
+/// proportional deposit would not have brought is charged, rounded up.
+fn single_asset_fee(token_count: usize, amount: u64, fee_rate: u16) -> Result<u64> {
+    let n = token_count as u64;
+    let taxable = mul_div(amount, n - 1, n, Rounding::Up)?;
+    fee_amount(taxable, fee_rate)
+}
+
+/// LP tokens minted for depositing only `amount_in` of one token, and the fee
+/// charged on it: `lp_supply · ((1 + amount_after_fee / reserve)^(1/n) - 1)`,
+/// rounded down.
+pub fn single_asset_deposit(
+    token_count: usize,
+    amount_in: u64,
+    reserve: u64,
+    lp_supply: u64,
+    fee_rate: u16,
+) -> Result<(u64, u64)> {
+    require!(token_count > 1, AmmError::InvalidAmount);
+    require!(
+        reserve > 0 && lp_supply > 0,
+        AmmError::InsufficientLiquidity
+    );
+
+    let fee = single_asset_fee(token_count, amount_in, fee_rate)?;
+    let amount_after_fee = amount_in - fee;
+
+    // Every rounding below makes the growth, and so the LP tokens, smaller
+    let grown = reserve as u128 + amount_after_fee as u128;
+    let base = (U256::from(grown) << 64) / U256::from(reserve);
+    require!(base < U256::from(u128::MAX >> 1), AmmError::MathOverflow);
+    let exponent = ONE_X64 / token_count as u128;
+    let power = weighted::pow_down(base.as_u128(), exponent)?;
+
+    let growth = U256::from(power.saturating_sub(ONE_X64));
+    let lp_tokens = (U256::from(lp_supply) * growth) >> 64;
+    require!(lp_tokens <= U256::from(u64::MAX), AmmError::MathOverflow);
+    Ok((lp_tokens.as_u64(), fee))
+}
+
+/// Amount of one token released for burning `lp_tokens`, and the fee kept
+/// from it: `reserve · (1 - ((lp_supply - lp_tokens) / lp_supply)^n)` less
+/// the fee, rounded down. The pool always keeps at least one unit.
+pub fn single_asset_withdraw(
+    token_count: usize,
+    lp_tokens: u64,
+    reserve: u64,
+    lp_supply: u64,
+    fee_rate: u16,
+) -> Result<(u64, u64)> {
+    require!(token_count > 1, AmmError::InvalidAmount);
+    require!(
+        reserve > 0 && lp_supply > 0,
+        AmmError::InsufficientLiquidity
+    );
+    require!(lp_tokens < lp_supply, AmmError::InvalidAmount);
+
+    // Every rounding below makes what the pool keeps larger. The ratio is
+    // at most one, so each product fits before the shift back.
+    let one = U256::from(ONE_X64);
+    let ratio = div_ceil(
+        U256::from(lp_supply - lp_tokens) << 64,
+        U256::from(lp_supply),
+    );
+    let mut power = one;
+    for _ in 0..token_count {
+        power = div_ceil(power * ratio, one);
+    }
+
+    let kept = div_ceil(U256::from(reserve) * power, one).max(U256::one());
+    let amount_before_fee = reserve - kept.min(U256::from(reserve)).as_u64();
+
+    let fee = single_asset_fee(token_count, amount_before_fee, fee_rate)?;
+    Ok((amount_before_fee - fee, fee))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    #[test]
+    fn initial_lp_tokens_is_the_geometric_mean() {
+        assert_eq!(
+            initial_lp_tokens(&[1_000_000, 1_000_000, 1_000_000]).unwrap(),
+            999_999
+        );
+        // (1e6 · 8e6 · 27e6)^(1/3) = 6e6
+        let lp = initial_lp_tokens(&[1_000_000, 8_000_000, 27_000_000]).unwrap();
+        assert!((5_999_998..6_000_000).contains(&lp));
+        assert!(initial_lp_tokens(&[1_000, 0, 1_000]).is_err());
+    }
+
+    #[test]
+    fn proportional_deposit_takes_the_smallest_share() {
+        let reserves = [1_000, 2_000, 4_000];
+        let lp = lp_tokens_for_deposit(&[100, 300, 400], &reserves, 1_000).unwrap();
+        assert_eq!(lp, 100);
+        assert_eq!(
+            deposit_amounts(lp, &reserves, 1_000).unwrap(),
+            vec![100, 200, 400]
+        );
+        assert_eq!(
+            deposit_amounts(1, &reserves, 3).unwrap(),
+            vec![334, 667, 1_334]
+        );
+        assert_eq!(
+            withdraw_amounts(1, &reserves, 3).unwrap(),
+            vec![333, 666, 1_333]
+        );
+    }
+
+    #[test]
+    fn single_asset_deposit_matches_floating_point() {
+        // 1e6 · ((1 + 3e5 / 1e6)^(1/3) - 1) without a fee
+        let (lp, fee) = single_asset_deposit(3, 300_000, 1_000_000, 1_000_000, 0).unwrap();
+        let expected = 1e6 * (1.3f64.powf(1.0 / 3.0) - 1.0);
+        assert_eq!(fee, 0);
+        assert!(lp as f64 <= expected && lp as f64 >= expected - 2.0);
+
+        // 1% on the two thirds that a proportional deposit would not bring
+        let (_, fee) = single_asset_deposit(3, 300_000, 1_000_000, 1_000_000, 100).unwrap();
+        assert_eq!(fee, 2_000);
+    }
+
+    #[test]
+    fn single_asset_withdraw_matches_floating_point() {
+        // 1e6 · (1 - 0.9^4) without a fee
+        let (amount, fee) = single_asset_withdraw(4, 100_000, 1_000_000, 1_000_000, 0).unwrap();
+        assert_eq!(fee, 0);
+        assert!((343_899..=343_900).contains(&amount));
+
+        // Burning all but one LP token still leaves a unit behind
+        let (amount, _) = single_asset_withdraw(8, 999_999, 1_000_000, 1_000_000, 0).unwrap();
+        assert_eq!(amount, 999_999);
+    }
+
+    proptest! {
+        #[test]
+        fn single_asset_round_trip_never_profits(
+            token_count in 3usize..=8,
+            reserve in 1_000u64..=1_000_000_000_000_000,
+            lp_supply in 1_000u64..=1_000_000_000_000_000,
+            fraction in 1u64..=10_000,
+            fee_rate in 0u16..=1_000,
+        ) {
+            let amount_in = reserve / 1_000 * fraction;
+            let (lp, _) =
+                single_asset_deposit(token_count, amount_in, reserve, lp_supply, fee_rate).unwrap();
+            let (amount_out, _) = single_asset_withdraw(
+                token_count,
+                lp,
+                reserve + amount_in,
+                lp_supply + lp,
+                fee_rate,
+            )
+            .unwrap();
+            prop_assert!(amount_out <= amount_in);
+        }
+
+        #[test]
+        fn proportional_deposit_covers_its_share(
+            reserves in prop::collection::vec(1u64..=1_000_000_000_000_000, 3..=8),
+            lp_supply in 1_000u64..=1_000_000_000_000_000,
+            fraction in 1u64..=1_000,
+        ) {
+            let lp_tokens = lp_supply / 1_000 * fraction;
+            let amounts = deposit_amounts(lp_tokens, &reserves, lp_supply).unwrap();
+            let minted = lp_tokens_for_deposit(&amounts, &reserves, lp_supply).unwrap();
+            prop_assert!(minted >= lp_tokens);
+            let withdrawn = withdraw_amounts(lp_tokens, &reserves, lp_supply).unwrap();
+            for (deposited, withdrawn) in amounts.iter().zip(&withdrawn) {
+                prop_assert!(withdrawn <= deposited);
+            }
+        }
+    }
+}
@@ -0,0 +1,282 @@
+//! Two-coin StableSwap curve.
+//!
+//! The invariant `D` satisfies `A·n^n·(x + y) + D = A·D·n^n + D^(n+1) / (n^n·x·y)`
+//! with `n = 2`, and is solved with Newton's method in 256-bit arithmetic.
+
+use anchor_lang::prelude::*;
+
+use crate::errors::AmmError;
+use crate::math::{mul_div_u256, Rounding, U256};
+
+/// Smallest amplification coefficient a pool may use.
+pub const MIN_AMP: u64 = 1;
+/// Largest amplification coefficient a pool may use.
+pub const MAX_AMP: u64 = 1_000_000;
+/// Largest factor by which a single ramp may raise or lower the amplification.
+pub const MAX_AMP_CHANGE: u64 = 10;
+/// Shortest duration of an amplification ramp, in seconds.
+pub const MIN_RAMP_DURATION: i64 = 86400;
+
+const N_COINS: u64 = 2;
+const MAX_ITERATIONS: usize = 256;
+/// Extra units `swap_input` may add to cover Newton's method rounding.
+const MAX_INPUT_ADJUSTMENTS: usize = 8;
+
+fn amp_times_coins(amp: u64) -> U256 {
+    // A·n^n
+    U256::from(amp) * U256::from(N_COINS * N_COINS)
+}
+
+/// Computes the invariant `D` for the given balances.
+pub fn compute_d(amp: u64, amount_a: u64, amount_b: u64) -> Result<U256> {
+    let sum = U256::from(amount_a) + U256::from(amount_b);
+    if sum.is_zero() {
+        return Ok(U256::zero());
+    }
+    require!(
+        amount_a > 0 && amount_b > 0,
+        AmmError::InsufficientLiquidity
+    );
+
+    let ann = amp_times_coins(amp);
+    let n = U256::from(N_COINS);
+    let mut d = sum;
+
+    for _ in 0..MAX_ITERATIONS {
+        // D^(n+1) / (n^n·x·y), built up one balance at a time
+        let mut d_p = d;
+        d_p = d_p * d / (U256::from(amount_a) * n);
+        d_p = d_p * d / (U256::from(amount_b) * n);
+
+        let d_prev = d;
+        let numerator = (ann * sum + d_p * n) * d;
+        let denominator = (ann - U256::one()) * d + (n + U256::one()) * d_p;
+        d = numerator / denominator;
+
+        if abs_diff(d, d_prev) <= U256::one() {
+            return Ok(d);
+        }
+    }
+
+    err!(AmmError::CurveDidNotConverge)
+}
+
+/// Computes the balance of the other coin that keeps the invariant at `d`
+/// once one side holds `new_amount`.
+pub fn compute_y(amp: u64, new_amount: u64, d: U256) -> Result<U256> {
+    require!(new_amount > 0, AmmError::InsufficientLiquidity);
+
+    let ann = amp_times_coins(amp);
+    let n = U256::from(N_COINS);
+    let x = U256::from(new_amount);
+
+    let c = d * d / (x * n) * d / (ann * n);
+    let b = x + d / ann;
+    let mut y = d;
+
+    for _ in 0..MAX_ITERATIONS {
+        let y_prev = y;
+        let denominator = (y * n + b).checked_sub(d).ok_or(AmmError::MathOverflow)?;
+        y = (y * y + c) / denominator;
+
+        if abs_diff(y, y_prev) <= U256::one() {
+            return Ok(y);
+        }
+    }
+
+    err!(AmmError::CurveDidNotConverge)
+}
+
+/// Output of a swap for an input that has already had its fee removed.
+/// One extra unit is withheld to cover Newton's method rounding.
+pub fn swap_output(amp: u64, amount_in: u64, reserve_in: u64, reserve_out: u64) -> Result<u64> {
+    require!(
+        reserve_in > 0 && reserve_out > 0,
+        AmmError::InsufficientLiquidity
+    );
+
+    let d = compute_d(amp, reserve_in, reserve_out)?;
+    let new_reserve_in = reserve_in
+        .checked_add(amount_in)
+        .ok_or(AmmError::MathOverflow)?;
+    let new_reserve_out = compute_y(amp, new_reserve_in, d)?;
+
+    let amount_out = U256::from(reserve_out)
+        .saturating_sub(new_reserve_out)
+        .saturating_sub(U256::one());
+    // Never more than `reserve_out - 1`
+    Ok(amount_out.as_u64())
+}
+
+/// Input, after fees, needed for a swap to pay out exactly `amount_out`.
+/// Solved on the curve and then checked against `swap_output`, so the
+/// result always buys at least `amount_out`.
+pub fn swap_input(amp: u64, amount_out: u64, reserve_in: u64, reserve_out: u64) -> Result<u64> {
+    require!(reserve_in > 0, AmmError::InsufficientLiquidity);
+    // `swap_output` withholds one unit, so the pool must keep at least two
+    let new_reserve_out = reserve_out
+        .checked_sub(amount_out)
+        .and_then(|reserve| reserve.checked_sub(1))
+        .filter(|reserve| *reserve > 0)
+        .ok_or(AmmError::InsufficientLiquidity)?;
+
+    let d = compute_d(amp, reserve_in, reserve_out)?;
+    let new_reserve_in = compute_y(amp, new_reserve_out, d)?;
+    let estimate = new_reserve_in.saturating_sub(U256::from(reserve_in)) + U256::one();
+    require!(estimate <= U256::from(u64::MAX), AmmError::MathOverflow);
+
+    let mut amount_in = estimate.as_u64();
+    for _ in 0..MAX_INPUT_ADJUSTMENTS {
+        if swap_output(amp, amount_in, reserve_in, reserve_out)? >= amount_out {
+            return Ok(amount_in);
+        }
+        amount_in = amount_in.checked_add(1).ok_or(AmmError::MathOverflow)?;
+    }
+
+    err!(AmmError::CurveDidNotConverge)
+}
+
+/// LP tokens minted for the very first deposit into an empty pool.
+pub fn initial_lp_tokens(amp: u64, amount_a: u64, amount_b: u64) -> Result<u64> {
+    let d = compute_d(amp, amount_a, amount_b)?;
+    require!(d <= U256::from(u64::MAX), AmmError::MathOverflow);
+    Ok(d.as_u64())
+}
+
+/// Price of one unit of the base token in quote tokens as Q64.64: the slope
+/// of the invariant at these balances, `(4·A·n^n·x·y + D³/x) / (4·A·n^n·x·y + D³/y)`
+/// for base `x` and quote `y`. Zero while the pool is empty.
+pub fn spot_price_x64(amp: u64, reserve_base: u64, reserve_quote: u64) -> Result<u128> {
+    if reserve_base == 0 || reserve_quote == 0 {
+        return Ok(0);
+    }
+    let d = compute_d(amp, reserve_base, reserve_quote)?;
+    let (x, y) = (U256::from(reserve_base), U256::from(reserve_quote));
+    let d_cubed = d * d * d;
+    let product = U256::from(4) * amp_times_coins(amp) * x * y;
+    let price = mul_div_u256(
+        product + d_cubed / x,
+        U256::one() << 64,
+        product + d_cubed / y,
+        Rounding::Down,
+    )?;
+    Ok(if price > U256::from(u128::MAX) {
+        u128::MAX
+    } else {
+        price.as_u128()
+    })
+}
+
+/// Amplification coefficient at `now` for a linear ramp from `amp_initial`
+/// at `start_ts` to `amp_target` at `stop_ts`.
+pub fn ramp_amp(amp_initial: u64, amp_target: u64, start_ts: i64, stop_ts: i64, now: i64) -> u64 {
+    if now >= stop_ts || stop_ts <= start_ts {
+        return amp_target;
+    }
+    if now <= start_ts {
+        return amp_initial;
+    }
+
+    let elapsed = (now - start_ts) as u128;
+    let duration = (stop_ts - start_ts) as u128;
+    if amp_target > amp_initial {
+        let delta = (amp_target - amp_initial) as u128 * elapsed / duration;
+        amp_initial + delta as u64
+    } else {
+        let delta = (amp_initial - amp_target) as u128 * elapsed / duration;
+        amp_initial - delta as u64
+    }
+}
+
+fn abs_diff(a: U256, b: U256) -> U256 {
+    if a > b {
+        a - b
+    } else {
+        b - a
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::constant_product;
+    use proptest::prelude::*;
+
+    const NINE_DECIMALS: u64 = 1_000_000_000;
+
+    #[test]
+    fn balanced_pool_invariant_is_sum() {
+        let amount = 1_000_000 * NINE_DECIMALS;
+        assert_eq!(
+            compute_d(100, amount, amount).unwrap(),
+            U256::from(2 * amount)
+        );
+        assert_eq!(compute_d(100, 0, 0).unwrap(), U256::zero());
+        assert!(compute_d(100, 0, amount).is_err());
+    }
+
+    #[test]
+    fn high_amp_beats_constant_product_near_peg() {
+        let reserve = 10_000_000 * NINE_DECIMALS;
+        let amount_in = 100_000 * NINE_DECIMALS;
+
+        let stable = swap_output(1000, amount_in, reserve, reserve).unwrap();
+        let product = constant_product::swap_output(amount_in, reserve, reserve).unwrap();
+
+        assert!(stable > product);
+        assert!(stable < amount_in);
+        assert!(amount_in - stable < amount_in / 1000);
+    }
+
+    #[test]
+    fn spot_price_stays_near_peg_until_the_pool_is_lopsided() {
+        let reserve = 1_000_000 * NINE_DECIMALS;
+        assert_eq!(spot_price_x64(100, reserve, reserve).unwrap(), 1 << 64);
+
+        // Twice as much base as quote: far below the peg at a low
+        // amplification, barely under it at a high one
+        let low = spot_price_x64(1, 2 * reserve, reserve).unwrap();
+        let high = spot_price_x64(1_000, 2 * reserve, reserve).unwrap();
+        assert!(low < (9 << 64) / 10);
+        assert!(high > (99 << 64) / 100 && high < 1 << 64);
+        assert_eq!(spot_price_x64(100, 0, reserve).unwrap(), 0);
+    }
+
+    #[test]
+    fn ramp_interpolates_linearly() {
+        assert_eq!(ramp_amp(100, 200, 0, 100, -5), 100);
+        assert_eq!(ramp_amp(100, 200, 0, 100, 50), 150);
+        assert_eq!(ramp_amp(200, 100, 0, 100, 25), 175);
+        assert_eq!(ramp_amp(100, 200, 0, 100, 100), 200);
+        assert_eq!(ramp_amp(100, 200, 0, 0, 0), 200);
+    }
+
+    proptest! {
+        #[test]
+        fn swap_never_decreases_invariant(
+            amp in MIN_AMP..=MAX_AMP,
+            amount_in in 1..1u64 << 50,
+            reserve_in in 1_000..1u64 << 60,
+            reserve_out in 1_000..1u64 << 60,
+        ) {
+            let amount_out = swap_output(amp, amount_in, reserve_in, reserve_out).unwrap();
+            prop_assert!(amount_out < reserve_out);
+
+            let d_before = compute_d(amp, reserve_in, reserve_out).unwrap();
+            let d_after = compute_d(amp, reserve_in + amount_in, reserve_out - amount_out).unwrap();
+            prop_assert!(d_after >= d_before);
+        }
+
+        #[test]
+        fn swap_input_buys_at_least_amount_out(
+            amp in MIN_AMP..=MAX_AMP,
+            amount_out in 1..1u64 << 50,
+            reserve_in in 1_000..1u64 << 60,
+            reserve_out in 1_000..1u64 << 60,
+        ) {
+            prop_assume!(amount_out < reserve_out / 2);
+            let amount_in = swap_input(amp, amount_out, reserve_in, reserve_out).unwrap();
+            prop_assert!(swap_output(amp, amount_in, reserve_in, reserve_out).unwrap() >= amount_out);
+        }
+    }
+}
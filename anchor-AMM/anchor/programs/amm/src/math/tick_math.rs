@@ -0,0 +1,150 @@
+//! Conversions between ticks and Q64.64 square-root prices for concentrated
+//! liquidity pools. Tick `i` corresponds to the price `1.0001^i` of token A
+//! in units of token B.
+
+use anchor_lang::prelude::*;
+
+use crate::errors::AmmError;
+
+pub const MIN_TICK: i32 = -443636;
+pub const MAX_TICK: i32 = 443636;
+
+/// `sqrt_price_at_tick(MIN_TICK)`
+pub const MIN_SQRT_PRICE_X64: u128 = 4295048016;
+/// `sqrt_price_at_tick(MAX_TICK)`
+pub const MAX_SQRT_PRICE_X64: u128 = 79226673515401279988681420430;
+
+/// `sqrt(1.0001)^(2^i)` in Q64.64, for every bit of `|MAX_TICK|`.
+const SQRT_POWERS_X64: [u128; 19] = [
+    18447666387855959851,
+    18448588748116922571,
+    18450433606991734263,
+    18454123878217468680,
+    18461506635090006702,
+    18476281010653910145,
+    18505865242158250042,
+    18565175891880433523,
+    18684368066214940583,
+    18925053041275764672,
+    19415764168677886927,
+    20435687552633177495,
+    22639080592224303007,
+    27784196929998399742,
+    41848122137994986129,
+    94936283578220370716,
+    488590176327622479861,
+    12941056668319229769860,
+    9078618265828848800676189,
+];
+
+/// `1 / log2(sqrt(1.0001))` in Q32.32.
+const TICKS_PER_LOG2_X32: i128 = 59543866431248;
+
+/// Number of fractional bits resolved when taking `log2` of a price.
+const LOG2_PRECISION_BITS: u32 = 24;
+
+/// `a * b >> 64` for values whose result fits in a `u128`.
+fn mul_shift_64(a: u128, b: u128) -> u128 {
+    let (a_hi, a_lo) = (a >> 64, a & u64::MAX as u128);
+    let (b_hi, b_lo) = (b >> 64, b & u64::MAX as u128);
+
+    ((a_hi * b_hi) << 64) + a_hi * b_lo + a_lo * b_hi + ((a_lo * b_lo) >> 64)
+}
+
+/// Square-root price of `tick` in Q64.64.
+pub fn sqrt_price_at_tick(tick: i32) -> Result<u128> {
+    require!(
+        (MIN_TICK..=MAX_TICK).contains(&tick),
+        AmmError::InvalidTickIndex
+    );
+
+    let abs_tick = tick.unsigned_abs();
+    let mut ratio: u128 = 1 << 64;
+    for (bit, power) in SQRT_POWERS_X64.iter().enumerate() {
+        if abs_tick & (1 << bit) != 0 {
+            ratio = mul_shift_64(ratio, *power);
+        }
+    }
+
+    if tick < 0 {
+        // 1 / ratio, with ratio >= 1 so the result stays below 2^64
+        ratio = u128::MAX / ratio;
+    }
+
+    Ok(ratio)
+}
+
+/// Greatest tick whose square-root price is at or below `sqrt_price_x64`.
+pub fn tick_at_sqrt_price(sqrt_price_x64: u128) -> Result<i32> {
+    require!(
+        (MIN_SQRT_PRICE_X64..=MAX_SQRT_PRICE_X64).contains(&sqrt_price_x64),
+        AmmError::InvalidSqrtPrice
+    );
+
+    // Integer part of log2, then the fractional bits by repeated squaring
+    let msb = 127 - sqrt_price_x64.leading_zeros() as i128;
+    let mut log2_x64: i128 = (msb - 64) << 64;
+    let mut r = if msb >= 63 {
+        sqrt_price_x64 >> (msb - 63)
+    } else {
+        sqrt_price_x64 << (63 - msb)
+    };
+    for bit in 1..=LOG2_PRECISION_BITS {
+        r = (r * r) >> 63;
+        if r >= 1 << 64 {
+            r >>= 1;
+            log2_x64 |= 1 << (64 - bit);
+        }
+    }
+
+    // The estimate is within one tick of the answer, so settle it exactly
+    let estimate = ((log2_x64 >> 32) * TICKS_PER_LOG2_X32) >> 64;
+    let mut tick = (estimate as i32).clamp(MIN_TICK, MAX_TICK);
+    while tick > MIN_TICK && sqrt_price_at_tick(tick)? > sqrt_price_x64 {
+        tick -= 1;
+    }
+    while tick < MAX_TICK && sqrt_price_at_tick(tick + 1)? <= sqrt_price_x64 {
+        tick += 1;
+    }
+
+    Ok(tick)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    #[test]
+    fn bounds_match_constants() {
+        assert_eq!(sqrt_price_at_tick(0).unwrap(), 1 << 64);
+        assert_eq!(sqrt_price_at_tick(MIN_TICK).unwrap(), MIN_SQRT_PRICE_X64);
+        assert_eq!(sqrt_price_at_tick(MAX_TICK).unwrap(), MAX_SQRT_PRICE_X64);
+        assert!(sqrt_price_at_tick(MIN_TICK - 1).is_err());
+        assert!(sqrt_price_at_tick(MAX_TICK + 1).is_err());
+    }
+
+    #[test]
+    fn tick_round_trips_at_bounds() {
+        assert_eq!(tick_at_sqrt_price(MIN_SQRT_PRICE_X64).unwrap(), MIN_TICK);
+        assert_eq!(tick_at_sqrt_price(MAX_SQRT_PRICE_X64).unwrap(), MAX_TICK);
+        assert_eq!(tick_at_sqrt_price(1 << 64).unwrap(), 0);
+        assert_eq!(tick_at_sqrt_price((1 << 64) - 1).unwrap(), -1);
+        assert!(tick_at_sqrt_price(MIN_SQRT_PRICE_X64 - 1).is_err());
+    }
+
+    proptest! {
+        #[test]
+        fn sqrt_price_is_strictly_increasing(tick in MIN_TICK..MAX_TICK) {
+            prop_assert!(sqrt_price_at_tick(tick).unwrap() < sqrt_price_at_tick(tick + 1).unwrap());
+        }
+
+        #[test]
+        fn tick_at_sqrt_price_inverts(tick in MIN_TICK..MAX_TICK, offset in 0u128..1_000_000) {
+            let lower = sqrt_price_at_tick(tick).unwrap();
+            let upper = sqrt_price_at_tick(tick + 1).unwrap();
+            let price = lower + offset % (upper - lower);
+            prop_assert_eq!(tick_at_sqrt_price(price).unwrap(), tick);
+        }
+    }
+}
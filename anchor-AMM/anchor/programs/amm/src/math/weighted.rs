@@ -0,0 +1,322 @@
+//! Two-token weighted-product curve, as used by Balancer.
+//!
+//! The invariant is `V = x^w_x · y^w_y` with `w_x + w_y = 1`. Powers are
+//! evaluated in Q64.64 fixed point through `log2` and `exp2`, and every
+//! power is then widened in the pool's favour by more than their combined
+//! approximation error.
+
+use anchor_lang::prelude::*;
+
+use crate::errors::AmmError;
+use crate::math::{BPS_DENOMINATOR, U256};
+
+/// Smallest weight, in basis points, either token of a weighted pool may have.
+pub const MIN_WEIGHT: u16 = 100;
+
+const ONE_X64: u128 = 1 << 64;
+/// ln(2) as Q64.64.
+const LN_2_X64: u128 = 12786308645202655659;
+/// Powers are widened by 2^-POW_ERROR_SHIFT of themselves plus two units in
+/// the last place, well above the error of `log2_x64` and `exp2_x64`.
+pub(crate) const POW_ERROR_SHIFT: u32 = 50;
+/// Times `swap_input` may step its estimate up to cover `swap_output` rounding.
+const MAX_INPUT_ADJUSTMENTS: usize = 16;
+
+/// Base-2 logarithm of a positive Q64.64 value as signed Q64.64, rounded down.
+pub fn log2_x64(x: u128) -> Result<i128> {
+    require!(x > 0, AmmError::MathOverflow);
+
+    let msb = 127 - x.leading_zeros() as i32;
+    let mut result = ((msb - 64) as i128) << 64;
+
+    // Normalise to [1, 2) as Q1.63 so that squaring fits in a u128, then
+    // read the fraction off one bit per squaring
+    let mut y = if msb >= 63 {
+        x >> (msb - 63)
+    } else {
+        x << (63 - msb)
+    };
+    for bit in (0..64).rev() {
+        y = (y * y) >> 63;
+        if y >= ONE_X64 {
+            y >>= 1;
+            result += 1 << bit;
+        }
+    }
+
+    Ok(result)
+}
+
+/// Two raised to a signed Q64.64 exponent, as Q64.64 rounded down.
+pub fn exp2_x64(exponent: i128) -> Result<u128> {
+    let integer = exponent >> 64;
+    let fraction = (exponent - (integer << 64)) as u128;
+    require!(integer < 64, AmmError::MathOverflow);
+    if integer < -65 {
+        return Ok(0);
+    }
+
+    // 2^f = e^(f·ln 2), summed as a Taylor series until the terms vanish
+    let x = (fraction * LN_2_X64) >> 64;
+    let mut term = ONE_X64;
+    let mut sum = ONE_X64;
+    let mut k = 1;
+    while term > 0 {
+        term = ((term * x) >> 64) / k;
+        sum += term;
+        k += 1;
+    }
+
+    if integer >= 0 {
+        Ok(sum << integer as u32)
+    } else {
+        Ok(sum >> (-integer) as u32)
+    }
+}
+
+/// `base^exponent` for a Q64.64 base and an unsigned Q64.64 exponent,
+/// before any widening.
+fn pow_x64(base: u128, exponent: u128) -> Result<u128> {
+    let log = log2_x64(base)?;
+    let magnitude = (U256::from(log.unsigned_abs()) * U256::from(exponent)) >> 64;
+
+    if log < 0 {
+        if magnitude >= U256::from(128u128 << 64) {
+            return Ok(0);
+        }
+        exp2_x64(-(magnitude.as_u128() as i128))
+    } else {
+        require!(magnitude < U256::from(64u128 << 64), AmmError::MathOverflow);
+        exp2_x64(magnitude.as_u128() as i128)
+    }
+}
+
+/// `base^exponent` widened past its approximation error, so never below
+/// the exact power.
+pub fn pow_up(base: u128, exponent: u128) -> Result<u128> {
+    let power = pow_x64(base, exponent)?;
+    power
+        .checked_add((power >> POW_ERROR_SHIFT) + 2)
+        .ok_or_else(|| error!(AmmError::MathOverflow))
+}
+
+/// `base^exponent` narrowed past its approximation error, so never above
+/// the exact power.
+pub fn pow_down(base: u128, exponent: u128) -> Result<u128> {
+    let power = pow_x64(base, exponent)?;
+    Ok(power.saturating_sub((power >> POW_ERROR_SHIFT) + 2))
+}
+
+/// Output of a swap for an input that has already had its fee removed,
+/// rounded down: `reserve_out · (1 - (reserve_in / (reserve_in + amount_in))^(w_in / w_out))`.
+pub fn swap_output(
+    weight_in: u16,
+    weight_out: u16,
+    amount_in: u64,
+    reserve_in: u64,
+    reserve_out: u64,
+) -> Result<u64> {
+    require!(
+        reserve_in > 0 && reserve_out > 0,
+        AmmError::InsufficientLiquidity
+    );
+
+    // Every rounding below makes the power, and so what the pool keeps, larger
+    let base = ((reserve_in as u128) << 64).div_ceil(reserve_in as u128 + amount_in as u128);
+    let exponent = ((weight_in as u128) << 64) / weight_out as u128;
+    let power = pow_up(base, exponent)?.min(ONE_X64);
+
+    // The pool keeps at least one unit however large the input
+    let kept = (reserve_out as u128 * power).div_ceil(ONE_X64).max(1);
+    Ok(reserve_out - kept as u64)
+}
+
+/// Input, after fees, needed for a swap to pay out exactly `amount_out`,
+/// rounded up: `reserve_in · ((reserve_out / (reserve_out - amount_out))^(w_out / w_in) - 1)`.
+pub fn swap_input(
+    weight_in: u16,
+    weight_out: u16,
+    amount_out: u64,
+    reserve_in: u64,
+    reserve_out: u64,
+) -> Result<u64> {
+    require!(
+        reserve_in > 0 && amount_out < reserve_out,
+        AmmError::InsufficientLiquidity
+    );
+
+    let base = ((reserve_out as u128) << 64).div_ceil((reserve_out - amount_out) as u128);
+    let exponent = ((weight_out as u128) << 64).div_ceil(weight_in as u128);
+    let power = pow_up(base, exponent)?;
+
+    let growth = U256::from(power.saturating_sub(ONE_X64));
+    let estimate = (U256::from(reserve_in) * growth + U256::from(ONE_X64 - 1)) >> 64;
+    require!(estimate <= U256::from(u64::MAX), AmmError::MathOverflow);
+
+    // `swap_output` widens its own power, so the estimate may fall a hair
+    // short of buying `amount_out`; step up until it does not
+    let mut amount_in = estimate.as_u64();
+    let mut step = (amount_in >> POW_ERROR_SHIFT).max(1);
+    for _ in 0..MAX_INPUT_ADJUSTMENTS {
+        if swap_output(weight_in, weight_out, amount_in, reserve_in, reserve_out)? >= amount_out {
+            return Ok(amount_in);
+        }
+        amount_in = amount_in.checked_add(step).ok_or(AmmError::MathOverflow)?;
+        step = step.saturating_mul(2);
+    }
+
+    err!(AmmError::CurveDidNotConverge)
+}
+
+/// LP tokens minted for the very first deposit into an empty pool: the
+/// invariant `amount_a^w_a · amount_b^w_b`, rounded down.
+pub fn initial_lp_tokens(
+    weight_a: u16,
+    weight_b: u16,
+    amount_a: u64,
+    amount_b: u64,
+) -> Result<u64> {
+    require!(
+        amount_a > 0 && amount_b > 0,
+        AmmError::InsufficientLiquidity
+    );
+
+    let log_a = log2_x64((amount_a as u128) << 64)?;
+    let log_b = log2_x64((amount_b as u128) << 64)?;
+    let log_invariant =
+        (log_a * weight_a as i128 + log_b * weight_b as i128) / BPS_DENOMINATOR as i128;
+
+    let invariant = exp2_x64(log_invariant)?;
+    let invariant = invariant.saturating_sub((invariant >> POW_ERROR_SHIFT) + 2);
+    Ok((invariant >> 64) as u64)
+}
+
+/// Price of one unit of the base token in quote tokens as Q64.64, which for
+/// a weighted pool is `(reserve_quote / w_quote) / (reserve_base / w_base)`.
+/// Zero while the pool is empty.
+pub fn spot_price_x64(
+    weight_base: u16,
+    reserve_base: u64,
+    weight_quote: u16,
+    reserve_quote: u64,
+) -> u128 {
+    if reserve_base == 0 {
+        return 0;
+    }
+    let numerator = U256::from(reserve_quote as u128 * weight_base as u128) << 64;
+    let price = numerator / U256::from(reserve_base as u128 * weight_quote as u128);
+    if price > U256::from(u128::MAX) {
+        u128::MAX
+    } else {
+        price.as_u128()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::constant_product;
+    use proptest::prelude::*;
+
+    fn to_f64(x: u128) -> f64 {
+        x as f64 / ONE_X64 as f64
+    }
+
+    #[test]
+    fn log2_and_exp2_match_floating_point() {
+        assert_eq!(log2_x64(ONE_X64).unwrap(), 0);
+        assert_eq!(log2_x64(8 << 64).unwrap(), 3 << 64);
+        assert_eq!(log2_x64(ONE_X64 / 4).unwrap(), -2 << 64);
+        assert_eq!(exp2_x64(0).unwrap(), ONE_X64);
+        assert_eq!(exp2_x64(5 << 64).unwrap(), 32 << 64);
+        assert_eq!(exp2_x64(-3 << 64).unwrap(), ONE_X64 / 8);
+
+        let log = log2_x64(3 << 64).unwrap() as f64 / ONE_X64 as f64;
+        assert!((log - 3f64.log2()).abs() < 1e-15);
+        let power = to_f64(exp2_x64(ONE_X64 as i128 / 3).unwrap());
+        assert!((power - 2f64.powf(1.0 / 3.0)).abs() < 1e-15);
+        assert!(exp2_x64(64 << 64).is_err());
+    }
+
+    #[test]
+    fn even_weights_match_constant_product() {
+        for amount_in in [1u64, 1_000, 1_000_000, 1_000_000_000] {
+            let weighted =
+                swap_output(5000, 5000, amount_in, 1_000_000_000, 3_000_000_000).unwrap();
+            let plain =
+                constant_product::swap_output(amount_in, 1_000_000_000, 3_000_000_000).unwrap();
+            assert!(weighted <= plain && plain - weighted <= 2);
+        }
+        assert_eq!(
+            initial_lp_tokens(5000, 5000, 4_000_000, 4_000_000).unwrap(),
+            3_999_999
+        );
+    }
+
+    #[test]
+    fn eighty_twenty_swap_matches_floating_point() {
+        // 80% token in, 20% token out: 1e9 · (1 - (1e9 / 1.1e9)^4)
+        let amount_out =
+            swap_output(8000, 2000, 100_000_000, 1_000_000_000, 1_000_000_000).unwrap();
+        let expected = 1e9 * (1.0 - (1.0f64 / 1.1).powi(4));
+        assert!(amount_out as f64 <= expected && amount_out as f64 >= expected - 2.0);
+
+        let amount_in = swap_input(8000, 2000, amount_out, 1_000_000_000, 1_000_000_000).unwrap();
+        assert!((100_000_000 - 2..=100_000_000 + 2).contains(&amount_in));
+    }
+
+    #[test]
+    fn pool_always_keeps_a_unit() {
+        assert_eq!(swap_output(9900, 100, u64::MAX, 1, 1_000).unwrap(), 999);
+        assert!(swap_input(5000, 5000, 1_000, 1_000, 1_000).is_err());
+    }
+
+    #[test]
+    fn spot_price_accounts_for_weights() {
+        // 80/20 with equal balances: one unit of the 80% token is worth four
+        assert_eq!(spot_price_x64(8000, 1_000, 2000, 1_000), 4 << 64);
+        assert_eq!(spot_price_x64(5000, 1_000, 5000, 2_000), 2 << 64);
+        assert_eq!(spot_price_x64(5000, 0, 5000, 2_000), 0);
+    }
+
+    proptest! {
+        #[test]
+        fn swap_never_lowers_the_invariant(
+            weight_in in MIN_WEIGHT..=BPS_DENOMINATOR as u16 - MIN_WEIGHT,
+            reserve_in in 1_000u64..=1_000_000_000_000_000,
+            reserve_out in 1_000u64..=1_000_000_000_000_000,
+            fraction in 1u64..=1_000,
+        ) {
+            let weight_out = BPS_DENOMINATOR as u16 - weight_in;
+            let amount_in = reserve_in / 1_000 * fraction;
+            let amount_out =
+                swap_output(weight_in, weight_out, amount_in, reserve_in, reserve_out).unwrap();
+
+            // ln V, scaled by the weight denominator, up to floating-point error
+            let log_invariant = |x: u64, y: u64| {
+                weight_in as f64 * (x as f64).ln() + weight_out as f64 * (y as f64).ln()
+            };
+            let before = log_invariant(reserve_in, reserve_out);
+            let after = log_invariant(reserve_in + amount_in, reserve_out - amount_out);
+            prop_assert!(after >= before - 1e-9);
+        }
+
+        #[test]
+        fn exact_out_input_buys_the_output(
+            weight_in in MIN_WEIGHT..=BPS_DENOMINATOR as u16 - MIN_WEIGHT,
+            reserve_in in 1_000u64..=1_000_000_000_000_000,
+            reserve_out in 1_000u64..=1_000_000_000_000_000,
+            fraction in 1u64..=900,
+        ) {
+            let weight_out = BPS_DENOMINATOR as u16 - weight_in;
+            let amount_out = reserve_out / 1_000 * fraction;
+            let amount_in =
+                swap_input(weight_in, weight_out, amount_out, reserve_in, reserve_out);
+            if let Ok(amount_in) = amount_in {
+                let bought =
+                    swap_output(weight_in, weight_out, amount_in, reserve_in, reserve_out).unwrap();
+                prop_assert!(bought >= amount_out);
+            }
+        }
+    }
+}
@@ -1,15 +1,1135 @@
+use std::ops::RangeInclusive;
+
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{hash::hashv, program::MAX_RETURN_DATA};
+
+use crate::errors::AmmError;
+use crate::math::{
+    self, concentrated_liquidity, dynamic_fee, farm, stable_swap, weighted, Curve, SwapResult,
+};
 
 #[account]
 #[derive(InitSpace)]
 pub struct Pool {
     pub authority: Pubkey,
+    /// Authority proposed by `propose_authority`, which takes over once it
+    /// calls `accept_authority`.
+    pub pending_authority: Option<Pubkey>,
     pub token_a_mint: Pubkey,
     pub token_b_mint: Pubkey,
     pub token_a_vault: Pubkey,
     pub token_b_vault: Pubkey,
     pub lp_mint: Pubkey,
     pub fee_rate: u16,
+    /// Fee rate the pool was created with, which seeds its address and so
+    /// never changes.
+    pub fee_tier: u16,
+    pub curve_type: CurveType,
+    /// StableSwap amplification at `amp_ramp_start_ts`.
+    pub amp_initial: u64,
+    /// StableSwap amplification reached at `amp_ramp_stop_ts`.
+    pub amp_target: u64,
+    pub amp_ramp_start_ts: i64,
+    pub amp_ramp_stop_ts: i64,
+    /// Weight of token A, in basis points of the pool's value, for
+    /// `CurveType::Weighted`. Even weights for every other curve.
+    pub weight_a: u16,
+    /// Weight of token B; always `10000 - weight_a`.
+    pub weight_b: u16,
+    /// Share of every swap fee, in basis points of the fee, set aside for the protocol.
+    pub protocol_fee_rate: u16,
+    /// Uncollected protocol fees held in `token_a_vault`.
+    pub protocol_fees_a: u64,
+    /// Uncollected protocol fees held in `token_b_vault`.
+    pub protocol_fees_b: u64,
+    /// Set between `flash_borrow` and `flash_repay`; every other instruction
+    /// that moves vault tokens is rejected while it is.
+    pub flash_loan_active: bool,
+    /// Amount of token A lent by the active flash loan.
+    pub flash_loan_amount_a: u64,
+    /// Amount of token B lent by the active flash loan.
+    pub flash_loan_amount_b: u64,
+    /// Blocks swaps, deposits and flash loans. Withdrawals stay open.
+    pub paused: bool,
+    /// Token-2022 transfer hook programs the pool's mints may invoke. Empty
+    /// accepts any hook.
+    #[max_len(MAX_TRANSFER_HOOK_PROGRAMS)]
+    pub transfer_hook_programs: Vec<Pubkey>,
+    /// Bounds of the volatility-driven swap fee. While set, swaps charge it
+    /// instead of `fee_rate`.
+    pub dynamic_fee: Option<DynamicFeeConfig>,
+    /// Recent price movement in basis points as of `volatility_updated_ts`.
+    /// Only tracked while `dynamic_fee` is set.
+    pub volatility: u64,
+    pub volatility_updated_ts: i64,
+    pub bump: u8,
+    pub lp_mint_bump: u8,
+}
+
+/// Longest transfer hook allowlist a `Pool` can hold.
+pub const MAX_TRANSFER_HOOK_PROGRAMS: usize = 4;
+
+/// Whether `hook_program` passes a transfer hook allowlist, where an empty
+/// list accepts any hook and a mint without one always passes.
+fn allowlist_accepts(allowlist: &[Pubkey], hook_program: Option<Pubkey>) -> bool {
+    hook_program.is_none_or(|program| allowlist.is_empty() || allowlist.contains(&program))
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, InitSpace)]
+pub enum CurveType {
+    ConstantProduct,
+    StableSwap,
+    Weighted,
+}
+
+/// Parameters of a pool's volatility-driven swap fee.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, InitSpace)]
+pub struct DynamicFeeConfig {
+    pub min_fee_rate: u16,
+    pub max_fee_rate: u16,
+    /// Fee added per unit of volatility, in basis points: 10000 adds one
+    /// basis point of fee per basis point of volatility.
+    pub volatility_multiplier: u16,
+    /// Seconds over which recorded volatility decays to zero.
+    pub decay_period: u32,
+}
+
+impl Pool {
+    /// Returns the reserves backing LP shares, i.e. the vault balances minus
+    /// protocol fees that have not been collected yet.
+    pub fn reserves(&self, vault_a_amount: u64, vault_b_amount: u64) -> Result<(u64, u64)> {
+        let reserve_a = vault_a_amount
+            .checked_sub(self.protocol_fees_a)
+            .ok_or(AmmError::InvalidPoolState)?;
+        let reserve_b = vault_b_amount
+            .checked_sub(self.protocol_fees_b)
+            .ok_or(AmmError::InvalidPoolState)?;
+        Ok((reserve_a, reserve_b))
+    }
+
+    /// Records `protocol_fee` as owed to the protocol on the input side of a
+    /// swap. The tokens stay in that side's vault, outside the reserves, until
+    /// `collect_protocol_fees` sends them to the treasury.
+    pub fn accrue_protocol_fee(&mut self, protocol_fee: u64, a_to_b: bool) -> Result<()> {
+        let protocol_fees = if a_to_b {
+            &mut self.protocol_fees_a
+        } else {
+            &mut self.protocol_fees_b
+        };
+        *protocol_fees = protocol_fees
+            .checked_add(protocol_fee)
+            .ok_or(AmmError::MathOverflow)?;
+        Ok(())
+    }
+
+    /// Whether a mint whose transfer hook invokes `hook_program` may move
+    /// through this pool.
+    pub fn accepts_transfer_hook(&self, hook_program: Option<Pubkey>) -> bool {
+        allowlist_accepts(&self.transfer_hook_programs, hook_program)
+    }
+
+    /// StableSwap amplification coefficient in effect at `now`.
+    pub fn current_amp(&self, now: i64) -> u64 {
+        stable_swap::ramp_amp(
+            self.amp_initial,
+            self.amp_target,
+            self.amp_ramp_start_ts,
+            self.amp_ramp_stop_ts,
+            now,
+        )
+    }
+
+    /// Volatility decayed to `now`.
+    pub fn current_volatility(&self, now: i64) -> u64 {
+        self.dynamic_fee.map_or(0, |config| {
+            dynamic_fee::decayed_volatility(
+                self.volatility,
+                now.saturating_sub(self.volatility_updated_ts),
+                config.decay_period,
+            )
+        })
+    }
+
+    /// Fee rate a swap pays at `now`.
+    pub fn swap_fee_rate(&self, now: i64) -> u16 {
+        match self.dynamic_fee {
+            None => self.fee_rate,
+            Some(config) => dynamic_fee::fee_rate(
+                config.min_fee_rate,
+                config.max_fee_rate,
+                config.volatility_multiplier,
+                self.current_volatility(now),
+            ),
+        }
+    }
+
+    /// Adds the move of the curve's spot price caused by `swap`, in the
+    /// direction given by `a_to_b` from `reserve_in` and `reserve_out`, to
+    /// the pool's volatility. Must be called after the swap's fee rate was
+    /// read.
+    pub fn record_swap(
+        &mut self,
+        swap: &SwapResult,
+        a_to_b: bool,
+        reserve_in: u64,
+        reserve_out: u64,
+        now: i64,
+    ) -> Result<()> {
+        if self.dynamic_fee.is_none() {
+            return Ok(());
+        }
+
+        let curve = self.swap_curve(now, a_to_b);
+        let (reserve_in_after, reserve_out_after) =
+            math::reserves_after_swap(swap, reserve_in, reserve_out)?;
+        let price_move = dynamic_fee::price_move_bps(
+            curve.spot_price_x64(reserve_in, reserve_out)?,
+            curve.spot_price_x64(reserve_in_after, reserve_out_after)?,
+        );
+        self.volatility = self.current_volatility(now).saturating_add(price_move);
+        self.volatility_updated_ts = now;
+        Ok(())
+    }
+
+    /// Pricing curve of this pool at `now`, oriented from A to B.
+    pub fn curve(&self, now: i64) -> Curve {
+        match self.curve_type {
+            CurveType::ConstantProduct => Curve::ConstantProduct,
+            CurveType::StableSwap => Curve::StableSwap {
+                amp: self.current_amp(now),
+            },
+            CurveType::Weighted => Curve::Weighted {
+                weight_in: self.weight_a,
+                weight_out: self.weight_b,
+            },
+        }
+    }
+
+    /// Pricing curve of this pool at `now` for a swap in the given direction.
+    pub fn swap_curve(&self, now: i64, a_to_b: bool) -> Curve {
+        let curve = self.curve(now);
+        if a_to_b {
+            curve
+        } else {
+            curve.reversed()
+        }
+    }
+
+    /// Q64.64 prices of token A in B and of token B in A at the given reserves.
+    pub fn spot_prices_x64(&self, reserve_a: u64, reserve_b: u64) -> (u128, u128) {
+        match self.curve_type {
+            CurveType::Weighted => (
+                weighted::spot_price_x64(self.weight_a, reserve_a, self.weight_b, reserve_b),
+                weighted::spot_price_x64(self.weight_b, reserve_b, self.weight_a, reserve_a),
+            ),
+            _ => (
+                math::spot_price_x64(reserve_a, reserve_b),
+                math::spot_price_x64(reserve_b, reserve_a),
+            ),
+        }
+    }
+}
+
+/// Most fee tiers an `AmmConfig` can allow.
+pub const MAX_FEE_TIERS: usize = 8;
+
+/// Program-wide settings that every new pool is checked against, and the
+/// registry of the pools created so far.
+#[account]
+#[derive(InitSpace)]
+pub struct AmmConfig {
+    pub admin: Pubkey,
+    /// Admin proposed by `propose_amm_admin`, which takes over once it calls
+    /// `accept_amm_admin`.
+    pub pending_admin: Option<Pubkey>,
+    /// Swap fee rates, in basis points and ascending, a new pool may pick.
+    #[max_len(MAX_FEE_TIERS)]
+    pub fee_tiers: Vec<u16>,
+    /// Lamports the creator of every pool pays to `fee_receiver`. Zero for none.
+    pub pool_creation_fee: u64,
+    pub fee_receiver: Pubkey,
+    /// Pools created so far, which is also the index of the next `PoolRecord`.
+    pub pool_count: u64,
+    pub bump: u8,
+}
+
+impl AmmConfig {
+    /// Replaces the fee tiers, which must be strictly ascending and below
+    /// 100%.
+    pub fn set_fee_tiers(&mut self, fee_tiers: Vec<u16>) -> Result<()> {
+        require!(fee_tiers.len() <= MAX_FEE_TIERS, AmmError::InvalidFeeTiers);
+        require!(
+            fee_tiers
+                .iter()
+                .all(|fee_rate| (*fee_rate as u64) < math::BPS_DENOMINATOR),
+            AmmError::InvalidFeeRate
+        );
+        require!(
+            fee_tiers.windows(2).all(|pair| pair[0] < pair[1]),
+            AmmError::InvalidFeeTiers
+        );
+
+        self.fee_tiers = fee_tiers;
+        Ok(())
+    }
+
+    /// Fee rates a pool keyed by `fee_tier` may charge: everything above the
+    /// next lower tier and below the next higher one, so it never charges
+    /// the rate of a pool at another tier's address.
+    pub fn fee_band(&self, fee_tier: u16) -> RangeInclusive<u16> {
+        let lowest = self
+            .fee_tiers
+            .iter()
+            .rev()
+            .find(|tier| **tier < fee_tier)
+            .map_or(0, |tier| tier + 1);
+        let highest = self
+            .fee_tiers
+            .iter()
+            .find(|tier| **tier > fee_tier)
+            .map_or(math::BPS_DENOMINATOR as u16 - 1, |tier| tier - 1);
+        lowest..=highest
+    }
+}
+
+/// Entry `index` of the pool registry. Every pool, concentrated liquidity
+/// and multi-asset pools included, gets the next one when it is created, so
+/// clients can enumerate pools up to `AmmConfig::pool_count`.
+#[account]
+#[derive(InitSpace)]
+pub struct PoolRecord {
+    pub pool: Pubkey,
+    pub index: u64,
+    pub bump: u8,
+}
+
+/// Fewest tokens a `MultiPool` can hold.
+pub const MIN_MULTI_POOL_TOKENS: usize = 3;
+
+/// Most tokens a `MultiPool` can hold.
+pub const MAX_MULTI_POOL_TOKENS: usize = 8;
+
+/// Pool of three to eight equally weighted tokens sharing one LP mint. It
+/// has no protocol fee, oracle or flash loans, and takes no mints with a
+/// transfer hook.
+#[account]
+#[derive(InitSpace)]
+pub struct MultiPool {
+    pub authority: Pubkey,
+    /// Hash of the sorted constituent mints, which seeds the pool address.
+    pub mints_hash: [u8; 32],
+    pub lp_mint: Pubkey,
+    pub fee_rate: u16,
+    /// Blocks swaps and deposits. Withdrawals stay open.
+    pub paused: bool,
+    /// Constituents in ascending mint order.
+    #[max_len(MAX_MULTI_POOL_TOKENS)]
+    pub tokens: Vec<MultiPoolToken>,
     pub bump: u8,
     pub lp_mint_bump: u8,
 }
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, InitSpace)]
+pub struct MultiPoolToken {
+    pub mint: Pubkey,
+    pub vault: Pubkey,
+}
+
+impl MultiPool {
+    /// Seed of the pool holding `mints`, which must already be sorted.
+    pub fn mints_hash(mints: &[Pubkey]) -> [u8; 32] {
+        let seeds: Vec<&[u8]> = mints.iter().map(|mint| mint.as_ref()).collect();
+        hashv(&seeds).to_bytes()
+    }
+
+    /// Position of `mint` among the pool's constituents.
+    pub fn token_index(&self, mint: &Pubkey) -> Result<usize> {
+        self.tokens
+            .iter()
+            .position(|token| token.mint == *mint)
+            .ok_or_else(|| error!(AmmError::InvalidTokenMint))
+    }
+}
+
+/// Number of observations kept in an `Oracle` ring buffer.
+pub const OBSERVATION_CAPACITY: usize = 64;
+
+/// Most `seconds_ago` entries a single `observe` call answers: the returned
+/// observations and their 4-byte length prefix must fit in return data.
+pub const MAX_OBSERVE_ENTRIES: usize = (MAX_RETURN_DATA - 4) / Observation::INIT_SPACE;
+
+/// Price history of a `Pool`, written at most once per second by every
+/// instruction that trades against or changes its reserves.
+#[account]
+#[derive(InitSpace)]
+pub struct Oracle {
+    pub pool: Pubkey,
+    /// Slot of the most recent observation in `observations`.
+    pub observation_index: u16,
+    /// Number of populated observations, up to `OBSERVATION_CAPACITY`.
+    pub observation_count: u16,
+    pub observations: [Observation; OBSERVATION_CAPACITY],
+    pub bump: u8,
+}
+
+/// Time-weighted price accumulators at `timestamp`. The TWAP between two
+/// observations is the difference of their cumulatives (wrapping) divided
+/// by the seconds between them.
+#[derive(
+    AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq, InitSpace,
+)]
+pub struct Observation {
+    pub timestamp: i64,
+    /// Sum of the Q64.64 price of token A in token B over every second.
+    pub price_a_cumulative_x64: u128,
+    /// Sum of the Q64.64 price of token B in token A over every second.
+    pub price_b_cumulative_x64: u128,
+}
+
+impl Observation {
+    /// This observation carried forward to `timestamp` at constant prices.
+    pub fn advance(&self, timestamp: i64, price_a: u128, price_b: u128) -> Self {
+        let elapsed = timestamp.saturating_sub(self.timestamp).max(0) as u128;
+
+        Self {
+            timestamp,
+            price_a_cumulative_x64: self
+                .price_a_cumulative_x64
+                .wrapping_add(price_a.wrapping_mul(elapsed)),
+            price_b_cumulative_x64: self
+                .price_b_cumulative_x64
+                .wrapping_add(price_b.wrapping_mul(elapsed)),
+        }
+    }
+
+    /// Cumulatives at `timestamp`, which must lie between `self` and `newer`,
+    /// assuming the average price held throughout.
+    fn interpolate(&self, newer: &Self, timestamp: i64) -> Self {
+        let span = (newer.timestamp - self.timestamp) as u128;
+        let elapsed = (timestamp - self.timestamp) as u128;
+        let step = |older: u128, newer: u128| {
+            let average = newer.wrapping_sub(older) / span;
+            older.wrapping_add(average.wrapping_mul(elapsed))
+        };
+
+        Self {
+            timestamp,
+            price_a_cumulative_x64: step(self.price_a_cumulative_x64, newer.price_a_cumulative_x64),
+            price_b_cumulative_x64: step(self.price_b_cumulative_x64, newer.price_b_cumulative_x64),
+        }
+    }
+}
+
+impl Oracle {
+    pub fn initialize(&mut self, pool: Pubkey, now: i64, bump: u8) {
+        self.pool = pool;
+        self.observation_index = 0;
+        self.observation_count = 1;
+        self.observations[0] = Observation {
+            timestamp: now,
+            ..Observation::default()
+        };
+        self.bump = bump;
+    }
+
+    pub fn latest(&self) -> Observation {
+        self.observations[self.observation_index as usize]
+    }
+
+    /// Records the prices the pool has held since the last observation, as
+    /// given by `Pool::spot_prices_x64`. Must be called with the prices from
+    /// before the current instruction changes the reserves.
+    pub fn update(&mut self, now: i64, price_a: u128, price_b: u128) {
+        let latest = self.latest();
+        if now <= latest.timestamp {
+            return;
+        }
+
+        let index = (self.observation_index as usize + 1) % OBSERVATION_CAPACITY;
+        self.observations[index] = latest.advance(now, price_a, price_b);
+        self.observation_index = index as u16;
+        self.observation_count = (self.observation_count + 1).min(OBSERVATION_CAPACITY as u16);
+    }
+
+    /// Cumulatives at `timestamp`, interpolated between stored observations
+    /// or extrapolated from the latest one at the current prices.
+    pub fn observe_at(&self, timestamp: i64, price_a: u128, price_b: u128) -> Result<Observation> {
+        let mut newer = self.latest();
+        if timestamp >= newer.timestamp {
+            return Ok(newer.advance(timestamp, price_a, price_b));
+        }
+
+        for age in 1..self.observation_count as usize {
+            let index = (self.observation_index as usize + OBSERVATION_CAPACITY - age)
+                % OBSERVATION_CAPACITY;
+            let older = self.observations[index];
+            if older.timestamp <= timestamp {
+                return Ok(older.interpolate(&newer, timestamp));
+            }
+            newer = older;
+        }
+
+        err!(AmmError::ObservationTooOld)
+    }
+}
+
+/// Number of ticks tracked by a single `TickArray` account.
+pub const TICK_ARRAY_SIZE: usize = 32;
+
+/// Largest tick spacing a concentrated liquidity pool may use.
+pub const MAX_TICK_SPACING: u16 = 16384;
+
+#[account]
+#[derive(InitSpace)]
+pub struct ClPool {
+    pub authority: Pubkey,
+    pub token_a_mint: Pubkey,
+    pub token_b_mint: Pubkey,
+    pub token_a_vault: Pubkey,
+    pub token_b_vault: Pubkey,
+    pub tick_spacing: u16,
+    /// One of the config's fee tiers, and part of the pool's address.
+    pub fee_rate: u16,
+    /// Square root of the price of token A in token B, Q64.64.
+    pub sqrt_price_x64: u128,
+    pub tick_current: i32,
+    /// Liquidity of the positions whose range contains the current tick.
+    pub liquidity: u128,
+    /// Swap fees earned per unit of liquidity over the pool's lifetime, Q64.64.
+    pub fee_growth_global_a_x64: u128,
+    pub fee_growth_global_b_x64: u128,
+    /// Transfer hook programs the pool's mints may invoke. Empty accepts any.
+    #[max_len(MAX_TRANSFER_HOOK_PROGRAMS)]
+    pub transfer_hook_programs: Vec<Pubkey>,
+    pub bump: u8,
+}
+
+impl ClPool {
+    /// Whether a mint whose transfer hook invokes `hook_program` may move
+    /// through this pool.
+    pub fn accepts_transfer_hook(&self, hook_program: Option<Pubkey>) -> bool {
+        allowlist_accepts(&self.transfer_hook_programs, hook_program)
+    }
+
+    /// Applies `liquidity_delta` to `position` and its bounding ticks, settling
+    /// the fees it earned so far. `tick_array_upper` is `None` when both
+    /// bounds live in `tick_array_lower`.
+    pub fn modify_position(
+        &mut self,
+        position: &mut Position,
+        tick_array_lower: &mut TickArray,
+        tick_array_upper: Option<&mut TickArray>,
+        liquidity_delta: i128,
+    ) -> Result<()> {
+        let (tick_lower, tick_upper) = (position.tick_lower, position.tick_upper);
+
+        let mut lower = *tick_array_lower.tick_mut(tick_lower, self.tick_spacing)?;
+        lower.update(
+            tick_lower,
+            self.tick_current,
+            liquidity_delta,
+            self.fee_growth_global_a_x64,
+            self.fee_growth_global_b_x64,
+            false,
+        )?;
+
+        let tick_array_upper = tick_array_upper.unwrap_or(&mut *tick_array_lower);
+        let mut upper = *tick_array_upper.tick_mut(tick_upper, self.tick_spacing)?;
+        upper.update(
+            tick_upper,
+            self.tick_current,
+            liquidity_delta,
+            self.fee_growth_global_a_x64,
+            self.fee_growth_global_b_x64,
+            true,
+        )?;
+
+        let fee_growth_inside_a_x64 = concentrated_liquidity::fee_growth_inside(
+            self.tick_current,
+            tick_lower,
+            lower.fee_growth_outside_a_x64,
+            tick_upper,
+            upper.fee_growth_outside_a_x64,
+            self.fee_growth_global_a_x64,
+        );
+        let fee_growth_inside_b_x64 = concentrated_liquidity::fee_growth_inside(
+            self.tick_current,
+            tick_lower,
+            lower.fee_growth_outside_b_x64,
+            tick_upper,
+            upper.fee_growth_outside_b_x64,
+            self.fee_growth_global_b_x64,
+        );
+        position.settle_fees(fee_growth_inside_a_x64, fee_growth_inside_b_x64)?;
+        position.liquidity =
+            concentrated_liquidity::add_liquidity_delta(position.liquidity, liquidity_delta)?;
+
+        if (tick_lower..tick_upper).contains(&self.tick_current) {
+            self.liquidity =
+                concentrated_liquidity::add_liquidity_delta(self.liquidity, liquidity_delta)?;
+        }
+
+        // Ticks no position refers to any more are cleared only after fee
+        // growth inside has been read from them
+        for tick in [&mut lower, &mut upper] {
+            if tick.liquidity_gross == 0 {
+                *tick = Tick::default();
+            }
+        }
+        *tick_array_upper.tick_mut(tick_upper, self.tick_spacing)? = upper;
+        *tick_array_lower.tick_mut(tick_lower, self.tick_spacing)? = lower;
+
+        Ok(())
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, InitSpace)]
+pub struct Tick {
+    pub initialized: bool,
+    /// Liquidity added when the price crosses this tick upwards.
+    pub liquidity_net: i128,
+    /// Total liquidity of the positions that use this tick as a bound.
+    pub liquidity_gross: u128,
+    /// Fee growth on the other side of this tick from the current price, Q64.64.
+    pub fee_growth_outside_a_x64: u128,
+    pub fee_growth_outside_b_x64: u128,
+}
+
+impl Tick {
+    /// Adds `liquidity_delta` to a position bound at this tick. A tick left
+    /// with no liquidity keeps its state until the caller clears it.
+    pub fn update(
+        &mut self,
+        tick_index: i32,
+        tick_current: i32,
+        liquidity_delta: i128,
+        fee_growth_global_a_x64: u128,
+        fee_growth_global_b_x64: u128,
+        is_upper: bool,
+    ) -> Result<()> {
+        let liquidity_gross =
+            concentrated_liquidity::add_liquidity_delta(self.liquidity_gross, liquidity_delta)?;
+
+        if !self.initialized {
+            // By convention all fee growth so far happened below the tick
+            if tick_index <= tick_current {
+                self.fee_growth_outside_a_x64 = fee_growth_global_a_x64;
+                self.fee_growth_outside_b_x64 = fee_growth_global_b_x64;
+            }
+            self.initialized = true;
+        }
+
+        self.liquidity_gross = liquidity_gross;
+        self.liquidity_net = if is_upper {
+            self.liquidity_net.checked_sub(liquidity_delta)
+        } else {
+            self.liquidity_net.checked_add(liquidity_delta)
+        }
+        .ok_or(AmmError::MathOverflow)?;
+
+        Ok(())
+    }
+
+    /// Flips fee growth outside when the price crosses this tick and returns
+    /// the tick's `liquidity_net`.
+    pub fn cross(&mut self, fee_growth_global_a_x64: u128, fee_growth_global_b_x64: u128) -> i128 {
+        self.fee_growth_outside_a_x64 =
+            fee_growth_global_a_x64.wrapping_sub(self.fee_growth_outside_a_x64);
+        self.fee_growth_outside_b_x64 =
+            fee_growth_global_b_x64.wrapping_sub(self.fee_growth_outside_b_x64);
+        self.liquidity_net
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct TickArray {
+    pub pool: Pubkey,
+    pub start_tick_index: i32,
+    pub ticks: [Tick; TICK_ARRAY_SIZE],
+}
+
+impl TickArray {
+    /// Start index of the tick array that contains `tick_index`.
+    pub fn start_index_for(tick_index: i32, tick_spacing: u16) -> i32 {
+        let span = tick_spacing as i32 * TICK_ARRAY_SIZE as i32;
+        tick_index.div_euclid(span) * span
+    }
+
+    /// Last tick index covered by this array.
+    pub fn last_tick_index(&self, tick_spacing: u16) -> i32 {
+        self.start_tick_index + (TICK_ARRAY_SIZE as i32 - 1) * tick_spacing as i32
+    }
+
+    pub fn contains(&self, tick_index: i32, tick_spacing: u16) -> bool {
+        Self::start_index_for(tick_index, tick_spacing) == self.start_tick_index
+    }
+
+    pub fn tick_mut(&mut self, tick_index: i32, tick_spacing: u16) -> Result<&mut Tick> {
+        require!(
+            tick_index % tick_spacing as i32 == 0 && self.contains(tick_index, tick_spacing),
+            AmmError::InvalidTickIndex
+        );
+        let offset = (tick_index - self.start_tick_index) / tick_spacing as i32;
+        Ok(&mut self.ticks[offset as usize])
+    }
+
+    /// Next initialized tick in this array in the swap direction: the
+    /// greatest one at or below `tick_current` when `a_to_b`, otherwise the
+    /// smallest one above it.
+    pub fn next_initialized_tick(
+        &self,
+        tick_current: i32,
+        tick_spacing: u16,
+        a_to_b: bool,
+    ) -> Option<i32> {
+        let spacing = tick_spacing as i32;
+        let tick_of = |offset: usize| self.start_tick_index + offset as i32 * spacing;
+
+        if a_to_b {
+            (0..TICK_ARRAY_SIZE)
+                .rev()
+                .find(|offset| self.ticks[*offset].initialized && tick_of(*offset) <= tick_current)
+                .map(tick_of)
+        } else {
+            (0..TICK_ARRAY_SIZE)
+                .find(|offset| self.ticks[*offset].initialized && tick_of(*offset) > tick_current)
+                .map(tick_of)
+        }
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Position {
+    pub pool: Pubkey,
+    pub owner: Pubkey,
+    pub tick_lower: i32,
+    pub tick_upper: i32,
+    pub liquidity: u128,
+    /// Fee growth inside the range when fees were last settled, Q64.64.
+    pub fee_growth_inside_a_last_x64: u128,
+    pub fee_growth_inside_b_last_x64: u128,
+    /// Fees settled to this position and not yet collected.
+    pub tokens_owed_a: u64,
+    pub tokens_owed_b: u64,
+    pub bump: u8,
+}
+
+impl Position {
+    /// Credits the fees earned since the last settlement and records the new
+    /// fee growth inside the range.
+    pub fn settle_fees(
+        &mut self,
+        fee_growth_inside_a_x64: u128,
+        fee_growth_inside_b_x64: u128,
+    ) -> Result<()> {
+        let fees_a = concentrated_liquidity::fees_earned(
+            self.liquidity,
+            fee_growth_inside_a_x64,
+            self.fee_growth_inside_a_last_x64,
+        )?;
+        let fees_b = concentrated_liquidity::fees_earned(
+            self.liquidity,
+            fee_growth_inside_b_x64,
+            self.fee_growth_inside_b_last_x64,
+        )?;
+
+        self.tokens_owed_a = self
+            .tokens_owed_a
+            .checked_add(fees_a)
+            .ok_or(AmmError::MathOverflow)?;
+        self.tokens_owed_b = self
+            .tokens_owed_b
+            .checked_add(fees_b)
+            .ok_or(AmmError::MathOverflow)?;
+        self.fee_growth_inside_a_last_x64 = fee_growth_inside_a_x64;
+        self.fee_growth_inside_b_last_x64 = fee_growth_inside_b_x64;
+
+        Ok(())
+    }
+}
+
+/// Liquidity-mining farm paying `reward_mint` to holders of a pool's LP
+/// tokens staked in `stake_vault`.
+#[account]
+#[derive(InitSpace)]
+pub struct Farm {
+    pub pool: Pubkey,
+    pub lp_mint: Pubkey,
+    pub reward_mint: Pubkey,
+    pub stake_vault: Pubkey,
+    pub reward_vault: Pubkey,
+    /// Reward tokens emitted per second, shared by all stakers.
+    pub reward_rate: u64,
+    /// Rewards emitted per staked LP token over the farm's lifetime, Q64.64.
+    pub reward_per_share_x64: u128,
+    /// Funded rewards not emitted yet. Emission stops when this runs out.
+    pub rewards_unallocated: u64,
+    pub total_staked: u64,
+    pub last_update_ts: i64,
+    pub bump: u8,
+}
+
+impl Farm {
+    /// Emits the rewards accrued since the last update to the current
+    /// stakers. Nothing is emitted while nothing is staked.
+    pub fn update(&mut self, now: i64) {
+        if now <= self.last_update_ts {
+            return;
+        }
+        let elapsed = (now - self.last_update_ts) as u64;
+        self.last_update_ts = now;
+        if self.total_staked == 0 {
+            return;
+        }
+
+        let emitted = farm::rewards_emitted(self.reward_rate, elapsed, self.rewards_unallocated);
+        self.rewards_unallocated -= emitted;
+        self.reward_per_share_x64 = self
+            .reward_per_share_x64
+            .wrapping_add(farm::reward_per_share_delta(emitted, self.total_staked));
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct FarmPosition {
+    pub farm: Pubkey,
+    pub owner: Pubkey,
+    /// LP tokens staked.
+    pub amount: u64,
+    /// Farm reward per share when rewards were last settled, Q64.64.
+    pub reward_per_share_last_x64: u128,
+    /// Rewards settled to this position and not yet claimed.
+    pub rewards_owed: u64,
+    pub bump: u8,
+}
+
+impl FarmPosition {
+    /// Credits the rewards earned since the last settlement.
+    pub fn settle_rewards(&mut self, reward_per_share_x64: u128) -> Result<()> {
+        let earned = farm::rewards_earned(
+            self.amount,
+            reward_per_share_x64,
+            self.reward_per_share_last_x64,
+        )?;
+
+        self.rewards_owed = self
+            .rewards_owed
+            .checked_add(earned)
+            .ok_or(AmmError::MathOverflow)?;
+        self.reward_per_share_last_x64 = reward_per_share_x64;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn oracle_at(now: i64) -> Oracle {
+        let mut oracle = Oracle {
+            pool: Pubkey::default(),
+            observation_index: 0,
+            observation_count: 0,
+            observations: [Observation::default(); OBSERVATION_CAPACITY],
+            bump: 0,
+        };
+        oracle.initialize(Pubkey::default(), now, 0);
+        oracle
+    }
+
+    fn pool() -> Pool {
+        Pool {
+            authority: Pubkey::default(),
+            pending_authority: None,
+            token_a_mint: Pubkey::default(),
+            token_b_mint: Pubkey::default(),
+            token_a_vault: Pubkey::default(),
+            token_b_vault: Pubkey::default(),
+            lp_mint: Pubkey::default(),
+            fee_rate: 30,
+            fee_tier: 30,
+            curve_type: CurveType::ConstantProduct,
+            amp_initial: 0,
+            amp_target: 0,
+            amp_ramp_start_ts: 0,
+            amp_ramp_stop_ts: 0,
+            weight_a: 5000,
+            weight_b: 5000,
+            protocol_fee_rate: 0,
+            protocol_fees_a: 0,
+            protocol_fees_b: 0,
+            flash_loan_active: false,
+            flash_loan_amount_a: 0,
+            flash_loan_amount_b: 0,
+            paused: false,
+            transfer_hook_programs: Vec::new(),
+            dynamic_fee: None,
+            volatility: 0,
+            volatility_updated_ts: 0,
+            bump: 0,
+            lp_mint_bump: 0,
+        }
+    }
+
+    #[test]
+    fn oracle_accumulates_time_weighted_price() {
+        let mut oracle = oracle_at(100);
+        // Price 2.0 for 10 seconds, then 4.0 for 30 seconds
+        oracle.update(110, 2 << 64, 1 << 63);
+        oracle.update(110, 1 << 64, 1 << 64);
+        oracle.update(140, 4 << 64, 1 << 62);
+
+        let start = oracle.observe_at(100, 4 << 64, 1 << 62).unwrap();
+        let end = oracle.observe_at(140, 4 << 64, 1 << 62).unwrap();
+        let twap = (end.price_a_cumulative_x64 - start.price_a_cumulative_x64) / 40;
+        assert_eq!(twap, (7 << 64) / 2);
+
+        let mid = oracle.observe_at(125, 4 << 64, 1 << 62).unwrap();
+        assert_eq!(mid.price_a_cumulative_x64, (20u128 << 64) + (60u128 << 64));
+        let ahead = oracle.observe_at(150, 4 << 64, 1 << 62).unwrap();
+        assert_eq!(
+            ahead.price_a_cumulative_x64,
+            (140u128 << 64) + (40u128 << 64)
+        );
+    }
+
+    #[test]
+    fn farm_emits_only_while_staked_and_funded() {
+        let mut farm = Farm {
+            pool: Pubkey::default(),
+            lp_mint: Pubkey::default(),
+            reward_mint: Pubkey::default(),
+            stake_vault: Pubkey::default(),
+            reward_vault: Pubkey::default(),
+            reward_rate: 10,
+            reward_per_share_x64: 0,
+            rewards_unallocated: 150,
+            total_staked: 0,
+            last_update_ts: 0,
+            bump: 0,
+        };
+        let mut position = FarmPosition {
+            farm: Pubkey::default(),
+            owner: Pubkey::default(),
+            amount: 0,
+            reward_per_share_last_x64: 0,
+            rewards_owed: 0,
+            bump: 0,
+        };
+
+        // Nobody is staked for the first 5 seconds
+        farm.update(5);
+        assert_eq!(farm.rewards_unallocated, 150);
+
+        position.settle_rewards(farm.reward_per_share_x64).unwrap();
+        position.amount = 4;
+        farm.total_staked = 4;
+
+        // 10 seconds at 10 per second, then funding runs out after 5 more
+        farm.update(15);
+        farm.update(30);
+        position.settle_rewards(farm.reward_per_share_x64).unwrap();
+        assert_eq!(farm.rewards_unallocated, 0);
+        assert_eq!(position.rewards_owed, 150);
+    }
+
+    #[test]
+    fn dynamic_fee_follows_decaying_volatility() {
+        let mut pool = pool();
+        // Takes the price of the input token from 10/9 to 9/10, a 19% fall
+        // that loses a basis point to the rounding of both prices
+        let swap = SwapResult {
+            amount_in: 1_000,
+            amount_out: 1_000,
+            fee: 0,
+            protocol_fee: 0,
+        };
+
+        pool.record_swap(&swap, true, 9_000, 10_000, 0).unwrap();
+        assert_eq!(pool.volatility, 0);
+        assert_eq!(pool.swap_fee_rate(0), 30);
+
+        pool.dynamic_fee = Some(DynamicFeeConfig {
+            min_fee_rate: 5,
+            max_fee_rate: 100,
+            volatility_multiplier: 500,
+            decay_period: 100,
+        });
+        assert_eq!(pool.swap_fee_rate(0), 5);
+
+        pool.record_swap(&swap, true, 9_000, 10_000, 0).unwrap();
+        assert_eq!(pool.volatility, 1_899);
+        assert_eq!(pool.swap_fee_rate(0), 99);
+        assert_eq!(pool.swap_fee_rate(50), 52);
+        assert_eq!(pool.swap_fee_rate(100), 5);
+
+        // A second move stacks on what is left of the first
+        pool.record_swap(&swap, true, 9_000, 10_000, 50).unwrap();
+        assert_eq!(pool.volatility, 2_848);
+        assert_eq!(pool.volatility_updated_ts, 50);
+    }
+
+    #[test]
+    fn balanced_stable_swap_barely_moves_volatility() {
+        let config = DynamicFeeConfig {
+            min_fee_rate: 5,
+            max_fee_rate: 100,
+            volatility_multiplier: 500,
+            decay_period: 100,
+        };
+        let swap = SwapResult {
+            amount_in: 10_000,
+            amount_out: 9_990,
+            fee: 0,
+            protocol_fee: 0,
+        };
+
+        // The same 1% swap moves a constant product price by about 2%
+        let mut constant_product = pool();
+        constant_product.dynamic_fee = Some(config);
+        constant_product
+            .record_swap(&swap, true, 1_000_000, 1_000_000, 0)
+            .unwrap();
+        assert!(constant_product.volatility >= 190);
+
+        // but leaves a stable pair near its peg
+        let mut stable = pool();
+        stable.curve_type = CurveType::StableSwap;
+        stable.amp_initial = 100;
+        stable.amp_target = 100;
+        stable.dynamic_fee = Some(config);
+        stable
+            .record_swap(&swap, true, 1_000_000, 1_000_000, 0)
+            .unwrap();
+        assert!(stable.volatility <= 2);
+        assert_eq!(stable.swap_fee_rate(0), 5);
+    }
+
+    #[test]
+    fn weighted_pool_prices_by_weight() {
+        let mut pool = pool();
+        pool.curve_type = CurveType::Weighted;
+        pool.weight_a = 8000;
+        pool.weight_b = 2000;
+
+        // Equal balances at 80/20 value one A at four B
+        assert_eq!(pool.spot_prices_x64(1_000, 1_000), (4 << 64, 1 << 62));
+        assert_eq!(
+            pool.swap_curve(0, false),
+            Curve::Weighted {
+                weight_in: 2000,
+                weight_out: 8000
+            }
+        );
+    }
+
+    #[test]
+    fn empty_transfer_hook_allowlist_accepts_any_hook() {
+        let hook = Pubkey::new_unique();
+        assert!(allowlist_accepts(&[], None));
+        assert!(allowlist_accepts(&[], Some(hook)));
+        assert!(allowlist_accepts(&[hook], Some(hook)));
+        assert!(allowlist_accepts(&[hook], None));
+        assert!(!allowlist_accepts(&[hook], Some(Pubkey::new_unique())));
+    }
+
+    #[test]
+    fn fee_tiers_must_ascend_below_one_hundred_percent() {
+        let mut config = AmmConfig {
+            admin: Pubkey::default(),
+            pending_admin: None,
+            fee_tiers: Vec::new(),
+            pool_creation_fee: 0,
+            fee_receiver: Pubkey::default(),
+            pool_count: 0,
+            bump: 0,
+        };
+
+        config.set_fee_tiers(vec![1, 5, 30, 100]).unwrap();
+        assert_eq!(config.fee_tiers, vec![1, 5, 30, 100]);
+
+        assert!(config.set_fee_tiers(vec![30, 5]).is_err());
+        assert!(config.set_fee_tiers(vec![30, 30]).is_err());
+        assert!(config.set_fee_tiers(vec![30, 10_000]).is_err());
+        assert!(config.set_fee_tiers((1..=9).collect()).is_err());
+        assert_eq!(config.fee_tiers, vec![1, 5, 30, 100]);
+    }
+
+    #[test]
+    fn fee_band_stops_short_of_the_neighbouring_tiers() {
+        let config = AmmConfig {
+            admin: Pubkey::default(),
+            pending_admin: None,
+            fee_tiers: vec![1, 5, 30, 100],
+            pool_creation_fee: 0,
+            fee_receiver: Pubkey::default(),
+            pool_count: 0,
+            bump: 0,
+        };
+
+        assert_eq!(config.fee_band(1), 0..=4);
+        assert_eq!(config.fee_band(5), 2..=29);
+        assert_eq!(config.fee_band(30), 6..=99);
+        assert_eq!(config.fee_band(100), 31..=9_999);
+        // A tier dropped from the config still sits between its neighbours
+        assert_eq!(config.fee_band(10), 6..=29);
+    }
+
+    #[test]
+    fn multi_pool_is_seeded_by_its_mints_in_order() {
+        let mut mints: Vec<Pubkey> = (0..3).map(|_| Pubkey::new_unique()).collect();
+        mints.sort();
+        let pool = MultiPool {
+            authority: Pubkey::default(),
+            mints_hash: MultiPool::mints_hash(&mints),
+            lp_mint: Pubkey::default(),
+            fee_rate: 30,
+            paused: false,
+            tokens: mints
+                .iter()
+                .map(|mint| MultiPoolToken {
+                    mint: *mint,
+                    vault: Pubkey::new_unique(),
+                })
+                .collect(),
+            bump: 0,
+            lp_mint_bump: 0,
+        };
+
+        mints.swap(0, 1);
+        assert_ne!(MultiPool::mints_hash(&mints), pool.mints_hash);
+        assert_eq!(pool.token_index(&mints[0]).unwrap(), 1);
+        assert!(pool.token_index(&Pubkey::new_unique()).is_err());
+    }
+
+    #[test]
+    fn oracle_ring_buffer_forgets_oldest() {
+        let mut oracle = oracle_at(0);
+        for second in 1..=OBSERVATION_CAPACITY as i64 {
+            oracle.update(second, 1 << 64, 1 << 64);
+        }
+
+        assert_eq!(oracle.observation_count as usize, OBSERVATION_CAPACITY);
+        assert!(oracle.observe_at(0, 1 << 64, 1 << 64).is_err());
+        assert_eq!(
+            oracle
+                .observe_at(1, 1 << 64, 1 << 64)
+                .unwrap()
+                .price_a_cumulative_x64,
+            1 << 64
+        );
+    }
+
+    #[test]
+    fn observe_entries_fit_in_return_data() {
+        let returned = |entries: usize| vec![Observation::default(); entries].try_to_vec().unwrap();
+
+        assert_eq!(MAX_OBSERVE_ENTRIES, 25);
+        assert!(returned(MAX_OBSERVE_ENTRIES).len() <= MAX_RETURN_DATA);
+        assert!(returned(MAX_OBSERVE_ENTRIES + 1).len() > MAX_RETURN_DATA);
+    }
+}
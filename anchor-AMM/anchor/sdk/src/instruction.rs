@@ -0,0 +1,1283 @@
+//! Builders for every `amm` instruction.
+//!
+//! Arguments are the program's generated `amm::instruction` types, and every
+//! PDA is derived from the keys structs below. Mints with a Token-2022
+//! transfer hook need the hook's extra accounts appended to the returned
+//! instruction's `accounts`.
+
+use amm::{
+    accounts, instruction as args,
+    states::{AmmConfig, TickArray},
+};
+use anchor_lang::{
+    prelude::*,
+    solana_program::{instruction::Instruction, system_program, sysvar},
+    InstructionData,
+};
+use anchor_spl::{associated_token, token};
+
+use crate::pda;
+
+/// Fills the `event_authority` and `program` accounts `#[event_cpi]` adds
+/// when the program is built with `event-cpi`.
+macro_rules! with_event_cpi {
+    (accounts::$name:ident { $($field:ident $(: $value:expr)?),* $(,)? }) => {
+        accounts::$name {
+            $($field $(: $value)?,)*
+            #[cfg(feature = "event-cpi")]
+            event_authority: pda::event_authority_address().0,
+            #[cfg(feature = "event-cpi")]
+            program: amm::ID,
+        }
+    };
+}
+
+fn build(accounts: impl ToAccountMetas, args: impl InstructionData) -> Instruction {
+    Instruction {
+        program_id: amm::ID,
+        accounts: accounts.to_account_metas(None),
+        data: args.data(),
+    }
+}
+
+/// Creates the program-wide config. `admin` must be the program's upgrade
+/// authority.
+pub fn initialize_amm_config(admin: Pubkey, args: args::InitializeAmmConfig) -> Instruction {
+    let accounts = with_event_cpi!(accounts::InitializeAmmConfig {
+        admin,
+        amm_config: pda::amm_config_address().0,
+        program_data: pda::program_data_address().0,
+        system_program: system_program::ID,
+    });
+    build(accounts, args)
+}
+
+fn update_amm_config_accounts(admin: Pubkey) -> accounts::UpdateAmmConfig {
+    with_event_cpi!(accounts::UpdateAmmConfig {
+        admin,
+        amm_config: pda::amm_config_address().0,
+    })
+}
+
+pub fn set_fee_tiers(admin: Pubkey, args: args::SetFeeTiers) -> Instruction {
+    build(update_amm_config_accounts(admin), args)
+}
+
+pub fn set_pool_creation_fee(admin: Pubkey, args: args::SetPoolCreationFee) -> Instruction {
+    build(update_amm_config_accounts(admin), args)
+}
+
+pub fn propose_amm_admin(admin: Pubkey, args: args::ProposeAmmAdmin) -> Instruction {
+    build(update_amm_config_accounts(admin), args)
+}
+
+pub fn accept_amm_admin(new_admin: Pubkey) -> Instruction {
+    let accounts = with_event_cpi!(accounts::AcceptAmmAdmin {
+        new_admin,
+        amm_config: pda::amm_config_address().0,
+    });
+    build(accounts, args::AcceptAmmAdmin {})
+}
+
+/// Addresses of a constant-product or StableSwap pool.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PoolKeys {
+    pub pool: Pubkey,
+    pub fee_tier: u16,
+    pub oracle: Pubkey,
+    pub lp_mint: Pubkey,
+    pub locked_lp_vault: Pubkey,
+    pub token_a_mint: Pubkey,
+    pub token_b_mint: Pubkey,
+    pub token_a_vault: Pubkey,
+    pub token_b_vault: Pubkey,
+    pub token_a_program: Pubkey,
+    pub token_b_program: Pubkey,
+    /// Token program of the LP mint.
+    pub lp_token_program: Pubkey,
+}
+
+impl PoolKeys {
+    /// Keys of the pool for the given mints and fee tier, with its LP mint
+    /// under SPL Token. `token_a_mint` must sort before `token_b_mint`.
+    pub fn new(
+        token_a_mint: Pubkey,
+        token_b_mint: Pubkey,
+        token_a_program: Pubkey,
+        token_b_program: Pubkey,
+        fee_tier: u16,
+    ) -> Self {
+        let (pool, _) = pda::pool_address(&token_a_mint, &token_b_mint, fee_tier);
+        let (lp_mint, _) = pda::lp_mint_address(&pool);
+        let lp_token_program = token::ID;
+
+        Self {
+            pool,
+            fee_tier,
+            oracle: pda::oracle_address(&pool).0,
+            lp_mint,
+            locked_lp_vault: pda::vault_address(&pool, &lp_mint, &lp_token_program),
+            token_a_mint,
+            token_b_mint,
+            token_a_vault: pda::vault_address(&pool, &token_a_mint, &token_a_program),
+            token_b_vault: pda::vault_address(&pool, &token_b_mint, &token_b_program),
+            token_a_program,
+            token_b_program,
+            lp_token_program,
+        }
+    }
+
+    /// Associated token accounts of `owner` for both pool tokens.
+    pub fn user_tokens(&self, owner: Pubkey) -> UserTokens {
+        UserTokens::associated(
+            owner,
+            (&self.token_a_mint, &self.token_a_program),
+            (&self.token_b_mint, &self.token_b_program),
+        )
+    }
+
+    /// Associated LP token account of `owner`.
+    pub fn user_lp_token(&self, owner: &Pubkey) -> Pubkey {
+        pda::vault_address(owner, &self.lp_mint, &self.lp_token_program)
+    }
+}
+
+/// A wallet and its token accounts for both tokens of a pool.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct UserTokens {
+    pub owner: Pubkey,
+    pub token_a: Pubkey,
+    pub token_b: Pubkey,
+}
+
+impl UserTokens {
+    /// `owner` with its associated token accounts for `(mint, token_program)`
+    /// of each side.
+    pub fn associated(
+        owner: Pubkey,
+        token_a: (&Pubkey, &Pubkey),
+        token_b: (&Pubkey, &Pubkey),
+    ) -> Self {
+        Self {
+            owner,
+            token_a: pda::vault_address(&owner, token_a.0, token_a.1),
+            token_b: pda::vault_address(&owner, token_b.0, token_b.1),
+        }
+    }
+}
+
+/// Creates the pool. `config` is the current `AmmConfig`, whose pool count
+/// picks the registry entry and whose receiver is paid the creation fee.
+pub fn initialize_pool(
+    keys: &PoolKeys,
+    config: &AmmConfig,
+    authority: Pubkey,
+    args: args::InitializePool,
+) -> Instruction {
+    let accounts = with_event_cpi!(accounts::InitializePool {
+        authority,
+        amm_config: pda::amm_config_address().0,
+        pool_record: pda::pool_record_address(config.pool_count).0,
+        fee_receiver: config.fee_receiver,
+        pool: keys.pool,
+        token_a_mint: keys.token_a_mint,
+        token_b_mint: keys.token_b_mint,
+        lp_mint: keys.lp_mint,
+        oracle: keys.oracle,
+        locked_lp_vault: keys.locked_lp_vault,
+        token_a_vault: keys.token_a_vault,
+        token_b_vault: keys.token_b_vault,
+        token_a_program: keys.token_a_program,
+        token_b_program: keys.token_b_program,
+        token_program: keys.lp_token_program,
+        associated_token_program: associated_token::ID,
+        system_program: system_program::ID,
+    });
+    build(accounts, args)
+}
+
+fn add_liquidity_accounts(keys: &PoolKeys, user: &UserTokens) -> accounts::AddLiquidity {
+    with_event_cpi!(accounts::AddLiquidity {
+        user: user.owner,
+        pool: keys.pool,
+        oracle: keys.oracle,
+        lp_mint: keys.lp_mint,
+        token_a_vault: keys.token_a_vault,
+        token_b_vault: keys.token_b_vault,
+        user_token_a: user.token_a,
+        user_token_b: user.token_b,
+        user_lp_token: keys.user_lp_token(&user.owner),
+        locked_lp_vault: keys.locked_lp_vault,
+        token_a_mint: keys.token_a_mint,
+        token_b_mint: keys.token_b_mint,
+        token_a_program: keys.token_a_program,
+        token_b_program: keys.token_b_program,
+        token_program: keys.lp_token_program,
+        associated_token_program: associated_token::ID,
+        system_program: system_program::ID,
+    })
+}
+
+fn remove_liquidity_accounts(keys: &PoolKeys, user: &UserTokens) -> accounts::RemoveLiquidity {
+    with_event_cpi!(accounts::RemoveLiquidity {
+        user: user.owner,
+        pool: keys.pool,
+        oracle: keys.oracle,
+        lp_mint: keys.lp_mint,
+        token_a_vault: keys.token_a_vault,
+        token_b_vault: keys.token_b_vault,
+        user_token_a: user.token_a,
+        user_token_b: user.token_b,
+        user_lp_token: keys.user_lp_token(&user.owner),
+        token_a_mint: keys.token_a_mint,
+        token_b_mint: keys.token_b_mint,
+        token_a_program: keys.token_a_program,
+        token_b_program: keys.token_b_program,
+        token_program: keys.lp_token_program,
+    })
+}
+
+fn swap_accounts(keys: &PoolKeys, user: &UserTokens) -> accounts::Swap {
+    with_event_cpi!(accounts::Swap {
+        user: user.owner,
+        pool: keys.pool,
+        oracle: keys.oracle,
+        token_a_vault: keys.token_a_vault,
+        token_b_vault: keys.token_b_vault,
+        user_token_a: user.token_a,
+        user_token_b: user.token_b,
+        token_a_mint: keys.token_a_mint,
+        token_b_mint: keys.token_b_mint,
+        token_a_program: keys.token_a_program,
+        token_b_program: keys.token_b_program,
+    })
+}
+
+/// Deposits into the user's associated LP token account.
+pub fn add_liquidity(keys: &PoolKeys, user: &UserTokens, args: args::AddLiquidity) -> Instruction {
+    build(add_liquidity_accounts(keys, user), args)
+}
+
+/// Burns from the user's associated LP token account.
+pub fn remove_liquidity(
+    keys: &PoolKeys,
+    user: &UserTokens,
+    args: args::RemoveLiquidity,
+) -> Instruction {
+    build(remove_liquidity_accounts(keys, user), args)
+}
+
+pub fn swap(keys: &PoolKeys, user: &UserTokens, args: args::Swap) -> Instruction {
+    build(swap_accounts(keys, user), args)
+}
+
+pub fn swap_exact_out(keys: &PoolKeys, user: &UserTokens, args: args::SwapExactOut) -> Instruction {
+    build(swap_accounts(keys, user), args)
+}
+
+pub fn zap_in(keys: &PoolKeys, user: &UserTokens, args: args::ZapIn) -> Instruction {
+    build(add_liquidity_accounts(keys, user), args)
+}
+
+pub fn zap_out(keys: &PoolKeys, user: &UserTokens, args: args::ZapOut) -> Instruction {
+    build(remove_liquidity_accounts(keys, user), args)
+}
+
+/// One leg of a `route_swap`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RouteHop {
+    pub pool: PoolKeys,
+    pub a_to_b: bool,
+}
+
+/// Swaps from `user_source` through `hops` into `user_destination`.
+/// `args.hop_count` is taken from `hops`.
+pub fn route_swap(
+    user: Pubkey,
+    user_source: Pubkey,
+    user_destination: Pubkey,
+    hops: &[RouteHop],
+    mut args: args::RouteSwap,
+) -> Instruction {
+    args.hop_count = hops.len() as u8;
+    let accounts = with_event_cpi!(accounts::RouteSwap {
+        user,
+        user_source,
+        user_destination,
+        token_program: token::ID,
+        token_2022_program: anchor_spl::token_2022::ID,
+    });
+    let mut ix = build(accounts, args);
+
+    for hop in hops {
+        let keys = &hop.pool;
+        let (vault_in, vault_out, mint_in, mint_out) = if hop.a_to_b {
+            (
+                keys.token_a_vault,
+                keys.token_b_vault,
+                keys.token_a_mint,
+                keys.token_b_mint,
+            )
+        } else {
+            (
+                keys.token_b_vault,
+                keys.token_a_vault,
+                keys.token_b_mint,
+                keys.token_a_mint,
+            )
+        };
+        ix.accounts.extend([
+            AccountMeta::new(keys.pool, false),
+            AccountMeta::new(keys.oracle, false),
+            AccountMeta::new(vault_in, false),
+            AccountMeta::new(vault_out, false),
+            AccountMeta::new_readonly(mint_in, false),
+            AccountMeta::new_readonly(mint_out, false),
+        ]);
+    }
+    ix
+}
+
+pub fn observe(keys: &PoolKeys, args: args::Observe) -> Instruction {
+    let accounts = accounts::Observe {
+        pool: keys.pool,
+        oracle: keys.oracle,
+        token_a_vault: keys.token_a_vault,
+        token_b_vault: keys.token_b_vault,
+    };
+    build(accounts, args)
+}
+
+pub fn flash_borrow(
+    keys: &PoolKeys,
+    borrower: &UserTokens,
+    args: args::FlashBorrow,
+) -> Instruction {
+    let accounts = with_event_cpi!(accounts::FlashBorrow {
+        borrower: borrower.owner,
+        pool: keys.pool,
+        token_a_vault: keys.token_a_vault,
+        token_b_vault: keys.token_b_vault,
+        borrower_token_a: borrower.token_a,
+        borrower_token_b: borrower.token_b,
+        token_a_mint: keys.token_a_mint,
+        token_b_mint: keys.token_b_mint,
+        token_a_program: keys.token_a_program,
+        token_b_program: keys.token_b_program,
+        instructions: sysvar::instructions::ID,
+    });
+    build(accounts, args)
+}
+
+pub fn flash_repay(keys: &PoolKeys, borrower: &UserTokens) -> Instruction {
+    let accounts = with_event_cpi!(accounts::FlashRepay {
+        borrower: borrower.owner,
+        pool: keys.pool,
+        token_a_vault: keys.token_a_vault,
+        token_b_vault: keys.token_b_vault,
+        borrower_token_a: borrower.token_a,
+        borrower_token_b: borrower.token_b,
+        token_a_mint: keys.token_a_mint,
+        token_b_mint: keys.token_b_mint,
+        token_a_program: keys.token_a_program,
+        token_b_program: keys.token_b_program,
+    });
+    build(accounts, args::FlashRepay {})
+}
+
+/// Sends the accrued protocol fees to `treasury`'s token accounts.
+pub fn collect_protocol_fees(
+    keys: &PoolKeys,
+    authority: Pubkey,
+    treasury: &UserTokens,
+) -> Instruction {
+    let accounts = with_event_cpi!(accounts::CollectProtocolFees {
+        authority,
+        pool: keys.pool,
+        token_a_vault: keys.token_a_vault,
+        token_b_vault: keys.token_b_vault,
+        treasury_token_a: treasury.token_a,
+        treasury_token_b: treasury.token_b,
+        token_a_mint: keys.token_a_mint,
+        token_b_mint: keys.token_b_mint,
+        token_a_program: keys.token_a_program,
+        token_b_program: keys.token_b_program,
+    });
+    build(accounts, args::CollectProtocolFees {})
+}
+
+pub fn ramp_amp(keys: &PoolKeys, authority: Pubkey, args: args::RampAmp) -> Instruction {
+    let accounts = with_event_cpi!(accounts::RampAmp {
+        authority,
+        pool: keys.pool,
+    });
+    build(accounts, args)
+}
+
+fn update_pool_accounts(keys: &PoolKeys, authority: Pubkey) -> accounts::UpdatePool {
+    with_event_cpi!(accounts::UpdatePool {
+        authority,
+        pool: keys.pool,
+    })
+}
+
+fn update_fee_rate_accounts(keys: &PoolKeys, authority: Pubkey) -> accounts::UpdateFeeRate {
+    with_event_cpi!(accounts::UpdateFeeRate {
+        authority,
+        amm_config: pda::amm_config_address().0,
+        pool: keys.pool,
+    })
+}
+
+pub fn update_fee_rate(
+    keys: &PoolKeys,
+    authority: Pubkey,
+    args: args::UpdateFeeRate,
+) -> Instruction {
+    build(update_fee_rate_accounts(keys, authority), args)
+}
+
+pub fn set_dynamic_fee(
+    keys: &PoolKeys,
+    authority: Pubkey,
+    args: args::SetDynamicFee,
+) -> Instruction {
+    build(update_fee_rate_accounts(keys, authority), args)
+}
+
+pub fn set_paused(keys: &PoolKeys, authority: Pubkey, args: args::SetPaused) -> Instruction {
+    build(update_pool_accounts(keys, authority), args)
+}
+
+pub fn set_transfer_hook_programs(
+    keys: &PoolKeys,
+    authority: Pubkey,
+    args: args::SetTransferHookPrograms,
+) -> Instruction {
+    build(update_pool_accounts(keys, authority), args)
+}
+
+pub fn propose_authority(
+    keys: &PoolKeys,
+    authority: Pubkey,
+    args: args::ProposeAuthority,
+) -> Instruction {
+    build(update_pool_accounts(keys, authority), args)
+}
+
+pub fn accept_authority(keys: &PoolKeys, new_authority: Pubkey) -> Instruction {
+    let accounts = with_event_cpi!(accounts::AcceptAuthority {
+        new_authority,
+        pool: keys.pool,
+    });
+    build(accounts, args::AcceptAuthority {})
+}
+
+/// Addresses of a concentrated-liquidity pool.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ClPoolKeys {
+    pub cl_pool: Pubkey,
+    pub tick_spacing: u16,
+    pub token_a_mint: Pubkey,
+    pub token_b_mint: Pubkey,
+    pub token_a_vault: Pubkey,
+    pub token_b_vault: Pubkey,
+    pub token_a_program: Pubkey,
+    pub token_b_program: Pubkey,
+}
+
+impl ClPoolKeys {
+    pub fn new(
+        token_a_mint: Pubkey,
+        token_b_mint: Pubkey,
+        token_a_program: Pubkey,
+        token_b_program: Pubkey,
+        tick_spacing: u16,
+        fee_tier: u16,
+    ) -> Self {
+        let (cl_pool, _) = pda::cl_pool_address(&token_a_mint, &token_b_mint, fee_tier);
+
+        Self {
+            cl_pool,
+            tick_spacing,
+            token_a_mint,
+            token_b_mint,
+            token_a_vault: pda::vault_address(&cl_pool, &token_a_mint, &token_a_program),
+            token_b_vault: pda::vault_address(&cl_pool, &token_b_mint, &token_b_program),
+            token_a_program,
+            token_b_program,
+        }
+    }
+
+    /// Associated token accounts of `owner` for both pool tokens.
+    pub fn user_tokens(&self, owner: Pubkey) -> UserTokens {
+        UserTokens::associated(
+            owner,
+            (&self.token_a_mint, &self.token_a_program),
+            (&self.token_b_mint, &self.token_b_program),
+        )
+    }
+
+    /// Tick array holding `tick_index`.
+    pub fn tick_array(&self, tick_index: i32) -> Pubkey {
+        let start_tick_index = TickArray::start_index_for(tick_index, self.tick_spacing);
+        pda::tick_array_address(&self.cl_pool, start_tick_index).0
+    }
+}
+
+/// Addresses of a CL position and the tick arrays holding its bounds.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PositionKeys {
+    pub position: Pubkey,
+    pub tick_lower: i32,
+    pub tick_upper: i32,
+    pub tick_array_lower: Pubkey,
+    pub tick_array_upper: Pubkey,
+}
+
+impl PositionKeys {
+    pub fn new(keys: &ClPoolKeys, owner: &Pubkey, tick_lower: i32, tick_upper: i32) -> Self {
+        Self {
+            position: pda::position_address(&keys.cl_pool, owner, tick_lower, tick_upper).0,
+            tick_lower,
+            tick_upper,
+            tick_array_lower: keys.tick_array(tick_lower),
+            tick_array_upper: keys.tick_array(tick_upper),
+        }
+    }
+}
+
+/// Creates the pool. `config` is the current `AmmConfig`, as for
+/// `initialize_pool`.
+pub fn initialize_cl_pool(
+    keys: &ClPoolKeys,
+    config: &AmmConfig,
+    authority: Pubkey,
+    args: args::InitializeClPool,
+) -> Instruction {
+    let accounts = with_event_cpi!(accounts::InitializeClPool {
+        authority,
+        amm_config: pda::amm_config_address().0,
+        pool_record: pda::pool_record_address(config.pool_count).0,
+        fee_receiver: config.fee_receiver,
+        cl_pool: keys.cl_pool,
+        token_a_mint: keys.token_a_mint,
+        token_b_mint: keys.token_b_mint,
+        token_a_vault: keys.token_a_vault,
+        token_b_vault: keys.token_b_vault,
+        token_a_program: keys.token_a_program,
+        token_b_program: keys.token_b_program,
+        associated_token_program: associated_token::ID,
+        system_program: system_program::ID,
+    });
+    build(accounts, args)
+}
+
+pub fn initialize_tick_array(
+    keys: &ClPoolKeys,
+    payer: Pubkey,
+    args: args::InitializeTickArray,
+) -> Instruction {
+    let accounts = accounts::InitializeTickArray {
+        payer,
+        cl_pool: keys.cl_pool,
+        tick_array: pda::tick_array_address(&keys.cl_pool, args.start_tick_index).0,
+        system_program: system_program::ID,
+    };
+    build(accounts, args)
+}
+
+pub fn open_position(keys: &ClPoolKeys, owner: Pubkey, args: args::OpenPosition) -> Instruction {
+    let accounts = with_event_cpi!(accounts::OpenPosition {
+        owner,
+        cl_pool: keys.cl_pool,
+        position: PositionKeys::new(keys, &owner, args.tick_lower, args.tick_upper).position,
+        system_program: system_program::ID,
+    });
+    build(accounts, args)
+}
+
+fn modify_liquidity_accounts(
+    keys: &ClPoolKeys,
+    owner: &UserTokens,
+    position: &PositionKeys,
+) -> accounts::ModifyLiquidity {
+    with_event_cpi!(accounts::ModifyLiquidity {
+        owner: owner.owner,
+        cl_pool: keys.cl_pool,
+        position: position.position,
+        tick_array_lower: position.tick_array_lower,
+        tick_array_upper: position.tick_array_upper,
+        token_a_vault: keys.token_a_vault,
+        token_b_vault: keys.token_b_vault,
+        user_token_a: owner.token_a,
+        user_token_b: owner.token_b,
+        token_a_mint: keys.token_a_mint,
+        token_b_mint: keys.token_b_mint,
+        token_a_program: keys.token_a_program,
+        token_b_program: keys.token_b_program,
+    })
+}
+
+pub fn increase_liquidity(
+    keys: &ClPoolKeys,
+    owner: &UserTokens,
+    position: &PositionKeys,
+    args: args::IncreaseLiquidity,
+) -> Instruction {
+    build(modify_liquidity_accounts(keys, owner, position), args)
+}
+
+pub fn decrease_liquidity(
+    keys: &ClPoolKeys,
+    owner: &UserTokens,
+    position: &PositionKeys,
+    args: args::DecreaseLiquidity,
+) -> Instruction {
+    build(modify_liquidity_accounts(keys, owner, position), args)
+}
+
+pub fn collect_position_fees(
+    keys: &ClPoolKeys,
+    owner: &UserTokens,
+    position: &PositionKeys,
+) -> Instruction {
+    build(
+        modify_liquidity_accounts(keys, owner, position),
+        args::CollectPositionFees {},
+    )
+}
+
+pub fn close_position(owner: Pubkey, position: &PositionKeys) -> Instruction {
+    let accounts = with_event_cpi!(accounts::ClosePosition {
+        owner,
+        position: position.position,
+    });
+    build(accounts, args::ClosePosition {})
+}
+
+/// Swaps through a CL pool. `tick_arrays` start with the one holding the
+/// current tick and follow in the swap direction. `args.tick_array_count`
+/// is taken from `tick_arrays`.
+pub fn cl_swap(
+    keys: &ClPoolKeys,
+    user: &UserTokens,
+    tick_arrays: &[Pubkey],
+    mut args: args::ClSwap,
+) -> Instruction {
+    args.tick_array_count = tick_arrays.len() as u8;
+    let accounts = with_event_cpi!(accounts::ClSwap {
+        user: user.owner,
+        cl_pool: keys.cl_pool,
+        token_a_vault: keys.token_a_vault,
+        token_b_vault: keys.token_b_vault,
+        user_token_a: user.token_a,
+        user_token_b: user.token_b,
+        token_a_mint: keys.token_a_mint,
+        token_b_mint: keys.token_b_mint,
+        token_a_program: keys.token_a_program,
+        token_b_program: keys.token_b_program,
+    });
+    let mut ix = build(accounts, args);
+    ix.accounts.extend(
+        tick_arrays
+            .iter()
+            .map(|tick_array| AccountMeta::new(*tick_array, false)),
+    );
+    ix
+}
+
+pub fn set_cl_transfer_hook_programs(
+    keys: &ClPoolKeys,
+    authority: Pubkey,
+    args: args::SetClTransferHookPrograms,
+) -> Instruction {
+    let accounts = with_event_cpi!(accounts::UpdateClPool {
+        authority,
+        cl_pool: keys.cl_pool,
+    });
+    build(accounts, args)
+}
+
+/// One token of a multi-asset pool.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MultiPoolTokenKeys {
+    pub mint: Pubkey,
+    pub vault: Pubkey,
+    pub token_program: Pubkey,
+}
+
+/// Addresses of a multi-asset pool.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MultiPoolKeys {
+    pub pool: Pubkey,
+    pub lp_mint: Pubkey,
+    pub locked_lp_vault: Pubkey,
+    /// Constituents in the pool's token order, which is ascending by mint.
+    pub tokens: Vec<MultiPoolTokenKeys>,
+}
+
+impl MultiPoolKeys {
+    /// Keys of the pool for the given `(mint, token_program)` pairs, in any order.
+    pub fn new(tokens: &[(Pubkey, Pubkey)]) -> Self {
+        let mut tokens = tokens.to_vec();
+        tokens.sort_by_key(|(mint, _)| *mint);
+        let mints: Vec<Pubkey> = tokens.iter().map(|(mint, _)| *mint).collect();
+
+        let (pool, _) = pda::multi_pool_address(&mints);
+        let (lp_mint, _) = pda::lp_mint_address(&pool);
+
+        Self {
+            pool,
+            lp_mint,
+            locked_lp_vault: pda::vault_address(&pool, &lp_mint, &token::ID),
+            tokens: tokens
+                .iter()
+                .map(|(mint, token_program)| MultiPoolTokenKeys {
+                    mint: *mint,
+                    vault: pda::vault_address(&pool, mint, token_program),
+                    token_program: *token_program,
+                })
+                .collect(),
+        }
+    }
+
+    pub fn mints(&self) -> Vec<Pubkey> {
+        self.tokens.iter().map(|token| token.mint).collect()
+    }
+
+    pub fn token(&self, mint: &Pubkey) -> Option<&MultiPoolTokenKeys> {
+        self.tokens.iter().find(|token| token.mint == *mint)
+    }
+
+    /// Associated LP token account of `owner`.
+    pub fn user_lp_token(&self, owner: &Pubkey) -> Pubkey {
+        pda::vault_address(owner, &self.lp_mint, &token::ID)
+    }
+}
+
+/// Remaining accounts for `tokens` of a multi-asset pool, with `owner`'s
+/// associated token accounts.
+fn constituent_metas(owner: &Pubkey, tokens: &[&MultiPoolTokenKeys]) -> Vec<AccountMeta> {
+    tokens
+        .iter()
+        .flat_map(|token| {
+            [
+                AccountMeta::new(token.vault, false),
+                AccountMeta::new_readonly(token.mint, false),
+                AccountMeta::new(
+                    pda::vault_address(owner, &token.mint, &token.token_program),
+                    false,
+                ),
+            ]
+        })
+        .collect()
+}
+
+/// Creates the pool and a vault for every token. `args.mints` must be
+/// `keys.mints()`, and `config` is the current `AmmConfig`, as for
+/// `initialize_pool`.
+pub fn initialize_multi_pool(
+    keys: &MultiPoolKeys,
+    config: &AmmConfig,
+    authority: Pubkey,
+    args: args::InitializeMultiPool,
+) -> Instruction {
+    let accounts = with_event_cpi!(accounts::InitializeMultiPool {
+        authority,
+        amm_config: pda::amm_config_address().0,
+        pool_record: pda::pool_record_address(config.pool_count).0,
+        fee_receiver: config.fee_receiver,
+        pool: keys.pool,
+        lp_mint: keys.lp_mint,
+        locked_lp_vault: keys.locked_lp_vault,
+        token_program: token::ID,
+        token_2022_program: anchor_spl::token_2022::ID,
+        associated_token_program: associated_token::ID,
+        system_program: system_program::ID,
+    });
+    let mut ix = build(accounts, args);
+    for token in &keys.tokens {
+        ix.accounts.extend([
+            AccountMeta::new_readonly(token.mint, false),
+            AccountMeta::new(token.vault, false),
+        ]);
+    }
+    ix
+}
+
+fn add_multi_liquidity_accounts(
+    keys: &MultiPoolKeys,
+    owner: Pubkey,
+) -> accounts::AddMultiLiquidity {
+    with_event_cpi!(accounts::AddMultiLiquidity {
+        user: owner,
+        pool: keys.pool,
+        lp_mint: keys.lp_mint,
+        user_lp_token: keys.user_lp_token(&owner),
+        locked_lp_vault: keys.locked_lp_vault,
+        token_program: token::ID,
+        token_2022_program: anchor_spl::token_2022::ID,
+    })
+}
+
+fn remove_multi_liquidity_accounts(
+    keys: &MultiPoolKeys,
+    owner: Pubkey,
+) -> accounts::RemoveMultiLiquidity {
+    with_event_cpi!(accounts::RemoveMultiLiquidity {
+        user: owner,
+        pool: keys.pool,
+        lp_mint: keys.lp_mint,
+        user_lp_token: keys.user_lp_token(&owner),
+        token_program: token::ID,
+        token_2022_program: anchor_spl::token_2022::ID,
+    })
+}
+
+/// Deposits every token from the owner's associated token accounts.
+pub fn add_multi_liquidity(
+    keys: &MultiPoolKeys,
+    owner: Pubkey,
+    args: args::AddMultiLiquidity,
+) -> Instruction {
+    let mut ix = build(add_multi_liquidity_accounts(keys, owner), args);
+    let tokens: Vec<_> = keys.tokens.iter().collect();
+    ix.accounts.extend(constituent_metas(&owner, &tokens));
+    ix
+}
+
+/// Withdraws every token into the owner's associated token accounts.
+pub fn remove_multi_liquidity(
+    keys: &MultiPoolKeys,
+    owner: Pubkey,
+    args: args::RemoveMultiLiquidity,
+) -> Instruction {
+    let mut ix = build(remove_multi_liquidity_accounts(keys, owner), args);
+    let tokens: Vec<_> = keys.tokens.iter().collect();
+    ix.accounts.extend(constituent_metas(&owner, &tokens));
+    ix
+}
+
+/// Deposits only `token` from the owner's associated token account.
+pub fn multi_zap_in(
+    keys: &MultiPoolKeys,
+    owner: Pubkey,
+    token: &MultiPoolTokenKeys,
+    args: args::MultiZapIn,
+) -> Instruction {
+    let mut ix = build(add_multi_liquidity_accounts(keys, owner), args);
+    ix.accounts.extend(constituent_metas(&owner, &[token]));
+    ix
+}
+
+/// Withdraws only `token` into the owner's associated token account.
+pub fn multi_zap_out(
+    keys: &MultiPoolKeys,
+    owner: Pubkey,
+    token: &MultiPoolTokenKeys,
+    args: args::MultiZapOut,
+) -> Instruction {
+    let mut ix = build(remove_multi_liquidity_accounts(keys, owner), args);
+    ix.accounts.extend(constituent_metas(&owner, &[token]));
+    ix
+}
+
+/// Swaps between the owner's associated token accounts for `token_in` and
+/// `token_out`.
+pub fn multi_swap(
+    keys: &MultiPoolKeys,
+    owner: Pubkey,
+    token_in: &MultiPoolTokenKeys,
+    token_out: &MultiPoolTokenKeys,
+    args: args::MultiSwap,
+) -> Instruction {
+    let accounts = with_event_cpi!(accounts::MultiSwap {
+        user: owner,
+        pool: keys.pool,
+        vault_in: token_in.vault,
+        vault_out: token_out.vault,
+        user_source: pda::vault_address(&owner, &token_in.mint, &token_in.token_program),
+        user_destination: pda::vault_address(&owner, &token_out.mint, &token_out.token_program),
+        mint_in: token_in.mint,
+        mint_out: token_out.mint,
+        token_in_program: token_in.token_program,
+        token_out_program: token_out.token_program,
+    });
+    build(accounts, args)
+}
+
+pub fn set_multi_pool_paused(
+    keys: &MultiPoolKeys,
+    authority: Pubkey,
+    args: args::SetMultiPoolPaused,
+) -> Instruction {
+    let accounts = with_event_cpi!(accounts::UpdateMultiPool {
+        authority,
+        pool: keys.pool,
+    });
+    build(accounts, args)
+}
+
+/// Addresses of an LP farm.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FarmKeys {
+    pub farm: Pubkey,
+    pub pool: Pubkey,
+    pub lp_mint: Pubkey,
+    pub reward_mint: Pubkey,
+    pub stake_vault: Pubkey,
+    pub reward_vault: Pubkey,
+    pub lp_token_program: Pubkey,
+    pub reward_token_program: Pubkey,
+}
+
+impl FarmKeys {
+    /// Keys of the farm paying `reward_mint` to stakers of `pool`'s LP tokens.
+    pub fn new(pool: &PoolKeys, reward_mint: Pubkey, reward_token_program: Pubkey) -> Self {
+        let (farm, _) = pda::farm_address(&pool.pool, &reward_mint);
+
+        Self {
+            farm,
+            pool: pool.pool,
+            lp_mint: pool.lp_mint,
+            reward_mint,
+            stake_vault: pda::vault_address(&farm, &pool.lp_mint, &pool.lp_token_program),
+            reward_vault: pda::vault_address(&farm, &reward_mint, &reward_token_program),
+            lp_token_program: pool.lp_token_program,
+            reward_token_program,
+        }
+    }
+
+    pub fn position(&self, owner: &Pubkey) -> Pubkey {
+        pda::farm_position_address(&self.farm, owner).0
+    }
+}
+
+pub fn initialize_farm(
+    keys: &FarmKeys,
+    authority: Pubkey,
+    args: args::InitializeFarm,
+) -> Instruction {
+    let accounts = with_event_cpi!(accounts::InitializeFarm {
+        authority,
+        pool: keys.pool,
+        lp_mint: keys.lp_mint,
+        reward_mint: keys.reward_mint,
+        farm: keys.farm,
+        stake_vault: keys.stake_vault,
+        reward_vault: keys.reward_vault,
+        lp_token_program: keys.lp_token_program,
+        reward_token_program: keys.reward_token_program,
+        associated_token_program: associated_token::ID,
+        system_program: system_program::ID,
+    });
+    build(accounts, args)
+}
+
+/// Funds the farm from `funder`'s associated reward token account.
+pub fn fund_farm(keys: &FarmKeys, funder: Pubkey, args: args::FundFarm) -> Instruction {
+    let accounts = with_event_cpi!(accounts::FundFarm {
+        funder,
+        farm: keys.farm,
+        reward_mint: keys.reward_mint,
+        reward_vault: keys.reward_vault,
+        funder_reward_token: pda::vault_address(
+            &funder,
+            &keys.reward_mint,
+            &keys.reward_token_program,
+        ),
+        reward_token_program: keys.reward_token_program,
+    });
+    build(accounts, args)
+}
+
+pub fn set_farm_reward_rate(
+    keys: &FarmKeys,
+    authority: Pubkey,
+    args: args::SetFarmRewardRate,
+) -> Instruction {
+    let accounts = with_event_cpi!(accounts::UpdateFarm {
+        authority,
+        pool: keys.pool,
+        farm: keys.farm,
+    });
+    build(accounts, args)
+}
+
+pub fn open_farm_position(keys: &FarmKeys, owner: Pubkey) -> Instruction {
+    let accounts = with_event_cpi!(accounts::OpenFarmPosition {
+        owner,
+        farm: keys.farm,
+        position: keys.position(&owner),
+        system_program: system_program::ID,
+    });
+    build(accounts, args::OpenFarmPosition {})
+}
+
+fn modify_stake_accounts(keys: &FarmKeys, owner: Pubkey) -> accounts::ModifyStake {
+    with_event_cpi!(accounts::ModifyStake {
+        owner,
+        farm: keys.farm,
+        position: keys.position(&owner),
+        lp_mint: keys.lp_mint,
+        stake_vault: keys.stake_vault,
+        user_lp_token: pda::vault_address(&owner, &keys.lp_mint, &keys.lp_token_program),
+        lp_token_program: keys.lp_token_program,
+    })
+}
+
+/// Stakes from the owner's associated LP token account.
+pub fn stake(keys: &FarmKeys, owner: Pubkey, args: args::Stake) -> Instruction {
+    build(modify_stake_accounts(keys, owner), args)
+}
+
+/// Unstakes into the owner's associated LP token account.
+pub fn unstake(keys: &FarmKeys, owner: Pubkey, args: args::Unstake) -> Instruction {
+    build(modify_stake_accounts(keys, owner), args)
+}
+
+/// Claims into the owner's associated reward token account.
+pub fn claim_farm_rewards(keys: &FarmKeys, owner: Pubkey) -> Instruction {
+    let accounts = with_event_cpi!(accounts::ClaimFarmRewards {
+        owner,
+        farm: keys.farm,
+        position: keys.position(&owner),
+        reward_mint: keys.reward_mint,
+        reward_vault: keys.reward_vault,
+        user_reward_token: pda::vault_address(
+            &owner,
+            &keys.reward_mint,
+            &keys.reward_token_program,
+        ),
+        reward_token_program: keys.reward_token_program,
+    });
+    build(accounts, args::ClaimFarmRewards {})
+}
+
+pub fn close_farm_position(keys: &FarmKeys, owner: Pubkey) -> Instruction {
+    let accounts = with_event_cpi!(accounts::CloseFarmPosition {
+        owner,
+        position: keys.position(&owner),
+    });
+    build(accounts, args::CloseFarmPosition {})
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_lang::{AnchorDeserialize, Discriminator};
+
+    fn keys() -> PoolKeys {
+        let mut mints = [Pubkey::new_unique(), Pubkey::new_unique()];
+        mints.sort();
+        PoolKeys::new(
+            mints[0],
+            mints[1],
+            token::ID,
+            anchor_spl::token_2022::ID,
+            30,
+        )
+    }
+
+    #[test]
+    fn swap_encodes_arguments_and_accounts() {
+        let keys = keys();
+        let user = keys.user_tokens(Pubkey::new_unique());
+        let ix = swap(
+            &keys,
+            &user,
+            args::Swap {
+                amount_in: 1_000,
+                min_amount_out: 990,
+                a_to_b: true,
+                deadline: Some(1_700_000_000),
+            },
+        );
+
+        assert_eq!(ix.program_id, amm::ID);
+        let (discriminator, data) = ix.data.split_at(args::Swap::DISCRIMINATOR.len());
+        assert_eq!(discriminator, args::Swap::DISCRIMINATOR);
+        let decoded = args::Swap::try_from_slice(data).unwrap();
+        assert_eq!(decoded.amount_in, 1_000);
+        assert_eq!(decoded.deadline, Some(1_700_000_000));
+
+        assert_eq!(ix.accounts[0], AccountMeta::new(user.owner, true));
+        assert_eq!(ix.accounts[1], AccountMeta::new(keys.pool, false));
+        assert!(ix
+            .accounts
+            .contains(&AccountMeta::new(keys.token_b_vault, false)));
+    }
+
+    #[test]
+    fn pool_keys_match_initialize_pool_seeds() {
+        let keys = keys();
+        let config = AmmConfig {
+            admin: Pubkey::new_unique(),
+            pending_admin: None,
+            fee_tiers: vec![5, 30, 100],
+            pool_creation_fee: 0,
+            fee_receiver: Pubkey::new_unique(),
+            pool_count: 7,
+            bump: 0,
+        };
+        let ix = initialize_pool(
+            &keys,
+            &config,
+            Pubkey::new_unique(),
+            args::InitializePool {
+                fee_rate: 30,
+                protocol_fee_rate: 0,
+                curve_type: amm::states::CurveType::ConstantProduct,
+                amp: 0,
+                weight_a: 0,
+            },
+        );
+
+        let (pool, _) = Pubkey::find_program_address(
+            &[
+                b"pool",
+                keys.token_a_mint.as_ref(),
+                keys.token_b_mint.as_ref(),
+                &30u16.to_le_bytes(),
+            ],
+            &amm::ID,
+        );
+        assert_eq!(keys.pool, pool);
+        assert_eq!(ix.accounts[4].pubkey, pool);
+        assert_eq!(ix.accounts[2].pubkey, pda::pool_record_address(7).0);
+        assert_eq!(ix.accounts[3], AccountMeta::new(config.fee_receiver, false));
+        assert_ne!(keys.token_a_vault, keys.token_b_vault);
+    }
+
+    #[test]
+    fn route_swap_appends_hops_in_swap_direction() {
+        let first = keys();
+        let second = keys();
+        let hops = [
+            RouteHop {
+                pool: first,
+                a_to_b: true,
+            },
+            RouteHop {
+                pool: second,
+                a_to_b: false,
+            },
+        ];
+        let ix = route_swap(
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            &hops,
+            args::RouteSwap {
+                amount_in: 1_000,
+                min_amount_out: 0,
+                hop_count: 0,
+                deadline: None,
+            },
+        );
+
+        let decoded = args::RouteSwap::try_from_slice(&ix.data[8..]).unwrap();
+        assert_eq!(decoded.hop_count, 2);
+
+        let hop_accounts =
+            &ix.accounts[ix.accounts.len() - 2 * amm::instructions::ACCOUNTS_PER_HOP..];
+        assert_eq!(hop_accounts[0].pubkey, first.pool);
+        assert_eq!(hop_accounts[2].pubkey, first.token_a_vault);
+        assert_eq!(hop_accounts[6].pubkey, second.pool);
+        assert_eq!(hop_accounts[8].pubkey, second.token_b_vault);
+        assert_eq!(hop_accounts[11].pubkey, second.token_a_mint);
+    }
+
+    #[test]
+    fn multi_pool_keys_sort_tokens_and_list_constituents_in_order() {
+        let mut mints: Vec<Pubkey> = (0..3).map(|_| Pubkey::new_unique()).collect();
+        let keys = MultiPoolKeys::new(&[
+            (mints[2], token::ID),
+            (mints[0], anchor_spl::token_2022::ID),
+            (mints[1], token::ID),
+        ]);
+        mints.sort();
+        assert_eq!(keys.mints(), mints);
+        assert_eq!(keys.pool, pda::multi_pool_address(&mints).0);
+
+        let owner = Pubkey::new_unique();
+        let ix = add_multi_liquidity(
+            &keys,
+            owner,
+            args::AddMultiLiquidity {
+                max_amounts: vec![1_000; 3],
+                min_lp_tokens: 0,
+                deadline: None,
+            },
+        );
+        let per_token = amm::instructions::ACCOUNTS_PER_CONSTITUENT;
+        let constituents = &ix.accounts[ix.accounts.len() - 3 * per_token..];
+        for (token, accounts) in keys.tokens.iter().zip(constituents.chunks(per_token)) {
+            assert_eq!(accounts[0], AccountMeta::new(token.vault, false));
+            assert_eq!(accounts[1].pubkey, token.mint);
+            assert_eq!(
+                accounts[2].pubkey,
+                pda::vault_address(&owner, &token.mint, &token.token_program)
+            );
+        }
+    }
+
+    #[test]
+    fn cl_pool_keys_match_initialize_cl_pool_seeds() {
+        let mut mints = [Pubkey::new_unique(), Pubkey::new_unique()];
+        mints.sort();
+        let keys = ClPoolKeys::new(mints[0], mints[1], token::ID, token::ID, 8, 30);
+        let config = AmmConfig {
+            admin: Pubkey::new_unique(),
+            pending_admin: None,
+            fee_tiers: vec![5, 30, 100],
+            pool_creation_fee: 0,
+            fee_receiver: Pubkey::new_unique(),
+            pool_count: 3,
+            bump: 0,
+        };
+        let ix = initialize_cl_pool(
+            &keys,
+            &config,
+            Pubkey::new_unique(),
+            args::InitializeClPool {
+                tick_spacing: 8,
+                fee_rate: 30,
+                initial_sqrt_price_x64: 1 << 64,
+            },
+        );
+
+        // Keyed by fee tier, so another tick spacing at the same tier is the
+        // same pool
+        assert_eq!(
+            keys.cl_pool,
+            ClPoolKeys::new(mints[0], mints[1], token::ID, token::ID, 16, 30).cl_pool
+        );
+        assert_ne!(
+            keys.cl_pool,
+            ClPoolKeys::new(mints[0], mints[1], token::ID, token::ID, 8, 100).cl_pool
+        );
+        assert_eq!(ix.accounts[2].pubkey, pda::pool_record_address(3).0);
+        assert_eq!(ix.accounts[4], AccountMeta::new(keys.cl_pool, false));
+    }
+
+    #[test]
+    fn position_bounds_in_one_array_share_it() {
+        let keys = ClPoolKeys::new(
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            token::ID,
+            token::ID,
+            8,
+            30,
+        );
+        let owner = Pubkey::new_unique();
+
+        let narrow = PositionKeys::new(&keys, &owner, 8, 64);
+        assert_eq!(narrow.tick_array_lower, narrow.tick_array_upper);
+
+        let wide = PositionKeys::new(&keys, &owner, -16, 64);
+        assert_ne!(wide.tick_array_lower, wide.tick_array_upper);
+    }
+}
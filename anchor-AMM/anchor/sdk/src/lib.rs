@@ -0,0 +1,21 @@
+//! Off-chain client for the `amm` program.
+//!
+//! Quotes are priced with the program's own `math` module against a
+//! snapshot of the pool, so they match what the instruction will do as long
+//! as the snapshot is current. Instruction builders derive every PDA and
+//! fill the program's generated account and argument types.
+
+pub mod instruction;
+pub mod pda;
+pub mod quote;
+
+pub use amm::{self, ID};
+
+pub use instruction::{
+    ClPoolKeys, FarmKeys, MultiPoolKeys, MultiPoolTokenKeys, PoolKeys, PositionKeys, RouteHop,
+    UserTokens,
+};
+pub use quote::{
+    quote_add_liquidity, quote_remove_liquidity, quote_swap, quote_swap_exact_out,
+    AddLiquidityQuote, PoolSnapshot, RemoveLiquidityQuote, SwapQuote,
+};
@@ -0,0 +1,117 @@
+//! Addresses the `amm` program derives from seeds.
+
+use anchor_lang::{prelude::Pubkey, solana_program::bpf_loader_upgradeable};
+use anchor_spl::associated_token::get_associated_token_address_with_program_id;
+
+/// Program-wide config every `initialize_pool` checks against.
+pub fn amm_config_address() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"amm_config"], &amm::ID)
+}
+
+/// Entry `index` of the pool registry, for `index` below the config's
+/// `pool_count`.
+pub fn pool_record_address(index: u64) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"pool_record", &index.to_le_bytes()], &amm::ID)
+}
+
+/// Program data account of the deployed program, which names its upgrade
+/// authority.
+pub fn program_data_address() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[amm::ID.as_ref()], &bpf_loader_upgradeable::ID)
+}
+
+/// Pool for the pair at `fee_tier`. `token_a_mint` must sort before
+/// `token_b_mint`.
+pub fn pool_address(token_a_mint: &Pubkey, token_b_mint: &Pubkey, fee_tier: u16) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            b"pool",
+            token_a_mint.as_ref(),
+            token_b_mint.as_ref(),
+            &fee_tier.to_le_bytes(),
+        ],
+        &amm::ID,
+    )
+}
+
+pub fn lp_mint_address(pool: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"lp_mint", pool.as_ref()], &amm::ID)
+}
+
+pub fn oracle_address(pool: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"oracle", pool.as_ref()], &amm::ID)
+}
+
+/// Vault holding `mint` for `pool`, which is the pool's associated token
+/// account. Also used for CL pools and for the locked LP vault.
+pub fn vault_address(pool: &Pubkey, mint: &Pubkey, token_program: &Pubkey) -> Pubkey {
+    get_associated_token_address_with_program_id(pool, mint, token_program)
+}
+
+/// Multi-asset pool holding `mints`, which must be sorted ascending.
+pub fn multi_pool_address(mints: &[Pubkey]) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"multi_pool", &amm::states::MultiPool::mints_hash(mints)],
+        &amm::ID,
+    )
+}
+
+/// Concentrated liquidity pool for the pair at `fee_tier`. `token_a_mint`
+/// must sort before `token_b_mint`.
+pub fn cl_pool_address(
+    token_a_mint: &Pubkey,
+    token_b_mint: &Pubkey,
+    fee_tier: u16,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            b"cl_pool",
+            token_a_mint.as_ref(),
+            token_b_mint.as_ref(),
+            &fee_tier.to_le_bytes(),
+        ],
+        &amm::ID,
+    )
+}
+
+pub fn tick_array_address(cl_pool: &Pubkey, start_tick_index: i32) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            b"tick_array",
+            cl_pool.as_ref(),
+            &start_tick_index.to_le_bytes(),
+        ],
+        &amm::ID,
+    )
+}
+
+pub fn position_address(
+    cl_pool: &Pubkey,
+    owner: &Pubkey,
+    tick_lower: i32,
+    tick_upper: i32,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            b"position",
+            cl_pool.as_ref(),
+            owner.as_ref(),
+            &tick_lower.to_le_bytes(),
+            &tick_upper.to_le_bytes(),
+        ],
+        &amm::ID,
+    )
+}
+
+pub fn farm_address(pool: &Pubkey, reward_mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"farm", pool.as_ref(), reward_mint.as_ref()], &amm::ID)
+}
+
+pub fn farm_position_address(farm: &Pubkey, owner: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"farm_position", farm.as_ref(), owner.as_ref()], &amm::ID)
+}
+
+/// Signer of the self-CPI that carries events under the `event-cpi` feature.
+pub fn event_authority_address() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"__event_authority"], &amm::ID)
+}
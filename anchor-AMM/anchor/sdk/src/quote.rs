@@ -0,0 +1,465 @@
+//! Quotes for the constant-product and StableSwap pool instructions.
+//!
+//! Each quote follows the same steps as its instruction handler: reserves
+//! exclude uncollected protocol fees, curve math runs on what the vaults
+//! actually receive, and Token-2022 transfer fees are applied on the way in
+//! and out.
+
+use amm::{
+    errors::AmmError,
+    math::{self, SwapResult, MINIMUM_LIQUIDITY},
+    states::Pool,
+};
+use anchor_lang::prelude::*;
+use anchor_spl::token_2022::{
+    self,
+    spl_token_2022::{
+        extension::{
+            transfer_fee::TransferFeeConfig, BaseStateWithExtensions, StateWithExtensions,
+        },
+        state::Mint as MintState,
+    },
+};
+
+/// Pool state a quote is priced against.
+#[derive(Clone)]
+pub struct PoolSnapshot {
+    pub pool: Pool,
+    /// Balance of `pool.token_a_vault`.
+    pub vault_a_amount: u64,
+    /// Balance of `pool.token_b_vault`.
+    pub vault_b_amount: u64,
+    /// Supply of `pool.lp_mint`.
+    pub lp_supply: u64,
+    /// Token-2022 transfer fee of token A, if its mint has one.
+    pub token_a_transfer_fee: Option<TransferFeeConfig>,
+    /// Token-2022 transfer fee of token B, if its mint has one.
+    pub token_b_transfer_fee: Option<TransferFeeConfig>,
+    /// Epoch the transaction is expected to land in.
+    pub epoch: u64,
+    /// Unix timestamp the transaction is expected to land at.
+    pub unix_timestamp: i64,
+}
+
+impl PoolSnapshot {
+    /// Reserves backing LP shares, as the program computes them.
+    pub fn reserves(&self) -> Result<(u64, u64)> {
+        self.pool.reserves(self.vault_a_amount, self.vault_b_amount)
+    }
+
+    fn transfer_fee(&self, token_a: bool) -> Option<&TransferFeeConfig> {
+        if token_a {
+            self.token_a_transfer_fee.as_ref()
+        } else {
+            self.token_b_transfer_fee.as_ref()
+        }
+    }
+
+    /// Amount that arrives when `amount` of token A or B is transferred.
+    pub fn amount_received(&self, token_a: bool, amount: u64) -> Result<u64> {
+        let Some(config) = self.transfer_fee(token_a) else {
+            return Ok(amount);
+        };
+        let fee = config
+            .calculate_epoch_fee(self.epoch, amount)
+            .ok_or(AmmError::MathOverflow)?;
+        Ok(amount - fee)
+    }
+
+    /// Amount of token A or B that must be transferred for `amount` to arrive.
+    pub fn amount_to_send(&self, token_a: bool, amount: u64) -> Result<u64> {
+        let Some(config) = self.transfer_fee(token_a) else {
+            return Ok(amount);
+        };
+        if amount == 0 {
+            return Ok(0);
+        }
+        let fee = config
+            .calculate_inverse_epoch_fee(self.epoch, amount)
+            .ok_or(AmmError::MathOverflow)?;
+        Ok(amount.checked_add(fee).ok_or(AmmError::MathOverflow)?)
+    }
+}
+
+/// Transfer fee configuration of a mint account owned by `owner`, if it is
+/// a Token-2022 mint with the TransferFeeConfig extension.
+pub fn transfer_fee_config(owner: &Pubkey, data: &[u8]) -> Result<Option<TransferFeeConfig>> {
+    if *owner != token_2022::ID {
+        return Ok(None);
+    }
+
+    let mint_state = StateWithExtensions::<MintState>::unpack(data)?;
+    Ok(mint_state
+        .get_extension::<TransferFeeConfig>()
+        .ok()
+        .copied())
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SwapQuote {
+    /// Tokens leaving the user's source account.
+    pub amount_in: u64,
+    /// Tokens arriving in the user's destination account.
+    pub amount_out: u64,
+    /// Curve result on the amounts the vaults send and receive.
+    pub swap: SwapResult,
+}
+
+/// Quote for `swap` with `amount_in`.
+pub fn quote_swap(snapshot: &PoolSnapshot, amount_in: u64, a_to_b: bool) -> Result<SwapQuote> {
+    let (reserve_in, reserve_out) = reserves_in_out(snapshot, a_to_b)?;
+    let pool = &snapshot.pool;
+
+    let swap = math::swap_exact_in(
+        pool.swap_curve(snapshot.unix_timestamp, a_to_b),
+        snapshot.amount_received(a_to_b, amount_in)?,
+        reserve_in,
+        reserve_out,
+        pool.swap_fee_rate(snapshot.unix_timestamp),
+        pool.protocol_fee_rate,
+    )?;
+
+    Ok(SwapQuote {
+        amount_in,
+        amount_out: snapshot.amount_received(!a_to_b, swap.amount_out)?,
+        swap,
+    })
+}
+
+/// Quote for `swap_exact_out` delivering exactly `amount_out`.
+pub fn quote_swap_exact_out(
+    snapshot: &PoolSnapshot,
+    amount_out: u64,
+    a_to_b: bool,
+) -> Result<SwapQuote> {
+    require!(amount_out > 0, AmmError::InvalidAmount);
+
+    let (reserve_in, reserve_out) = reserves_in_out(snapshot, a_to_b)?;
+    let pool = &snapshot.pool;
+
+    let swap = math::swap_exact_out(
+        pool.swap_curve(snapshot.unix_timestamp, a_to_b),
+        snapshot.amount_to_send(!a_to_b, amount_out)?,
+        reserve_in,
+        reserve_out,
+        pool.swap_fee_rate(snapshot.unix_timestamp),
+        pool.protocol_fee_rate,
+    )?;
+
+    Ok(SwapQuote {
+        amount_in: snapshot.amount_to_send(a_to_b, swap.amount_in)?,
+        amount_out,
+        swap,
+    })
+}
+
+fn reserves_in_out(snapshot: &PoolSnapshot, a_to_b: bool) -> Result<(u64, u64)> {
+    let (reserve_a, reserve_b) = snapshot.reserves()?;
+    Ok(if a_to_b {
+        (reserve_a, reserve_b)
+    } else {
+        (reserve_b, reserve_a)
+    })
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AddLiquidityQuote {
+    /// Token A leaving the user's account.
+    pub amount_a: u64,
+    /// Token B leaving the user's account.
+    pub amount_b: u64,
+    /// Token A arriving in the vault; `min_amount_a` is checked against this.
+    pub deposited_a: u64,
+    /// Token B arriving in the vault; `min_amount_b` is checked against this.
+    pub deposited_b: u64,
+    /// LP tokens minted to the user.
+    pub lp_tokens: u64,
+}
+
+/// Quote for `add_liquidity` with the desired amounts of each token.
+pub fn quote_add_liquidity(
+    snapshot: &PoolSnapshot,
+    amount_a_desired: u64,
+    amount_b_desired: u64,
+) -> Result<AddLiquidityQuote> {
+    require!(
+        amount_a_desired > 0 && amount_b_desired > 0,
+        AmmError::InvalidAmount
+    );
+
+    let (reserve_a, reserve_b) = snapshot.reserves()?;
+    let desired_a = snapshot.amount_received(true, amount_a_desired)?;
+    let desired_b = snapshot.amount_received(false, amount_b_desired)?;
+
+    let lp_supply = snapshot.lp_supply;
+    let is_first_deposit = lp_supply == 0;
+
+    let (deposited_a, deposited_b) = if is_first_deposit {
+        (desired_a, desired_b)
+    } else {
+        math::optimal_deposit(desired_a, desired_b, reserve_a, reserve_b)?
+    };
+
+    let amount_a = if deposited_a == desired_a {
+        amount_a_desired
+    } else {
+        snapshot.amount_to_send(true, deposited_a)?
+    };
+    let amount_b = if deposited_b == desired_b {
+        amount_b_desired
+    } else {
+        snapshot.amount_to_send(false, deposited_b)?
+    };
+
+    let lp_tokens = if is_first_deposit {
+        snapshot
+            .pool
+            .curve(snapshot.unix_timestamp)
+            .initial_lp_tokens(deposited_a, deposited_b)?
+            .checked_sub(MINIMUM_LIQUIDITY)
+            .ok_or(AmmError::InsufficientLiquidity)?
+    } else {
+        math::lp_tokens_for_deposit(deposited_a, deposited_b, reserve_a, reserve_b, lp_supply)?
+    };
+    require!(lp_tokens > 0, AmmError::InvalidAmount);
+
+    Ok(AddLiquidityQuote {
+        amount_a,
+        amount_b,
+        deposited_a,
+        deposited_b,
+        lp_tokens,
+    })
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RemoveLiquidityQuote {
+    /// Token A leaving the vault.
+    pub amount_a: u64,
+    /// Token B leaving the vault.
+    pub amount_b: u64,
+    /// Token A arriving in the user's account; `min_amount_a` is checked against this.
+    pub received_a: u64,
+    /// Token B arriving in the user's account; `min_amount_b` is checked against this.
+    pub received_b: u64,
+}
+
+/// Quote for `remove_liquidity` burning `lp_tokens`.
+pub fn quote_remove_liquidity(
+    snapshot: &PoolSnapshot,
+    lp_tokens: u64,
+) -> Result<RemoveLiquidityQuote> {
+    require!(lp_tokens > 0, AmmError::InvalidAmount);
+
+    let (reserve_a, reserve_b) = snapshot.reserves()?;
+    let lp_supply = snapshot.lp_supply;
+    require!(
+        lp_supply.saturating_sub(lp_tokens) >= MINIMUM_LIQUIDITY,
+        AmmError::InsufficientLiquidity
+    );
+
+    let (amount_a, amount_b) = math::withdraw_amounts(lp_tokens, reserve_a, reserve_b, lp_supply)?;
+    require!(amount_a > 0 && amount_b > 0, AmmError::InvalidAmount);
+
+    Ok(RemoveLiquidityQuote {
+        amount_a,
+        amount_b,
+        received_a: snapshot.amount_received(true, amount_a)?,
+        received_b: snapshot.amount_received(false, amount_b)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use amm::states::{CurveType, DynamicFeeConfig};
+    use anchor_spl::token_2022::spl_token_2022::extension::transfer_fee::TransferFee;
+
+    fn pool(fee_rate: u16, protocol_fee_rate: u16) -> Pool {
+        Pool {
+            authority: Pubkey::default(),
+            pending_authority: None,
+            token_a_mint: Pubkey::default(),
+            token_b_mint: Pubkey::default(),
+            token_a_vault: Pubkey::default(),
+            token_b_vault: Pubkey::default(),
+            lp_mint: Pubkey::default(),
+            fee_rate,
+            fee_tier: fee_rate,
+            curve_type: CurveType::ConstantProduct,
+            amp_initial: 0,
+            amp_target: 0,
+            amp_ramp_start_ts: 0,
+            amp_ramp_stop_ts: 0,
+            weight_a: 5000,
+            weight_b: 5000,
+            protocol_fee_rate,
+            protocol_fees_a: 0,
+            protocol_fees_b: 0,
+            flash_loan_active: false,
+            flash_loan_amount_a: 0,
+            flash_loan_amount_b: 0,
+            paused: false,
+            transfer_hook_programs: Vec::new(),
+            dynamic_fee: None,
+            volatility: 0,
+            volatility_updated_ts: 0,
+            bump: 0,
+            lp_mint_bump: 0,
+        }
+    }
+
+    fn snapshot(vault_a_amount: u64, vault_b_amount: u64, lp_supply: u64) -> PoolSnapshot {
+        PoolSnapshot {
+            pool: pool(30, 0),
+            vault_a_amount,
+            vault_b_amount,
+            lp_supply,
+            token_a_transfer_fee: None,
+            token_b_transfer_fee: None,
+            epoch: 0,
+            unix_timestamp: 0,
+        }
+    }
+
+    fn transfer_fee(basis_points: u16) -> TransferFeeConfig {
+        let fee = TransferFee {
+            epoch: 0.into(),
+            maximum_fee: u64::MAX.into(),
+            transfer_fee_basis_points: basis_points.into(),
+        };
+        TransferFeeConfig {
+            older_transfer_fee: fee,
+            newer_transfer_fee: fee,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn swap_matches_constant_product() {
+        let quote =
+            quote_swap(&snapshot(1_000_000_000, 2_000_000_000, 0), 1_000_000, true).unwrap();
+
+        // 0.3% of 1_000_000 is 3_000, and 2e9 * 997_000 / (1e9 + 997_000) rounds down
+        assert_eq!(quote.swap.fee, 3_000);
+        assert_eq!(quote.amount_out, 1_992_013);
+        assert_eq!(quote.swap.amount_out, quote.amount_out);
+    }
+
+    #[test]
+    fn swap_prices_against_reserves_without_protocol_fees() {
+        let mut with_fees = snapshot(1_000_500_000, 2_000_000_000, 0);
+        with_fees.pool.protocol_fees_a = 500_000;
+        let without_fees = snapshot(1_000_000_000, 2_000_000_000, 0);
+
+        assert_eq!(
+            quote_swap(&with_fees, 1_000_000, true).unwrap(),
+            quote_swap(&without_fees, 1_000_000, true).unwrap()
+        );
+    }
+
+    #[test]
+    fn swap_charges_the_decayed_dynamic_fee() {
+        let mut snapshot = snapshot(1_000_000_000, 2_000_000_000, 0);
+        snapshot.pool.dynamic_fee = Some(DynamicFeeConfig {
+            min_fee_rate: 10,
+            max_fee_rate: 200,
+            volatility_multiplier: 1_000,
+            decay_period: 60,
+        });
+        snapshot.pool.volatility = 1_000;
+        snapshot.unix_timestamp = 30;
+
+        // Half the volatility is left, which adds 50 bps to the 10 bps floor
+        assert_eq!(
+            quote_swap(&snapshot, 1_000_000, true).unwrap().swap.fee,
+            6_000
+        );
+    }
+
+    #[test]
+    fn weighted_pool_quotes_at_the_weighted_price() {
+        let mut snapshot = snapshot(1_000_000_000, 1_000_000_000, 0);
+        snapshot.pool.fee_rate = 0;
+        snapshot.pool.curve_type = CurveType::Weighted;
+        snapshot.pool.weight_a = 8000;
+        snapshot.pool.weight_b = 2000;
+
+        // One A is worth four B at equal balances, less a little price impact
+        let a_to_b = quote_swap(&snapshot, 1_000, true).unwrap();
+        let b_to_a = quote_swap(&snapshot, 4_000, false).unwrap();
+        assert_eq!(a_to_b.amount_out, 3_999);
+        assert_eq!(b_to_a.amount_out, 999);
+    }
+
+    #[test]
+    fn exact_out_input_buys_at_least_the_output() {
+        let mut snapshot = snapshot(1_000_000_000, 2_000_000_000, 0);
+        snapshot.token_a_transfer_fee = Some(transfer_fee(100));
+        snapshot.token_b_transfer_fee = Some(transfer_fee(50));
+
+        for a_to_b in [true, false] {
+            let exact_out = quote_swap_exact_out(&snapshot, 1_234_567, a_to_b).unwrap();
+            let exact_in = quote_swap(&snapshot, exact_out.amount_in, a_to_b).unwrap();
+            assert!(exact_in.amount_out >= 1_234_567);
+        }
+    }
+
+    #[test]
+    fn swap_applies_transfer_fees_on_both_sides() {
+        let mut with_fee = snapshot(1_000_000_000, 2_000_000_000, 0);
+        with_fee.token_a_transfer_fee = Some(transfer_fee(100));
+        with_fee.token_b_transfer_fee = Some(transfer_fee(100));
+        let quote = quote_swap(&with_fee, 1_010_000, true).unwrap();
+
+        // The vault nets 1_010_000 - 10_100, and the user nets 99% of the payout
+        let plain = quote_swap(&snapshot(1_000_000_000, 2_000_000_000, 0), 999_900, true).unwrap();
+        assert_eq!(quote.swap, plain.swap);
+        assert_eq!(
+            quote.amount_out,
+            plain.amount_out - plain.amount_out.div_ceil(100)
+        );
+    }
+
+    #[test]
+    fn first_deposit_locks_minimum_liquidity() {
+        let quote = quote_add_liquidity(&snapshot(0, 0, 0), 1_000_000, 4_000_000).unwrap();
+
+        assert_eq!(quote.lp_tokens, 2_000_000 - MINIMUM_LIQUIDITY);
+        assert_eq!((quote.amount_a, quote.amount_b), (1_000_000, 4_000_000));
+    }
+
+    #[test]
+    fn off_ratio_deposit_takes_only_the_ratio() {
+        let quote = quote_add_liquidity(
+            &snapshot(1_000_000, 2_000_000, 1_414_213),
+            500_000,
+            5_000_000,
+        )
+        .unwrap();
+
+        assert_eq!((quote.amount_a, quote.amount_b), (500_000, 1_000_000));
+        assert_eq!(quote.lp_tokens, 707_106);
+    }
+
+    #[test]
+    fn withdrawal_cannot_touch_minimum_liquidity() {
+        let snapshot = snapshot(1_000_000, 2_000_000, 1_414_213);
+
+        assert!(quote_remove_liquidity(&snapshot, 1_414_213 - MINIMUM_LIQUIDITY).is_ok());
+        assert!(quote_remove_liquidity(&snapshot, 1_414_213 - MINIMUM_LIQUIDITY + 1).is_err());
+    }
+
+    #[test]
+    fn withdrawal_reports_what_arrives_after_transfer_fees() {
+        let mut snapshot = snapshot(1_000_000, 2_000_000, 1_414_213);
+        snapshot.token_b_transfer_fee = Some(transfer_fee(100));
+        let quote = quote_remove_liquidity(&snapshot, 707_106).unwrap();
+
+        assert_eq!(quote.received_a, quote.amount_a);
+        assert_eq!(
+            quote.received_b,
+            quote.amount_b - quote.amount_b.div_ceil(100)
+        );
+    }
+}
@@ -0,0 +1,1468 @@
+import * as anchor from '@coral-xyz/anchor'
+import { BN, Program } from '@coral-xyz/anchor'
+import {
+  ASSOCIATED_TOKEN_PROGRAM_ID,
+  TOKEN_2022_PROGRAM_ID,
+  TOKEN_PROGRAM_ID,
+  createAssociatedTokenAccount,
+  createMint,
+  getAccount,
+  getAssociatedTokenAddressSync,
+  mintTo,
+} from '@solana/spl-token'
+import { AccountMeta, Keypair, PublicKey, SYSVAR_INSTRUCTIONS_PUBKEY, SystemProgram } from '@solana/web3.js'
+import { expect } from 'chai'
+import { createHash } from 'crypto'
+import { Amm } from '../target/types/amm'
+
+const DECIMALS = 6
+const INITIAL_BALANCE = 1_000_000_000_000
+const FEE_TIERS = [1, 5, 30, 100]
+
+interface PoolFixture {
+  mintA: PublicKey
+  mintB: PublicKey
+  programA: PublicKey
+  programB: PublicKey
+  pool: PublicKey
+  oracle: PublicKey
+  lpMint: PublicKey
+  vaultA: PublicKey
+  vaultB: PublicKey
+  lockedLpVault: PublicKey
+  userTokenA: PublicKey
+  userTokenB: PublicKey
+  userLpToken: PublicKey
+}
+
+describe('amm', () => {
+  const provider = anchor.AnchorProvider.env()
+  anchor.setProvider(provider)
+  const program = anchor.workspace.Amm as Program<Amm>
+  const payer = (provider.wallet as anchor.Wallet).payer
+  const [ammConfig] = PublicKey.findProgramAddressSync([Buffer.from('amm_config')], program.programId)
+  const feeReceiver = Keypair.generate().publicKey
+
+  before(async () => {
+    // The local validator deploys the program with the wallet as upgrade authority
+    const [programData] = PublicKey.findProgramAddressSync(
+      [program.programId.toBuffer()],
+      new PublicKey('BPFLoaderUpgradeab1e11111111111111111111111'),
+    )
+    await program.methods
+      .initializeAmmConfig(FEE_TIERS, new BN(0), feeReceiver)
+      .accountsPartial({
+        admin: payer.publicKey,
+        ammConfig,
+        programData,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc()
+  })
+
+  async function createFundedMint(
+    tokenProgram: PublicKey,
+    mintKeypair = Keypair.generate(),
+  ): Promise<[PublicKey, PublicKey]> {
+    const mint = await createMint(
+      provider.connection,
+      payer,
+      payer.publicKey,
+      null,
+      DECIMALS,
+      mintKeypair,
+      undefined,
+      tokenProgram,
+    )
+    const account = await createAssociatedTokenAccount(
+      provider.connection,
+      payer,
+      mint,
+      payer.publicKey,
+      undefined,
+      tokenProgram,
+    )
+    await mintTo(provider.connection, payer, mint, account, payer, INITIAL_BALANCE, [], undefined, tokenProgram)
+    return [mint, account]
+  }
+
+  async function initializePool(
+    mintA: PublicKey,
+    mintB: PublicKey,
+    programA: PublicKey,
+    programB: PublicKey,
+    weightA: number | null = null,
+    feeRate = 30,
+  ) {
+    const feeTier = Buffer.alloc(2)
+    feeTier.writeUInt16LE(feeRate)
+    const [pool] = PublicKey.findProgramAddressSync(
+      [Buffer.from('pool'), mintA.toBuffer(), mintB.toBuffer(), feeTier],
+      program.programId,
+    )
+    const [lpMint] = PublicKey.findProgramAddressSync([Buffer.from('lp_mint'), pool.toBuffer()], program.programId)
+    const [oracle] = PublicKey.findProgramAddressSync([Buffer.from('oracle'), pool.toBuffer()], program.programId)
+    const vaultA = getAssociatedTokenAddressSync(mintA, pool, true, programA)
+    const vaultB = getAssociatedTokenAddressSync(mintB, pool, true, programB)
+    const lockedLpVault = getAssociatedTokenAddressSync(lpMint, pool, true, TOKEN_PROGRAM_ID)
+
+    const { poolCount } = await program.account.ammConfig.fetch(ammConfig)
+    const [poolRecord] = PublicKey.findProgramAddressSync(
+      [Buffer.from('pool_record'), poolCount.toArrayLike(Buffer, 'le', 8)],
+      program.programId,
+    )
+
+    const curveType = weightA === null ? { constantProduct: {} } : { weighted: {} }
+    await program.methods
+      .initializePool(feeRate, 0, curveType, new BN(0), weightA ?? 0)
+      .accountsPartial({
+        authority: payer.publicKey,
+        ammConfig,
+        poolRecord,
+        feeReceiver,
+        pool,
+        tokenAMint: mintA,
+        tokenBMint: mintB,
+        lpMint,
+        oracle,
+        lockedLpVault,
+        tokenAVault: vaultA,
+        tokenBVault: vaultB,
+        tokenAProgram: programA,
+        tokenBProgram: programB,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc()
+
+    return { pool, lpMint, oracle, vaultA, vaultB, lockedLpVault }
+  }
+
+  async function createPool(
+    programA: PublicKey,
+    programB: PublicKey,
+    weightA: number | null = null,
+    feeRate = 30,
+  ): Promise<PoolFixture> {
+    // Pools only accept their mints in ascending order
+    const [keypairA, keypairB] = [Keypair.generate(), Keypair.generate()].sort((x, y) =>
+      Buffer.compare(x.publicKey.toBuffer(), y.publicKey.toBuffer()),
+    )
+    const [mintA, userTokenA] = await createFundedMint(programA, keypairA)
+    const [mintB, userTokenB] = await createFundedMint(programB, keypairB)
+
+    const { pool, lpMint, oracle, vaultA, vaultB, lockedLpVault } = await initializePool(
+      mintA,
+      mintB,
+      programA,
+      programB,
+      weightA,
+      feeRate,
+    )
+
+    const userLpToken = await createAssociatedTokenAccount(
+      provider.connection,
+      payer,
+      lpMint,
+      payer.publicKey,
+      undefined,
+      TOKEN_PROGRAM_ID,
+    )
+
+    return {
+      mintA,
+      mintB,
+      programA,
+      programB,
+      pool,
+      oracle,
+      lpMint,
+      vaultA,
+      vaultB,
+      lockedLpVault,
+      userTokenA,
+      userTokenB,
+      userLpToken,
+    }
+  }
+
+  async function balance(account: PublicKey, tokenProgram: PublicKey): Promise<number> {
+    return Number((await getAccount(provider.connection, account, undefined, tokenProgram)).amount)
+  }
+
+  // Events of each named type that `send` emitted
+  async function emitted(names: string[], send: () => Promise<unknown>): Promise<Record<string, any[]>> {
+    const events: Record<string, any[]> = Object.fromEntries(names.map((name) => [name, []]))
+    const listeners = names.map((name) =>
+      program.addEventListener(name as any, (event: any) => events[name].push(event)),
+    )
+    try {
+      await send()
+      await new Promise((resolve) => setTimeout(resolve, 1_000))
+    } finally {
+      await Promise.all(listeners.map((listener) => program.removeEventListener(listener)))
+    }
+    return events
+  }
+
+  function addLiquidityAccounts(f: PoolFixture) {
+    return {
+      user: payer.publicKey,
+      pool: f.pool,
+      oracle: f.oracle,
+      lpMint: f.lpMint,
+      tokenAVault: f.vaultA,
+      tokenBVault: f.vaultB,
+      userTokenA: f.userTokenA,
+      userTokenB: f.userTokenB,
+      userLpToken: f.userLpToken,
+      lockedLpVault: f.lockedLpVault,
+      tokenAMint: f.mintA,
+      tokenBMint: f.mintB,
+      tokenAProgram: f.programA,
+      tokenBProgram: f.programB,
+      tokenProgram: TOKEN_PROGRAM_ID,
+      associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+      systemProgram: SystemProgram.programId,
+    }
+  }
+
+  function addLiquidity(f: PoolFixture, amountA: number, amountB: number) {
+    return program.methods
+      .addLiquidity(new BN(amountA), new BN(amountB), new BN(0), new BN(0), new BN(0), null)
+      .accountsPartial(addLiquidityAccounts(f))
+  }
+
+  function swapAccounts(f: PoolFixture) {
+    return {
+      user: payer.publicKey,
+      pool: f.pool,
+      oracle: f.oracle,
+      tokenAVault: f.vaultA,
+      tokenBVault: f.vaultB,
+      userTokenA: f.userTokenA,
+      userTokenB: f.userTokenB,
+      tokenAMint: f.mintA,
+      tokenBMint: f.mintB,
+      tokenAProgram: f.programA,
+      tokenBProgram: f.programB,
+    }
+  }
+
+  function swap(f: PoolFixture, amountIn: number, aToB: boolean, deadline: BN | null = null) {
+    return program.methods.swap(new BN(amountIn), new BN(0), aToB, deadline).accountsPartial(swapAccounts(f))
+  }
+
+  function swapExactOut(f: PoolFixture, amountOut: number, maxAmountIn: number, aToB: boolean) {
+    return program.methods
+      .swapExactOut(new BN(amountOut), new BN(maxAmountIn), aToB, null)
+      .accountsPartial(swapAccounts(f))
+  }
+
+  function removeLiquidityAccounts(f: PoolFixture) {
+    return {
+      user: payer.publicKey,
+      pool: f.pool,
+      oracle: f.oracle,
+      lpMint: f.lpMint,
+      tokenAVault: f.vaultA,
+      tokenBVault: f.vaultB,
+      userTokenA: f.userTokenA,
+      userTokenB: f.userTokenB,
+      userLpToken: f.userLpToken,
+      tokenAMint: f.mintA,
+      tokenBMint: f.mintB,
+      tokenAProgram: f.programA,
+      tokenBProgram: f.programB,
+      tokenProgram: TOKEN_PROGRAM_ID,
+    }
+  }
+
+  function removeLiquidity(f: PoolFixture, lpTokens: number) {
+    return program.methods
+      .removeLiquidity(new BN(lpTokens), new BN(0), new BN(0), null)
+      .accountsPartial(removeLiquidityAccounts(f))
+  }
+
+  function zapIn(f: PoolFixture, amountIn: number, aSide: boolean) {
+    return program.methods
+      .zapIn(new BN(amountIn), aSide, new BN(0), null)
+      .accountsPartial(addLiquidityAccounts(f))
+  }
+
+  function zapOut(f: PoolFixture, lpTokens: number, toA: boolean) {
+    return program.methods
+      .zapOut(new BN(lpTokens), toA, new BN(0), null)
+      .accountsPartial(removeLiquidityAccounts(f))
+  }
+
+  const pairs: [string, PublicKey, PublicKey][] = [
+    ['SPL / SPL', TOKEN_PROGRAM_ID, TOKEN_PROGRAM_ID],
+    ['Token-2022 / Token-2022', TOKEN_2022_PROGRAM_ID, TOKEN_2022_PROGRAM_ID],
+    ['SPL / Token-2022', TOKEN_PROGRAM_ID, TOKEN_2022_PROGRAM_ID],
+    ['Token-2022 / SPL', TOKEN_2022_PROGRAM_ID, TOKEN_PROGRAM_ID],
+  ]
+
+  for (const [name, programA, programB] of pairs) {
+    describe(`${name} pool`, () => {
+      let f: PoolFixture
+
+      before(async () => {
+        f = await createPool(programA, programB)
+      })
+
+      it('adds liquidity', async () => {
+        await addLiquidity(f, 1_000_000_000, 2_000_000_000).rpc()
+
+        expect(await balance(f.vaultA, programA)).to.equal(1_000_000_000)
+        expect(await balance(f.vaultB, programB)).to.equal(2_000_000_000)
+        expect(await balance(f.userLpToken, TOKEN_PROGRAM_ID)).to.be.greaterThan(0)
+      })
+
+      it('takes only the pool ratio from an off-ratio deposit', async () => {
+        const vaultABefore = await balance(f.vaultA, programA)
+        const vaultBBefore = await balance(f.vaultB, programB)
+        await addLiquidity(f, 1_000_000, 10_000_000).rpc()
+
+        expect((await balance(f.vaultA, programA)) - vaultABefore).to.equal(1_000_000)
+        expect((await balance(f.vaultB, programB)) - vaultBBefore).to.equal(2_000_000)
+      })
+
+      it('swaps in both directions', async () => {
+        const bBefore = await balance(f.userTokenB, programB)
+        await swap(f, 1_000_000, true).rpc()
+        expect(await balance(f.userTokenB, programB)).to.be.greaterThan(bBefore)
+
+        const aBefore = await balance(f.userTokenA, programA)
+        await swap(f, 1_000_000, false).rpc()
+        expect(await balance(f.userTokenA, programA)).to.be.greaterThan(aBefore)
+      })
+
+      it('emits Swapped with the reserves after the swap', async () => {
+        const { swapped } = await emitted(['swapped'], () => swap(f, 1_000_000, true).rpc({ commitment: 'confirmed' }))
+
+        expect(swapped).to.have.lengthOf(1)
+        const [event] = swapped
+        expect(event.pool.equals(f.pool)).to.be.true
+        expect(event.aToB).to.be.true
+        expect(event.amountIn.toNumber()).to.equal(1_000_000)
+        expect(event.reserveA.toNumber()).to.equal(await balance(f.vaultA, programA))
+        expect(event.reserveB.toNumber()).to.equal(await balance(f.vaultB, programB))
+      })
+
+      it('zaps in and out with a single token', async () => {
+        const aBefore = await balance(f.userTokenA, programA)
+        const bBefore = await balance(f.userTokenB, programB)
+        const lpBefore = await balance(f.userLpToken, TOKEN_PROGRAM_ID)
+        await zapIn(f, 1_000_000, true).rpc()
+
+        expect(await balance(f.userTokenA, programA)).to.equal(aBefore - 1_000_000)
+        expect(await balance(f.userTokenB, programB)).to.equal(bBefore)
+        const minted = (await balance(f.userLpToken, TOKEN_PROGRAM_ID)) - lpBefore
+        expect(minted).to.be.greaterThan(0)
+
+        await zapOut(f, minted, false).rpc()
+        expect(await balance(f.userLpToken, TOKEN_PROGRAM_ID)).to.equal(lpBefore)
+        expect(await balance(f.userTokenB, programB)).to.be.greaterThan(bBefore)
+      })
+
+      it('rejects a swap past its deadline', async () => {
+        const expired = new BN(Math.floor(Date.now() / 1000) - 3600)
+        try {
+          await swap(f, 1_000, true, expired).rpc()
+          expect.fail('swap past its deadline succeeded')
+        } catch (err) {
+          expect(String(err)).to.match(/DeadlineExceeded/)
+        }
+
+        const future = new BN(Math.floor(Date.now() / 1000) + 3600)
+        await swap(f, 1_000, true, future).rpc()
+      })
+
+      it('removes liquidity', async () => {
+        const lpTokens = await balance(f.userLpToken, TOKEN_PROGRAM_ID)
+        await removeLiquidity(f, lpTokens).rpc()
+
+        expect(await balance(f.userLpToken, TOKEN_PROGRAM_ID)).to.equal(0)
+      })
+
+      it('rejects a token program that does not own the mint', async () => {
+        const wrongProgram = programA.equals(TOKEN_PROGRAM_ID) ? TOKEN_2022_PROGRAM_ID : TOKEN_PROGRAM_ID
+        try {
+          await swap({ ...f, programA: wrongProgram }, 1_000, true).rpc()
+          expect.fail('swap with the wrong token program succeeded')
+        } catch (err) {
+          expect(String(err)).to.match(/ConstraintMintTokenProgram/)
+        }
+      })
+    })
+  }
+
+  describe('account binding', () => {
+    let f: PoolFixture
+    let other: PoolFixture
+
+    before(async () => {
+      f = await createPool(TOKEN_PROGRAM_ID, TOKEN_PROGRAM_ID)
+      other = await createPool(TOKEN_PROGRAM_ID, TOKEN_PROGRAM_ID)
+      await addLiquidity(f, 1_000_000_000, 1_000_000_000).rpc()
+      await addLiquidity(other, 1_000_000_000, 1_000_000_000).rpc()
+    })
+
+    async function expectRejected(tx: { rpc(): Promise<string> }, error: RegExp) {
+      try {
+        await tx.rpc()
+        expect.fail('transaction with a substituted account succeeded')
+      } catch (err) {
+        expect(String(err)).to.match(error)
+      }
+    }
+
+    it('rejects a vault from another pool', async () => {
+      await expectRejected(swap({ ...f, vaultA: other.vaultA }, 1_000, true), /InvalidPoolState/)
+      await expectRejected(addLiquidity({ ...f, vaultB: other.vaultB }, 1_000, 1_000), /InvalidPoolState/)
+      await expectRejected(removeLiquidity({ ...f, vaultA: other.vaultA }, 1_000), /InvalidPoolState/)
+    })
+
+    it('rejects a mint that is not the pool mint', async () => {
+      const substituted = { ...f, mintA: other.mintA, userTokenA: other.userTokenA }
+      await expectRejected(swap(substituted, 1_000, true), /InvalidTokenMint/)
+      await expectRejected(addLiquidity(substituted, 1_000, 1_000), /InvalidTokenMint/)
+    })
+
+    it('rejects a user account for the wrong mint', async () => {
+      await expectRejected(swap({ ...f, userTokenA: f.userTokenB }, 1_000, true), /InvalidTokenMint/)
+      await expectRejected(removeLiquidity({ ...f, userLpToken: other.userLpToken }, 1_000), /InvalidTokenMint/)
+    })
+
+    it('rejects a user account owned by someone else', async () => {
+      const stranger = Keypair.generate()
+      const strangerTokenB = await createAssociatedTokenAccount(
+        provider.connection,
+        payer,
+        f.mintB,
+        stranger.publicKey,
+        undefined,
+        TOKEN_PROGRAM_ID,
+      )
+      await expectRejected(swap({ ...f, userTokenB: strangerTokenB }, 1_000, true), /Unauthorized/)
+    })
+  })
+
+  describe('transfer hook allowlist', () => {
+    let f: PoolFixture
+
+    before(async () => {
+      f = await createPool(TOKEN_PROGRAM_ID, TOKEN_2022_PROGRAM_ID)
+      await addLiquidity(f, 1_000_000_000, 1_000_000_000).rpc()
+    })
+
+    function setTransferHookPrograms(programs: PublicKey[]) {
+      return program.methods
+        .setTransferHookPrograms(programs)
+        .accountsPartial({ authority: payer.publicKey, pool: f.pool })
+    }
+
+    it('keeps mints without a hook tradable under an allowlist', async () => {
+      const hookProgram = Keypair.generate().publicKey
+      await setTransferHookPrograms([hookProgram]).rpc()
+
+      const pool = await program.account.pool.fetch(f.pool)
+      expect(pool.transferHookPrograms.map((key) => key.toBase58())).to.deep.equal([hookProgram.toBase58()])
+
+      const bBefore = await balance(f.userTokenB, TOKEN_2022_PROGRAM_ID)
+      await swap(f, 1_000_000, true).rpc()
+      expect(await balance(f.userTokenB, TOKEN_2022_PROGRAM_ID)).to.be.greaterThan(bBefore)
+    })
+
+    it('rejects an allowlist longer than the maximum', async () => {
+      const programs = Array.from({ length: 5 }, () => Keypair.generate().publicKey)
+      try {
+        await setTransferHookPrograms(programs).rpc()
+        expect.fail('oversized allowlist was accepted')
+      } catch (err) {
+        expect(String(err)).to.match(/TooManyTransferHookPrograms/)
+      }
+    })
+  })
+
+  describe('weighted pool', () => {
+    let f: PoolFixture
+
+    before(async () => {
+      f = await createPool(TOKEN_PROGRAM_ID, TOKEN_PROGRAM_ID, 8000)
+      await addLiquidity(f, 1_000_000_000, 1_000_000_000).rpc()
+    })
+
+    it('prices token A at four B with 80/20 weights and equal balances', async () => {
+      const bBefore = await balance(f.userTokenB, TOKEN_PROGRAM_ID)
+      await swap(f, 1_000_000, true).rpc()
+
+      // 0.3% fee, then a little price impact
+      const received = (await balance(f.userTokenB, TOKEN_PROGRAM_ID)) - bBefore
+      expect(received).to.be.within(3_960_000, 3_988_000)
+    })
+
+    it('rejects a weight below the minimum', async () => {
+      try {
+        await createPool(TOKEN_PROGRAM_ID, TOKEN_PROGRAM_ID, 50)
+        expect.fail('weight below the minimum was accepted')
+      } catch (err) {
+        expect(String(err)).to.match(/InvalidWeight/)
+      }
+    })
+  })
+
+  describe('route swap', () => {
+    // first trades its mint A for mint B, which second trades for its other mint
+    let first: PoolFixture
+    let second: PoolFixture
+    let secondAToB: boolean
+    let userTokenC: PublicKey
+
+    before(async () => {
+      first = await createPool(TOKEN_PROGRAM_ID, TOKEN_PROGRAM_ID)
+      const [mintC, tokenC] = await createFundedMint(TOKEN_PROGRAM_ID)
+      userTokenC = tokenC
+
+      secondAToB = Buffer.compare(first.mintB.toBuffer(), mintC.toBuffer()) < 0
+      const [mintA, userTokenA, mintB, userTokenB] = secondAToB
+        ? [first.mintB, first.userTokenB, mintC, tokenC]
+        : [mintC, tokenC, first.mintB, first.userTokenB]
+      const keys = await initializePool(mintA, mintB, TOKEN_PROGRAM_ID, TOKEN_PROGRAM_ID)
+      const userLpToken = await createAssociatedTokenAccount(
+        provider.connection,
+        payer,
+        keys.lpMint,
+        payer.publicKey,
+        undefined,
+        TOKEN_PROGRAM_ID,
+      )
+      second = {
+        ...keys,
+        mintA,
+        mintB,
+        programA: TOKEN_PROGRAM_ID,
+        programB: TOKEN_PROGRAM_ID,
+        userTokenA,
+        userTokenB,
+        userLpToken,
+      }
+
+      await addLiquidity(first, 1_000_000_000, 1_000_000_000).rpc()
+      await addLiquidity(second, 1_000_000_000, 1_000_000_000).rpc()
+    })
+
+    function hop(f: PoolFixture, aToB: boolean): AccountMeta[] {
+      const [vaultIn, vaultOut, mintIn, mintOut] = aToB
+        ? [f.vaultA, f.vaultB, f.mintA, f.mintB]
+        : [f.vaultB, f.vaultA, f.mintB, f.mintA]
+      return [
+        { pubkey: f.pool, isSigner: false, isWritable: true },
+        { pubkey: f.oracle, isSigner: false, isWritable: true },
+        { pubkey: vaultIn, isSigner: false, isWritable: true },
+        { pubkey: vaultOut, isSigner: false, isWritable: true },
+        { pubkey: mintIn, isSigner: false, isWritable: false },
+        { pubkey: mintOut, isSigner: false, isWritable: false },
+      ]
+    }
+
+    function routeSwap(
+      userSource: PublicKey,
+      userDestination: PublicKey,
+      hops: AccountMeta[][],
+      amountIn: number,
+      minAmountOut: number,
+    ) {
+      return program.methods
+        .routeSwap(new BN(amountIn), new BN(minAmountOut), hops.length, null)
+        .accountsPartial({
+          user: payer.publicKey,
+          userSource,
+          userDestination,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          token2022Program: TOKEN_2022_PROGRAM_ID,
+        })
+        .remainingAccounts(hops.flat())
+    }
+
+    it('swaps through both pools without paying out the intermediate token', async () => {
+      const sourceBefore = await balance(first.userTokenA, TOKEN_PROGRAM_ID)
+      const intermediateBefore = await balance(first.userTokenB, TOKEN_PROGRAM_ID)
+      const destinationBefore = await balance(userTokenC, TOKEN_PROGRAM_ID)
+
+      await routeSwap(first.userTokenA, userTokenC, [hop(first, true), hop(second, secondAToB)], 1_000_000, 1).rpc()
+
+      expect(await balance(first.userTokenA, TOKEN_PROGRAM_ID)).to.equal(sourceBefore - 1_000_000)
+      expect(await balance(first.userTokenB, TOKEN_PROGRAM_ID)).to.equal(intermediateBefore)
+      expect(await balance(userTokenC, TOKEN_PROGRAM_ID)).to.be.greaterThan(destinationBefore)
+    })
+
+    it('checks min_amount_out against the output of the last hop', async () => {
+      // Each hop pays out a little under 1:1, so two of them fall short of the input
+      try {
+        await routeSwap(
+          first.userTokenA,
+          userTokenC,
+          [hop(first, true), hop(second, secondAToB)],
+          1_000_000,
+          1_000_000,
+        ).rpc()
+        expect.fail('route below min_amount_out was accepted')
+      } catch (err) {
+        expect(String(err)).to.match(/SlippageExceeded/)
+      }
+    })
+
+    it('rejects a route through the same pool twice', async () => {
+      try {
+        await routeSwap(first.userTokenA, first.userTokenB, [hop(first, true), hop(first, false)], 1_000_000, 1).rpc()
+        expect.fail('route repeating a pool was accepted')
+      } catch (err) {
+        expect(String(err)).to.match(/InvalidRoute/)
+      }
+    })
+
+    it('rejects hops whose mints do not chain', async () => {
+      // The second hop starts from mint C instead of the first hop's output
+      try {
+        const broken = [hop(first, true), hop(second, !secondAToB)]
+        await routeSwap(first.userTokenA, first.userTokenB, broken, 1_000_000, 1).rpc()
+        expect.fail('route with a broken mint chain was accepted')
+      } catch (err) {
+        expect(String(err)).to.match(/InvalidRoute/)
+      }
+    })
+  })
+
+  describe('swap exact out', () => {
+    let f: PoolFixture
+
+    before(async () => {
+      f = await createPool(TOKEN_PROGRAM_ID, TOKEN_PROGRAM_ID)
+      await addLiquidity(f, 1_000_000_000, 1_000_000_000).rpc()
+    })
+
+    it('pays out exactly the requested amount', async () => {
+      const userABefore = await balance(f.userTokenA, TOKEN_PROGRAM_ID)
+      const userBBefore = await balance(f.userTokenB, TOKEN_PROGRAM_ID)
+
+      await swapExactOut(f, 1_000_000, 1_100_000, true).rpc()
+
+      expect(await balance(f.userTokenB, TOKEN_PROGRAM_ID)).to.equal(userBBefore + 1_000_000)
+      const paid = userABefore - (await balance(f.userTokenA, TOKEN_PROGRAM_ID))
+      expect(paid).to.be.greaterThan(1_000_000)
+      expect(paid).to.be.at.most(1_100_000)
+    })
+
+    it('rejects a swap that needs more than max_amount_in', async () => {
+      // The fee alone puts the input above the output on a balanced pool
+      const userABefore = await balance(f.userTokenA, TOKEN_PROGRAM_ID)
+      try {
+        await swapExactOut(f, 1_000_000, 1_000_000, true).rpc()
+        expect.fail('swap above max_amount_in was accepted')
+      } catch (err) {
+        expect(String(err)).to.match(/SlippageExceeded/)
+      }
+      expect(await balance(f.userTokenA, TOKEN_PROGRAM_ID)).to.equal(userABefore)
+    })
+  })
+
+  describe('oracle', () => {
+    let f: PoolFixture
+
+    before(async () => {
+      f = await createPool(TOKEN_PROGRAM_ID, TOKEN_PROGRAM_ID)
+      await addLiquidity(f, 1_000_000_000, 1_000_000_000).rpc()
+    })
+
+    function observe(entries: number) {
+      return program.methods
+        .observe(new Array(entries).fill(0))
+        .accountsPartial({ pool: f.pool, oracle: f.oracle, tokenAVault: f.vaultA, tokenBVault: f.vaultB })
+    }
+
+    it('answers as many offsets as fit in return data', async () => {
+      // 25 observations of 40 bytes and their length prefix fill 1004 of 1024 bytes
+      const observations = await observe(25).view()
+      expect(observations).to.have.lengthOf(25)
+    })
+
+    it('rejects one offset more', async () => {
+      try {
+        await observe(26).rpc()
+        expect.fail('observe beyond the return data limit was accepted')
+      } catch (err) {
+        expect(String(err)).to.match(/InvalidAmount/)
+      }
+    })
+  })
+
+  describe('flash loan', () => {
+    let f: PoolFixture
+
+    before(async () => {
+      f = await createPool(TOKEN_PROGRAM_ID, TOKEN_PROGRAM_ID)
+      await addLiquidity(f, 1_000_000_000, 1_000_000_000).rpc()
+    })
+
+    function flashAccounts(borrower: PublicKey, borrowerTokenA: PublicKey, borrowerTokenB: PublicKey) {
+      return {
+        borrower,
+        pool: f.pool,
+        tokenAVault: f.vaultA,
+        tokenBVault: f.vaultB,
+        borrowerTokenA,
+        borrowerTokenB,
+        tokenAMint: f.mintA,
+        tokenBMint: f.mintB,
+        tokenAProgram: f.programA,
+        tokenBProgram: f.programB,
+      }
+    }
+
+    function flashBorrow(
+      amountA: number,
+      amountB: number,
+      borrower = payer.publicKey,
+      tokenA = f.userTokenA,
+      tokenB = f.userTokenB,
+    ) {
+      return program.methods
+        .flashBorrow(new BN(amountA), new BN(amountB))
+        .accountsPartial({ ...flashAccounts(borrower, tokenA, tokenB), instructions: SYSVAR_INSTRUCTIONS_PUBKEY })
+    }
+
+    function flashRepay(borrower = payer.publicKey, tokenA = f.userTokenA, tokenB = f.userTokenB) {
+      return program.methods.flashRepay().accountsPartial(flashAccounts(borrower, tokenA, tokenB)).instruction()
+    }
+
+    it('lends and takes the loan back with its fee in one transaction', async () => {
+      const vaultABefore = await balance(f.vaultA, f.programA)
+      const vaultBBefore = await balance(f.vaultB, f.programB)
+
+      await flashBorrow(1_000_000, 0).postInstructions([await flashRepay()]).rpc()
+
+      // 30 bps on the borrowed amount stays in the vault
+      expect(await balance(f.vaultA, f.programA)).to.equal(vaultABefore + 3_000)
+      expect(await balance(f.vaultB, f.programB)).to.equal(vaultBBefore)
+      const pool = await program.account.pool.fetch(f.pool)
+      expect(pool.flashLoanActive).to.be.false
+    })
+
+    it('emits FlashBorrowed and FlashRepaid with the reserves after each', async () => {
+      const vaultABefore = await balance(f.vaultA, f.programA)
+      const { flashBorrowed, flashRepaid } = await emitted(['flashBorrowed', 'flashRepaid'], async () =>
+        flashBorrow(1_000_000, 0).postInstructions([await flashRepay()]).rpc({ commitment: 'confirmed' }),
+      )
+
+      expect(flashBorrowed).to.have.lengthOf(1)
+      expect(flashRepaid).to.have.lengthOf(1)
+      const [borrowed] = flashBorrowed
+      const [repaid] = flashRepaid
+      expect(borrowed.pool.equals(f.pool)).to.be.true
+      expect(borrowed.amountA.toNumber()).to.equal(1_000_000)
+      expect(borrowed.feeA.toNumber()).to.equal(3_000)
+      expect(borrowed.reserveA.toNumber()).to.equal(vaultABefore - 1_000_000)
+      expect(repaid.amountA.toNumber()).to.equal(1_000_000)
+      expect(repaid.feeA.toNumber()).to.equal(3_000)
+      expect(repaid.reserveA.toNumber()).to.equal(await balance(f.vaultA, f.programA))
+      expect(repaid.reserveB.toNumber()).to.equal(await balance(f.vaultB, f.programB))
+    })
+
+    it('rejects a loan with no repay in the transaction', async () => {
+      try {
+        await flashBorrow(1_000_000, 0).rpc()
+        expect.fail('loan without a repay was accepted')
+      } catch (err) {
+        expect(String(err)).to.match(/FlashLoanNotRepaid/)
+      }
+    })
+
+    it('rejects a repay the borrower cannot cover', async () => {
+      // The borrower holds only what it borrows, which is short of the fee
+      const borrower = Keypair.generate()
+      const [tokenA, tokenB] = await Promise.all(
+        [f.mintA, f.mintB].map((mint) =>
+          createAssociatedTokenAccount(
+            provider.connection,
+            payer,
+            mint,
+            borrower.publicKey,
+            undefined,
+            TOKEN_PROGRAM_ID,
+          ),
+        ),
+      )
+      const vaultABefore = await balance(f.vaultA, f.programA)
+
+      try {
+        await flashBorrow(1_000_000, 0, borrower.publicKey, tokenA, tokenB)
+          .postInstructions([await flashRepay(borrower.publicKey, tokenA, tokenB)])
+          .signers([borrower])
+          .rpc()
+        expect.fail('underpaid loan was accepted')
+      } catch (err) {
+        expect(String(err)).to.match(/insufficient funds|custom program error: 0x1\b/)
+      }
+      expect(await balance(f.vaultA, f.programA)).to.equal(vaultABefore)
+    })
+
+    it('rejects a swap against the pool while the loan is out', async () => {
+      try {
+        await flashBorrow(1_000_000, 0)
+          .postInstructions([await swap(f, 1_000, false).instruction(), await flashRepay()])
+          .rpc()
+        expect.fail('swap during a flash loan was accepted')
+      } catch (err) {
+        expect(String(err)).to.match(/FlashLoanActive/)
+      }
+    })
+  })
+
+  describe('pool administration', () => {
+    let f: PoolFixture
+
+    before(async () => {
+      f = await createPool(TOKEN_PROGRAM_ID, TOKEN_PROGRAM_ID)
+      await addLiquidity(f, 1_000_000_000, 1_000_000_000).rpc()
+    })
+
+    function setPaused(paused: boolean, authority = payer) {
+      return program.methods
+        .setPaused(paused)
+        .accountsPartial({ authority: authority.publicKey, pool: f.pool })
+        .signers(authority === payer ? [] : [authority])
+    }
+
+    it('halts swaps and deposits but keeps withdrawals open while paused', async () => {
+      await setPaused(true).rpc()
+
+      for (const tx of [swap(f, 1_000, true), addLiquidity(f, 1_000, 1_000)]) {
+        try {
+          await tx.rpc()
+          expect.fail('instruction on a paused pool was accepted')
+        } catch (err) {
+          expect(String(err)).to.match(/PoolPaused/)
+        }
+      }
+
+      const lpBefore = await balance(f.userLpToken, TOKEN_PROGRAM_ID)
+      await removeLiquidity(f, 1_000).rpc()
+      expect(await balance(f.userLpToken, TOKEN_PROGRAM_ID)).to.equal(lpBefore - 1_000)
+
+      await setPaused(false).rpc()
+      await swap(f, 1_000, true).rpc()
+    })
+
+    it('hands the pool to a proposed authority only once it accepts', async () => {
+      const newAuthority = Keypair.generate()
+      const stranger = Keypair.generate()
+      await program.methods
+        .proposeAuthority(newAuthority.publicKey)
+        .accountsPartial({ authority: payer.publicKey, pool: f.pool })
+        .rpc()
+
+      // Proposing alone changes nothing
+      let pool = await program.account.pool.fetch(f.pool)
+      expect(pool.authority.toBase58()).to.equal(payer.publicKey.toBase58())
+      expect(pool.pendingAuthority?.toBase58()).to.equal(newAuthority.publicKey.toBase58())
+
+      try {
+        await program.methods
+          .acceptAuthority()
+          .accountsPartial({ newAuthority: stranger.publicKey, pool: f.pool })
+          .signers([stranger])
+          .rpc()
+        expect.fail('stranger accepted the authority')
+      } catch (err) {
+        expect(String(err)).to.match(/Unauthorized/)
+      }
+
+      await program.methods
+        .acceptAuthority()
+        .accountsPartial({ newAuthority: newAuthority.publicKey, pool: f.pool })
+        .signers([newAuthority])
+        .rpc()
+
+      pool = await program.account.pool.fetch(f.pool)
+      expect(pool.authority.toBase58()).to.equal(newAuthority.publicKey.toBase58())
+      expect(pool.pendingAuthority).to.be.null
+
+      try {
+        await setPaused(true).rpc()
+        expect.fail('former authority paused the pool')
+      } catch (err) {
+        expect(String(err)).to.match(/Unauthorized/)
+      }
+      await setPaused(true, newAuthority).rpc()
+      await setPaused(false, newAuthority).rpc()
+    })
+  })
+
+  describe('amm config', () => {
+    it('registers every new pool', async () => {
+      const { poolCount } = await program.account.ammConfig.fetch(ammConfig)
+      const f = await createPool(TOKEN_PROGRAM_ID, TOKEN_PROGRAM_ID, null, 5)
+
+      const [poolRecord] = PublicKey.findProgramAddressSync(
+        [Buffer.from('pool_record'), poolCount.toArrayLike(Buffer, 'le', 8)],
+        program.programId,
+      )
+      const record = await program.account.poolRecord.fetch(poolRecord)
+      expect(record.pool.toBase58()).to.equal(f.pool.toBase58())
+      expect(record.index.toNumber()).to.equal(poolCount.toNumber())
+
+      const config = await program.account.ammConfig.fetch(ammConfig)
+      expect(config.poolCount.toNumber()).to.equal(poolCount.toNumber() + 1)
+    })
+
+    it('keeps one pool per fee tier for the same pair', async () => {
+      const f = await createPool(TOKEN_PROGRAM_ID, TOKEN_PROGRAM_ID, null, 30)
+      const { pool } = await initializePool(f.mintA, f.mintB, f.programA, f.programB, null, 100)
+
+      expect(pool.toBase58()).to.not.equal(f.pool.toBase58())
+      const account = await program.account.pool.fetch(pool)
+      expect(account.feeTier).to.equal(100)
+    })
+
+    it('rejects mints out of order', async () => {
+      const f = await createPool(TOKEN_PROGRAM_ID, TOKEN_PROGRAM_ID)
+      try {
+        await initializePool(f.mintB, f.mintA, f.programB, f.programA, null, 5)
+        expect.fail('mints out of order were accepted')
+      } catch (err) {
+        expect(String(err)).to.match(/UnsortedMints/)
+      }
+    })
+
+    it('rejects identical mints', async () => {
+      const f = await createPool(TOKEN_PROGRAM_ID, TOKEN_PROGRAM_ID)
+      try {
+        await initializePool(f.mintA, f.mintA, f.programA, f.programA, null, 5)
+        expect.fail('identical mints were accepted')
+      } catch (err) {
+        expect(String(err)).to.match(/IdenticalMints/)
+      }
+    })
+
+    it('rejects a fee rate outside the tiers', async () => {
+      try {
+        await createPool(TOKEN_PROGRAM_ID, TOKEN_PROGRAM_ID, null, 25)
+        expect.fail('fee rate outside the tiers was accepted')
+      } catch (err) {
+        expect(String(err)).to.match(/FeeTierNotAllowed/)
+      }
+    })
+
+    it('keeps the fee rate short of the neighbouring tiers', async () => {
+      const f = await createPool(TOKEN_PROGRAM_ID, TOKEN_PROGRAM_ID, null, 30)
+      const updateFeeRate = (feeRate: number) =>
+        program.methods.updateFeeRate(feeRate).accountsPartial({ authority: payer.publicKey, ammConfig, pool: f.pool })
+
+      await updateFeeRate(50).rpc()
+      const pool = await program.account.pool.fetch(f.pool)
+      expect(pool.feeRate).to.equal(50)
+      expect(pool.feeTier).to.equal(30)
+
+      // The 5 and 100 bps tiers have their own pool addresses
+      for (const feeRate of [5, 100]) {
+        try {
+          await updateFeeRate(feeRate).rpc()
+          expect.fail("fee rate of another tier's pool was accepted")
+        } catch (err) {
+          expect(String(err)).to.match(/FeeRateOutsideTier/)
+        }
+      }
+    })
+
+    it('only lets the admin change the tiers', async () => {
+      const stranger = Keypair.generate()
+      try {
+        await program.methods
+          .setFeeTiers([10])
+          .accountsPartial({ admin: stranger.publicKey, ammConfig })
+          .signers([stranger])
+          .rpc()
+        expect.fail('stranger changed the fee tiers')
+      } catch (err) {
+        expect(String(err)).to.match(/Unauthorized/)
+      }
+    })
+  })
+
+  describe('dynamic fee', () => {
+    let f: PoolFixture
+
+    before(async () => {
+      f = await createPool(TOKEN_PROGRAM_ID, TOKEN_PROGRAM_ID)
+      await addLiquidity(f, 1_000_000_000, 1_000_000_000).rpc()
+    })
+
+    function setDynamicFee(dynamicFee: object | null) {
+      return program.methods
+        .setDynamicFee(dynamicFee as any)
+        .accountsPartial({ authority: payer.publicKey, ammConfig, pool: f.pool })
+    }
+
+    it('raises the fee after a large swap', async () => {
+      // The widest range a 30 bps pool may span between the 5 and 100 bps tiers
+      await setDynamicFee({
+        minFeeRate: 6,
+        maxFeeRate: 99,
+        volatilityMultiplier: 10_000,
+        decayPeriod: 3_600,
+      }).rpc()
+
+      const events: any[] = []
+      const listener = program.addEventListener('swapped', (event) => events.push(event))
+      try {
+        await swap(f, 1_000_000, true).rpc({ commitment: 'confirmed' })
+        await swap(f, 100_000_000, true).rpc({ commitment: 'confirmed' })
+        await swap(f, 1_000_000, true).rpc({ commitment: 'confirmed' })
+        await new Promise((resolve) => setTimeout(resolve, 1_000))
+      } finally {
+        await program.removeEventListener(listener)
+      }
+
+      expect(events).to.have.lengthOf(3)
+      expect(events[0].fee.toNumber()).to.equal(600)
+      expect(events[2].fee.toNumber()).to.be.greaterThan(events[0].fee.toNumber())
+
+      const pool = await program.account.pool.fetch(f.pool)
+      expect(pool.volatility.toNumber()).to.be.greaterThan(0)
+    })
+
+    it('rejects a minimum above the maximum', async () => {
+      try {
+        await setDynamicFee({ minFeeRate: 60, maxFeeRate: 50, volatilityMultiplier: 0, decayPeriod: 60 }).rpc()
+        expect.fail('inverted fee bounds were accepted')
+      } catch (err) {
+        expect(String(err)).to.match(/InvalidDynamicFee/)
+      }
+    })
+
+    it('rejects a maximum at another tier', async () => {
+      try {
+        await setDynamicFee({ minFeeRate: 30, maxFeeRate: 100, volatilityMultiplier: 0, decayPeriod: 60 }).rpc()
+        expect.fail('maximum at the 100 bps tier was accepted')
+      } catch (err) {
+        expect(String(err)).to.match(/FeeRateOutsideTier/)
+      }
+    })
+
+    it('returns to the fixed fee', async () => {
+      await setDynamicFee(null).rpc()
+
+      const pool = await program.account.pool.fetch(f.pool)
+      expect(pool.dynamicFee).to.be.null
+      expect(pool.volatility.toNumber()).to.equal(0)
+    })
+  })
+
+  describe('concentrated liquidity', () => {
+    const tickSpacing = 8
+    let clPool: PublicKey
+
+    before(async () => {
+      // Pools only accept their mints in ascending order
+      const [keypairA, keypairB] = [Keypair.generate(), Keypair.generate()].sort((x, y) =>
+        Buffer.compare(x.publicKey.toBuffer(), y.publicKey.toBuffer()),
+      )
+      const [mintA] = await createFundedMint(TOKEN_PROGRAM_ID, keypairA)
+      const [mintB] = await createFundedMint(TOKEN_PROGRAM_ID, keypairB)
+
+      const feeTier = Buffer.alloc(2)
+      feeTier.writeUInt16LE(30)
+      ;[clPool] = PublicKey.findProgramAddressSync(
+        [Buffer.from('cl_pool'), mintA.toBuffer(), mintB.toBuffer(), feeTier],
+        program.programId,
+      )
+      const { poolCount } = await program.account.ammConfig.fetch(ammConfig)
+      const [poolRecord] = PublicKey.findProgramAddressSync(
+        [Buffer.from('pool_record'), poolCount.toArrayLike(Buffer, 'le', 8)],
+        program.programId,
+      )
+
+      await program.methods
+        .initializeClPool(tickSpacing, 30, new BN(1).shln(64))
+        .accountsPartial({
+          authority: payer.publicKey,
+          ammConfig,
+          poolRecord,
+          feeReceiver,
+          clPool,
+          tokenAMint: mintA,
+          tokenBMint: mintB,
+          tokenAVault: getAssociatedTokenAddressSync(mintA, clPool, true, TOKEN_PROGRAM_ID),
+          tokenBVault: getAssociatedTokenAddressSync(mintB, clPool, true, TOKEN_PROGRAM_ID),
+          tokenAProgram: TOKEN_PROGRAM_ID,
+          tokenBProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc()
+    })
+
+    it('emits PositionOpened and PositionClosed', async () => {
+      const [tickLower, tickUpper] = [-8 * tickSpacing, 8 * tickSpacing]
+      const ticks = Buffer.alloc(8)
+      ticks.writeInt32LE(tickLower, 0)
+      ticks.writeInt32LE(tickUpper, 4)
+      const [position] = PublicKey.findProgramAddressSync(
+        [
+          Buffer.from('position'),
+          clPool.toBuffer(),
+          payer.publicKey.toBuffer(),
+          ticks.subarray(0, 4),
+          ticks.subarray(4),
+        ],
+        program.programId,
+      )
+
+      const { positionOpened, positionClosed } = await emitted(['positionOpened', 'positionClosed'], async () => {
+        await program.methods
+          .openPosition(tickLower, tickUpper)
+          .accountsPartial({ owner: payer.publicKey, clPool, position, systemProgram: SystemProgram.programId })
+          .rpc({ commitment: 'confirmed' })
+        await program.methods
+          .closePosition()
+          .accountsPartial({ owner: payer.publicKey, position })
+          .rpc({ commitment: 'confirmed' })
+      })
+
+      expect(positionOpened).to.have.lengthOf(1)
+      expect(positionOpened[0].position.equals(position)).to.be.true
+      expect(positionOpened[0].pool.equals(clPool)).to.be.true
+      expect(positionOpened[0].tickLower).to.equal(tickLower)
+      expect(positionOpened[0].tickUpper).to.equal(tickUpper)
+      expect(positionClosed).to.have.lengthOf(1)
+      expect(positionClosed[0].position.equals(position)).to.be.true
+    })
+  })
+
+  describe('farm', () => {
+    let f: PoolFixture
+    let rewardMint: PublicKey
+    let userRewardToken: PublicKey
+    let farm: PublicKey
+    let position: PublicKey
+    let stakeVault: PublicKey
+    let rewardVault: PublicKey
+
+    before(async () => {
+      f = await createPool(TOKEN_PROGRAM_ID, TOKEN_PROGRAM_ID)
+      await addLiquidity(f, 1_000_000_000, 1_000_000_000).rpc()
+      ;[rewardMint, userRewardToken] = await createFundedMint(TOKEN_PROGRAM_ID)
+
+      ;[farm] = PublicKey.findProgramAddressSync(
+        [Buffer.from('farm'), f.pool.toBuffer(), rewardMint.toBuffer()],
+        program.programId,
+      )
+      ;[position] = PublicKey.findProgramAddressSync(
+        [Buffer.from('farm_position'), farm.toBuffer(), payer.publicKey.toBuffer()],
+        program.programId,
+      )
+      stakeVault = getAssociatedTokenAddressSync(f.lpMint, farm, true, TOKEN_PROGRAM_ID)
+      rewardVault = getAssociatedTokenAddressSync(rewardMint, farm, true, TOKEN_PROGRAM_ID)
+
+      await program.methods
+        .initializeFarm(new BN(1_000))
+        .accountsPartial({
+          authority: payer.publicKey,
+          pool: f.pool,
+          lpMint: f.lpMint,
+          rewardMint,
+          farm,
+          stakeVault,
+          rewardVault,
+          lpTokenProgram: TOKEN_PROGRAM_ID,
+          rewardTokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc()
+      await program.methods
+        .fundFarm(new BN(1_000_000_000))
+        .accountsPartial({
+          funder: payer.publicKey,
+          farm,
+          rewardMint,
+          rewardVault,
+          funderRewardToken: userRewardToken,
+          rewardTokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .rpc()
+      await program.methods
+        .openFarmPosition()
+        .accountsPartial({ owner: payer.publicKey, farm, position, systemProgram: SystemProgram.programId })
+        .rpc()
+    })
+
+    function stakeAccounts() {
+      return {
+        owner: payer.publicKey,
+        farm,
+        position,
+        lpMint: f.lpMint,
+        stakeVault,
+        userLpToken: f.userLpToken,
+        lpTokenProgram: TOKEN_PROGRAM_ID,
+      }
+    }
+
+    it('pays rewards to stakers and returns their LP tokens', async () => {
+      const lpTokens = await balance(f.userLpToken, TOKEN_PROGRAM_ID)
+      await program.methods.stake(new BN(lpTokens)).accountsPartial(stakeAccounts()).rpc()
+      expect(await balance(stakeVault, TOKEN_PROGRAM_ID)).to.equal(lpTokens)
+
+      await new Promise((resolve) => setTimeout(resolve, 2_000))
+
+      const rewardsBefore = await balance(userRewardToken, TOKEN_PROGRAM_ID)
+      await program.methods
+        .claimFarmRewards()
+        .accountsPartial({
+          owner: payer.publicKey,
+          farm,
+          position,
+          rewardMint,
+          rewardVault,
+          userRewardToken,
+          rewardTokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .rpc()
+      expect(await balance(userRewardToken, TOKEN_PROGRAM_ID)).to.be.greaterThan(rewardsBefore)
+
+      await program.methods.unstake(new BN(lpTokens)).accountsPartial(stakeAccounts()).rpc()
+      expect(await balance(f.userLpToken, TOKEN_PROGRAM_ID)).to.equal(lpTokens)
+    })
+
+    it('emits FarmPositionOpened and FarmPositionClosed', async () => {
+      const owner = Keypair.generate()
+      await provider.connection.confirmTransaction(
+        await provider.connection.requestAirdrop(owner.publicKey, 1_000_000_000),
+        'confirmed',
+      )
+      const [ownerPosition] = PublicKey.findProgramAddressSync(
+        [Buffer.from('farm_position'), farm.toBuffer(), owner.publicKey.toBuffer()],
+        program.programId,
+      )
+
+      const { farmPositionOpened, farmPositionClosed } = await emitted(
+        ['farmPositionOpened', 'farmPositionClosed'],
+        async () => {
+          await program.methods
+            .openFarmPosition()
+            .accountsPartial({
+              owner: owner.publicKey,
+              farm,
+              position: ownerPosition,
+              systemProgram: SystemProgram.programId,
+            })
+            .signers([owner])
+            .rpc({ commitment: 'confirmed' })
+          await program.methods
+            .closeFarmPosition()
+            .accountsPartial({ owner: owner.publicKey, position: ownerPosition })
+            .signers([owner])
+            .rpc({ commitment: 'confirmed' })
+        },
+      )
+
+      expect(farmPositionOpened).to.have.lengthOf(1)
+      expect(farmPositionOpened[0].farm.equals(farm)).to.be.true
+      expect(farmPositionOpened[0].owner.equals(owner.publicKey)).to.be.true
+      expect(farmPositionClosed).to.have.lengthOf(1)
+      expect(farmPositionClosed[0].position.equals(ownerPosition)).to.be.true
+    })
+
+    it('rejects unstaking more than is staked', async () => {
+      try {
+        await program.methods.unstake(new BN(1)).accountsPartial(stakeAccounts()).rpc()
+        expect.fail('unstaking an empty position succeeded')
+      } catch (err) {
+        expect(String(err)).to.match(/InsufficientStake/)
+      }
+    })
+  })
+
+  describe('multi-asset pool', () => {
+    const programs = [TOKEN_PROGRAM_ID, TOKEN_2022_PROGRAM_ID, TOKEN_PROGRAM_ID]
+    let tokens: { mint: PublicKey; program: PublicKey; vault: PublicKey; userToken: PublicKey }[]
+    let pool: PublicKey
+    let lpMint: PublicKey
+    let lockedLpVault: PublicKey
+    let userLpToken: PublicKey
+    let poolRecord: PublicKey
+
+    before(async () => {
+      const funded = await Promise.all(programs.map((tokenProgram) => createFundedMint(tokenProgram)))
+      const sorted = funded
+        .map(([mint, userToken], i) => ({ mint, userToken, program: programs[i] }))
+        .sort((a, b) => a.mint.toBuffer().compare(b.mint.toBuffer()))
+      const mints = sorted.map((token) => token.mint)
+
+      const mintsHash = createHash('sha256')
+        .update(Buffer.concat(mints.map((mint) => mint.toBuffer())))
+        .digest()
+      ;[pool] = PublicKey.findProgramAddressSync([Buffer.from('multi_pool'), mintsHash], program.programId)
+      ;[lpMint] = PublicKey.findProgramAddressSync([Buffer.from('lp_mint'), pool.toBuffer()], program.programId)
+      lockedLpVault = getAssociatedTokenAddressSync(lpMint, pool, true, TOKEN_PROGRAM_ID)
+      tokens = sorted.map((token) => ({
+        ...token,
+        vault: getAssociatedTokenAddressSync(token.mint, pool, true, token.program),
+      }))
+
+      const { poolCount } = await program.account.ammConfig.fetch(ammConfig)
+      ;[poolRecord] = PublicKey.findProgramAddressSync(
+        [Buffer.from('pool_record'), poolCount.toArrayLike(Buffer, 'le', 8)],
+        program.programId,
+      )
+
+      await program.methods
+        .initializeMultiPool(30, mints)
+        .accountsPartial({
+          authority: payer.publicKey,
+          ammConfig,
+          poolRecord,
+          feeReceiver,
+          pool,
+          lpMint,
+          lockedLpVault,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          token2022Program: TOKEN_2022_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .remainingAccounts(
+          tokens.flatMap((token) => [
+            { pubkey: token.mint, isSigner: false, isWritable: false },
+            { pubkey: token.vault, isSigner: false, isWritable: true },
+          ]),
+        )
+        .rpc()
+
+      userLpToken = await createAssociatedTokenAccount(
+        provider.connection,
+        payer,
+        lpMint,
+        payer.publicKey,
+        undefined,
+        TOKEN_PROGRAM_ID,
+      )
+    })
+
+    function constituents(selected = tokens) {
+      return selected.flatMap((token) => [
+        { pubkey: token.vault, isSigner: false, isWritable: true },
+        { pubkey: token.mint, isSigner: false, isWritable: false },
+        { pubkey: token.userToken, isSigner: false, isWritable: true },
+      ])
+    }
+
+    function liquidityAccounts() {
+      return {
+        user: payer.publicKey,
+        pool,
+        lpMint,
+        userLpToken,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        token2022Program: TOKEN_2022_PROGRAM_ID,
+      }
+    }
+
+    it('takes the next registry entry', async () => {
+      const record = await program.account.poolRecord.fetch(poolRecord)
+      expect(record.pool.toBase58()).to.equal(pool.toBase58())
+    })
+
+    it('mints the geometric mean on the first deposit', async () => {
+      const amounts = [1_000_000_000, 8_000_000_000, 27_000_000_000]
+      await program.methods
+        .addMultiLiquidity(amounts.map((amount) => new BN(amount)), new BN(0), null)
+        .accountsPartial({ ...liquidityAccounts(), lockedLpVault })
+        .remainingAccounts(constituents())
+        .rpc()
+
+      // (1e9 · 8e9 · 27e9)^(1/3) = 6e9, less rounding and the locked minimum
+      const lpTokens = await balance(userLpToken, TOKEN_PROGRAM_ID)
+      expect(lpTokens).to.be.within(5_999_990_000, 5_999_999_000)
+      for (const [i, token] of tokens.entries()) {
+        expect(await balance(token.vault, token.program)).to.equal(amounts[i])
+      }
+    })
+
+    it('swaps between two of its tokens', async () => {
+      const [tokenIn, , tokenOut] = tokens
+      const before = await balance(tokenOut.userToken, tokenOut.program)
+      await program.methods
+        .multiSwap(new BN(1_000_000), new BN(0), null)
+        .accountsPartial({
+          user: payer.publicKey,
+          pool,
+          vaultIn: tokenIn.vault,
+          vaultOut: tokenOut.vault,
+          userSource: tokenIn.userToken,
+          userDestination: tokenOut.userToken,
+          mintIn: tokenIn.mint,
+          mintOut: tokenOut.mint,
+          tokenInProgram: tokenIn.program,
+          tokenOutProgram: tokenOut.program,
+        })
+        .rpc()
+
+      expect(await balance(tokenOut.userToken, tokenOut.program)).to.be.greaterThan(before)
+    })
+
+    it('deposits and withdraws a single token', async () => {
+      const token = tokens[1]
+      const lpBefore = await balance(userLpToken, TOKEN_PROGRAM_ID)
+      await program.methods
+        .multiZapIn(new BN(100_000_000), new BN(0), null)
+        .accountsPartial({ ...liquidityAccounts(), lockedLpVault })
+        .remainingAccounts(constituents([token]))
+        .rpc()
+      const minted = (await balance(userLpToken, TOKEN_PROGRAM_ID)) - lpBefore
+      expect(minted).to.be.greaterThan(0)
+
+      const tokenBefore = await balance(token.userToken, token.program)
+      await program.methods
+        .multiZapOut(new BN(minted), new BN(0), null)
+        .accountsPartial(liquidityAccounts())
+        .remainingAccounts(constituents([token]))
+        .rpc()
+
+      // Both legs pay the fee, so the round trip returns a little less
+      const returned = (await balance(token.userToken, token.program)) - tokenBefore
+      expect(returned).to.be.within(99_000_000, 100_000_000)
+    })
+
+    it('rejects constituents out of pool order', async () => {
+      try {
+        await program.methods
+          .removeMultiLiquidity(new BN(1_000), [new BN(0), new BN(0), new BN(0)], null)
+          .accountsPartial(liquidityAccounts())
+          .remainingAccounts(constituents([tokens[1], tokens[0], tokens[2]]))
+          .rpc()
+        expect.fail('constituents out of order were accepted')
+      } catch (err) {
+        expect(String(err)).to.match(/InvalidTokenMint/)
+      }
+    })
+
+    it('withdraws every token in proportion', async () => {
+      const lpTokens = await balance(userLpToken, TOKEN_PROGRAM_ID)
+      await program.methods
+        .removeMultiLiquidity(new BN(lpTokens), tokens.map(() => new BN(1)), null)
+        .accountsPartial(liquidityAccounts())
+        .remainingAccounts(constituents())
+        .rpc()
+
+      expect(await balance(userLpToken, TOKEN_PROGRAM_ID)).to.equal(0)
+      for (const token of tokens) {
+        expect(await balance(token.vault, token.program)).to.be.greaterThan(0)
+      }
+    })
+  })
+})